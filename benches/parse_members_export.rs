@@ -0,0 +1,103 @@
+//! Parsing of large members exports, e.g. the one of a federation with thousands of members.
+//! Run with `cargo bench --bench parse_members_export`.
+//! Reading the worksheet XML takes most of the time, whatever is done with the rows afterward.
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::io::{Cursor, Read, Write};
+use uda_connector::retrieve_members::{parse_imported_members, parse_members_export};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+/// Export whose layout the synthetic ones copy, their worksheet aside.
+const TEMPLATE: &str = "test/resources/uda_members.xlsx";
+const WORKSHEET: &str = "xl/worksheets/sheet1.xml";
+const MEMBER_COUNTS: [usize; 2] = [2_000, 20_000];
+
+/// Export of the template layout, listing `member_count` synthetic members.
+/// Like in real exports, the ones above the max competitor id are read, then filtered out.
+fn synthetic_export(member_count: usize) -> Vec<u8> {
+    let mut template = ZipArchive::new(Cursor::new(std::fs::read(TEMPLATE).unwrap())).unwrap();
+    let mut worksheet = String::new();
+    template
+        .by_name(WORKSHEET)
+        .unwrap()
+        .read_to_string(&mut worksheet)
+        .unwrap();
+
+    // The header row is kept, the template members are replaced
+    let header_end = worksheet.find("</row>").unwrap() + "</row>".len();
+    let rows_end = worksheet.find("</sheetData>").unwrap();
+    let mut synthetic_worksheet = worksheet[..header_end].replace(
+        r#"<dimension ref="A1:O5"/>"#,
+        &format!(r#"<dimension ref="A1:O{}"/>"#, member_count + 1),
+    );
+    for id in 1..=member_count {
+        synthetic_worksheet.push_str(&member_row(id));
+    }
+    synthetic_worksheet.push_str(&worksheet[rows_end..]);
+
+    let mut export = ZipWriter::new(Cursor::new(vec![]));
+    for index in 0..template.len() {
+        let file = template.by_index_raw(index).unwrap();
+        if file.name() == WORKSHEET {
+            drop(file);
+            export
+                .start_file(WORKSHEET, SimpleFileOptions::default())
+                .unwrap();
+            export.write_all(synthetic_worksheet.as_bytes()).unwrap();
+        } else {
+            export.raw_copy_file(file).unwrap();
+        }
+    }
+    export.finish().unwrap().into_inner()
+}
+
+fn member_row(id: usize) -> String {
+    let row = id + 1;
+    let text = |column: &str, value: &str| {
+        format!(r#"<c r="{column}{row}" t="inlineStr"><is><t>{value}</t></is></c>"#)
+    };
+    [
+        format!(r#"<c r="A{row}"><v>{id}</v></c>"#),
+        text("B", &format!("{:06}", id)),
+        text("D", &format!("First{id}")),
+        text("E", &format!("Last{id}")),
+        text("F", "01.02.1983"),
+        text("G", "42, Le Village"),
+        text("H", "Cartuin"),
+        text("I", "Creuse"),
+        text("J", "23340"),
+        text("K", "France"),
+        text("L", "0123456789"),
+        text("M", &format!("member{id}@email.com")),
+        text("N", "Le club de test"),
+        format!(r#"<c r="O{row}" t="b"><v>{}</v></c>"#, id % 2),
+    ]
+    .into_iter()
+    .fold(format!(r#"<row r="{row}" spans="1:15">"#), |row, cell| {
+        row + &cell
+    }) + "</row>"
+}
+
+fn parse_members(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_members_export");
+    group.sample_size(10);
+    for member_count in MEMBER_COUNTS {
+        let export = synthetic_export(member_count);
+
+        group.throughput(Throughput::Elements(member_count as u64));
+        group.bench_with_input(
+            BenchmarkId::new("members", member_count),
+            &export,
+            |b, export| b.iter(|| parse_members_export(export).unwrap()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("imported_members", member_count),
+            &export,
+            |b, export| b.iter(|| parse_imported_members(export).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse_members);
+criterion_main!(benches);
@@ -0,0 +1,178 @@
+use crate::uda_registrant::UdaRegistrant;
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+
+/// Bucket counting registrants whose birthday is missing or malformed.
+pub const UNKNOWN_AGE_GROUP: &str = "unknown";
+
+/// Age groups registrants are split into, defined by the oldest age of each group but the last one.
+/// Defaults to the usual unicycling groups: 0-10, 11-14, 15-18, 19-29 and 30+.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgeGroups {
+    upper_bounds: Vec<u32>,
+}
+
+impl AgeGroups {
+    pub fn new(mut upper_bounds: Vec<u32>) -> Self {
+        upper_bounds.sort_unstable();
+        upper_bounds.dedup();
+        Self { upper_bounds }
+    }
+
+    /// Label of the group the age falls into, e.g. `11-14` or `30+`.
+    pub fn label(&self, age: u32) -> String {
+        let mut lower_bound = 0;
+        for &upper_bound in &self.upper_bounds {
+            if age <= upper_bound {
+                return format!("{lower_bound}-{upper_bound}");
+            }
+            lower_bound = upper_bound + 1;
+        }
+        format!("{lower_bound}+")
+    }
+}
+
+impl Default for AgeGroups {
+    fn default() -> Self {
+        Self::new(vec![10, 14, 18, 29])
+    }
+}
+
+/// Count registrants per default age group and gender, e.g. `15-18 Female`,
+/// their age being computed at the given date.
+/// Registrants without a valid birthday are counted in the `unknown` group.
+pub fn age_group_breakdown(
+    registrants: &[UdaRegistrant],
+    as_of: NaiveDate,
+) -> BTreeMap<String, usize> {
+    age_group_breakdown_with_groups(registrants, as_of, &AgeGroups::default())
+}
+
+/// Count registrants per age group and gender, like [`age_group_breakdown`], with custom age groups.
+pub fn age_group_breakdown_with_groups(
+    registrants: &[UdaRegistrant],
+    as_of: NaiveDate,
+    age_groups: &AgeGroups,
+) -> BTreeMap<String, usize> {
+    let mut breakdown = BTreeMap::new();
+    for registrant in registrants {
+        let age_group = registrant
+            .birthdate()
+            .and_then(|birthdate| as_of.years_since(birthdate))
+            .map(|age| age_groups.label(age))
+            .unwrap_or_else(|| UNKNOWN_AGE_GROUP.to_owned());
+        *breakdown
+            .entry(format!("{age_group} {}", registrant.gender()))
+            .or_insert(0) += 1;
+    }
+    breakdown
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod label {
+        use crate::age_groups::AgeGroups;
+
+        #[test]
+        fn use_default_groups() {
+            let age_groups = AgeGroups::default();
+
+            assert_eq!("0-10", age_groups.label(0));
+            assert_eq!("0-10", age_groups.label(10));
+            assert_eq!("11-14", age_groups.label(11));
+            assert_eq!("19-29", age_groups.label(29));
+            assert_eq!("30+", age_groups.label(30));
+        }
+
+        #[test]
+        fn use_custom_groups() {
+            let age_groups = AgeGroups::new(vec![40, 17]);
+
+            assert_eq!("0-17", age_groups.label(17));
+            assert_eq!("18-40", age_groups.label(18));
+            assert_eq!("41+", age_groups.label(41));
+        }
+    }
+
+    mod age_group_breakdown {
+        use crate::age_groups::{age_group_breakdown, age_group_breakdown_with_groups, AgeGroups};
+        use crate::gender::Gender;
+        use crate::ids::MemberId;
+        use crate::uda_registrant::UdaRegistrant;
+        use chrono::NaiveDate;
+        use std::collections::BTreeMap;
+
+        fn registrant(id: u16, gender: Gender, birthday: Option<&str>) -> UdaRegistrant {
+            let registrant = UdaRegistrant::new(
+                MemberId(id),
+                "Jon".to_owned(),
+                "Doe".to_owned(),
+                0,
+                gender,
+                vec![],
+            );
+            match birthday {
+                Some(birthday) => registrant.with_birthday(birthday.to_owned()),
+                None => registrant,
+            }
+        }
+
+        fn as_of() -> NaiveDate {
+            NaiveDate::from_ymd_opt(2025, 7, 19).unwrap()
+        }
+
+        #[test]
+        fn count_per_age_group_and_gender() {
+            let registrants = vec![
+                // Turns 11 on the reference date
+                registrant(1, Gender::Male, Some("19.07.2014")),
+                // Turns 11 the day after
+                registrant(2, Gender::Male, Some("20.07.2014")),
+                registrant(3, Gender::Female, Some("20.07.2006")),
+                registrant(4, Gender::Female, Some("19.07.2006")),
+                registrant(5, Gender::Female, Some("01.01.1970")),
+            ];
+
+            let breakdown = age_group_breakdown(&registrants, as_of());
+
+            let expected_breakdown = BTreeMap::from([
+                ("0-10 Male".to_owned(), 1),
+                ("11-14 Male".to_owned(), 1),
+                ("15-18 Female".to_owned(), 1),
+                ("19-29 Female".to_owned(), 1),
+                ("30+ Female".to_owned(), 1),
+            ]);
+            assert_eq!(expected_breakdown, breakdown);
+        }
+
+        #[test]
+        fn count_missing_birthdays_as_unknown() {
+            let registrants = vec![
+                registrant(1, Gender::Male, None),
+                registrant(2, Gender::Male, Some("not a date")),
+                registrant(3, Gender::Female, Some("01.01.1970")),
+            ];
+
+            let breakdown = age_group_breakdown(&registrants, as_of());
+
+            let expected_breakdown =
+                BTreeMap::from([("unknown Male".to_owned(), 2), ("30+ Female".to_owned(), 1)]);
+            assert_eq!(expected_breakdown, breakdown);
+        }
+
+        #[test]
+        fn use_custom_groups() {
+            let registrants = vec![
+                registrant(1, Gender::Male, Some("19.07.2007")),
+                registrant(2, Gender::Male, Some("20.07.2007")),
+            ];
+
+            let breakdown =
+                age_group_breakdown_with_groups(&registrants, as_of(), &AgeGroups::new(vec![17]));
+
+            let expected_breakdown =
+                BTreeMap::from([("0-17 Male".to_owned(), 1), ("18+ Male".to_owned(), 1)]);
+            assert_eq!(expected_breakdown, breakdown);
+        }
+    }
+}
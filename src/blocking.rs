@@ -0,0 +1,271 @@
+use crate::error::UdaError::OrganizationMembershipsAccessFailed;
+use crate::error::{log_error_and_return, log_message_and_return, UdaError};
+use crate::locale::Locale;
+use crate::login::{
+    read_sign_in_answer, read_sign_in_form, LoginConfig, LoginOutcome, SignInAnswer,
+    SignInAttempts, SignInForm, SignInSubmission,
+};
+use crate::retrieve_members::{
+    check_export_content, classify_export_failure, members_export_url, parse_members_export,
+    SPREADSHEET_CONTENT_TYPES,
+};
+use crate::tools::{redirect_policy, DEFAULT_TIMEOUT, DEFAULT_USER_AGENT, MAX_REDIRECTIONS};
+use crate::Result;
+use reqwest::blocking::Client;
+use std::sync::Arc;
+use uda_dto::uda_member::UdaMember;
+
+/// Build a blocking client for programs that don't run an async runtime, e.g. a small CLI.
+/// Like [`crate::tools::build_client`], it stores the session cookies and only follows redirections within the instance.
+/// It must be built, used and dropped outside of an async runtime, as it runs its own.
+pub fn build_client() -> Result<Client> {
+    Client::builder()
+        .connect_timeout(DEFAULT_TIMEOUT)
+        .timeout(DEFAULT_TIMEOUT)
+        .user_agent(DEFAULT_USER_AGENT)
+        .cookie_store(true)
+        .redirect(redirect_policy(MAX_REDIRECTIONS))
+        .build()
+        .map_err(log_message_and_return(
+            "Can't build HTTP client",
+            UdaError::connection,
+        ))
+}
+
+/// Log into UDA and makes given client able to request pages that require authentication,
+/// blocking until done. Unlike the async version, requests aren't retried.
+pub fn authenticate_into_uda(
+    client: &Client,
+    base_url: &str,
+    login: &str,
+    password: &str,
+) -> Result<()> {
+    authenticate_into_uda_with_config(client, base_url, login, password, &LoginConfig::default())
+}
+
+/// Log into UDA through the sign-in form the config tells, e.g. in another locale, blocking until done.
+/// The form is sent once more with a fresh authenticity token should UDA reject it, like the async version does.
+pub fn authenticate_into_uda_with_config(
+    client: &Client,
+    base_url: &str,
+    login: &str,
+    password: &str,
+    login_config: &LoginConfig,
+) -> Result<()> {
+    let sign_in_url = login_config.sign_in_url(base_url)?;
+    let mut attempts = SignInAttempts::default();
+    loop {
+        let sign_in_form = get_sign_in_form(client, &sign_in_url)?;
+        let result = check_credentials(client, &sign_in_form, login, password, login_config);
+        if !attempts.should_retry(&result, login) {
+            return result.map(|_outcome| ());
+        }
+    }
+}
+
+fn get_sign_in_form(client: &Client, sign_in_url: &str) -> Result<SignInForm> {
+    let response = client
+        .get(sign_in_url)
+        .send()
+        .map_err(log_message_and_return(
+            "Can't get authenticity token from UDA",
+            UdaError::connection,
+        ))?;
+    let status = response.status();
+    let body = response
+        .text()
+        .map_err(log_error_and_return(UdaError::connection))?;
+    read_sign_in_form(sign_in_url, status, &body)
+}
+
+fn check_credentials(
+    client: &Client,
+    sign_in_form: &SignInForm,
+    login: &str,
+    password: &str,
+    login_config: &LoginConfig,
+) -> Result<LoginOutcome> {
+    let submission = SignInSubmission::new(login, password, sign_in_form, login_config);
+    let mut request = client.post(submission.url).form(&submission.params);
+    if let Some(csrf_token) = submission.csrf_token {
+        request = request.header("X-CSRF-Token", csrf_token);
+    }
+    let response = request.send().map_err(log_message_and_return(
+        &format!("Failed to authenticate to UDA [user: {login}]"),
+        UdaError::connection,
+    ))?;
+
+    let status = response.status();
+    let headers = response.headers().clone();
+    let landing_url = response.url().clone();
+    let body = response.text().map_err(log_message_and_return(
+        &format!("Failed to authenticate to UDA [user: {login}]"),
+        UdaError::connection,
+    ))?;
+    read_sign_in_answer(
+        &SignInAnswer {
+            status,
+            headers: &headers,
+            landing_url: &landing_url,
+            body: &body,
+        },
+        sign_in_form,
+        login,
+        login_config,
+    )
+}
+
+/// Retrieve members from UDA's organisation membership page, blocking until done.
+/// Unlike the async version, the download isn't retried.
+pub fn retrieve_members(client: &Client, base_url: &str) -> Result<Vec<UdaMember>> {
+    let url = members_export_url(base_url, Locale::default())?;
+    let response = client
+        .get(&url)
+        .send()
+        .map_err(log_error_and_return(|error| {
+            OrganizationMembershipsAccessFailed(Arc::new(error))
+        }))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(classify_export_failure(&url, status, response.headers()));
+    }
+    let headers = response.headers().clone();
+    let body = response.bytes().map_err(log_message_and_return(
+        "Can't read organization_memberships content",
+        |error| OrganizationMembershipsAccessFailed(Arc::new(error)),
+    ))?;
+    check_export_content(&url, &headers, &body, &SPREADSHEET_CONTENT_TYPES)?;
+    parse_members_export(&body)
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod authenticate_into_uda {
+        use crate::blocking::{authenticate_into_uda, build_client};
+        use crate::error::UdaError;
+        use crate::fake_uda::FakeUda;
+        use crate::login::{setup_authentication, INVALID_AUTHENTICITY_TOKEN_PAGE};
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn success() {
+            let fake_uda = FakeUda::start().await;
+            let credentials = fake_uda.credentials().clone();
+
+            // The blocking client runs its own runtime, which can't be nested into the test's one
+            tokio::task::spawn_blocking(move || {
+                let client = build_client().unwrap();
+                authenticate_into_uda(
+                    &client,
+                    credentials.uda_url(),
+                    credentials.login(),
+                    credentials.password(),
+                )
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        }
+
+        #[tokio::test]
+        async fn fail_when_wrong_credentials() {
+            let fake_uda = FakeUda::start().await.with_wrong_credentials().await;
+            let credentials = fake_uda.credentials().clone();
+
+            let error = tokio::task::spawn_blocking(move || {
+                let client = build_client().unwrap();
+                authenticate_into_uda(
+                    &client,
+                    credentials.uda_url(),
+                    credentials.login(),
+                    credentials.password(),
+                )
+            })
+            .await
+            .unwrap()
+            .unwrap_err();
+
+            assert!(matches!(error, UdaError::WrongCredentials));
+        }
+
+        #[tokio::test]
+        async fn success_after_invalid_authenticity_token() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(
+                    ResponseTemplate::new(422).set_body_string(INVALID_AUTHENTICITY_TOKEN_PAGE),
+                )
+                .up_to_n_times(1)
+                .with_priority(1)
+                .mount(&mock_server)
+                .await;
+            let credentials = setup_authentication(&mock_server).await;
+
+            tokio::task::spawn_blocking(move || {
+                let client = build_client().unwrap();
+                authenticate_into_uda(
+                    &client,
+                    credentials.uda_url(),
+                    credentials.login(),
+                    credentials.password(),
+                )
+            })
+            .await
+            .unwrap()
+            .unwrap();
+
+            let requests = mock_server.received_requests().await.unwrap();
+            let sign_in_page_requests = requests
+                .iter()
+                .filter(|request| request.method.as_str() == "GET")
+                .count();
+            assert_eq!(2, sign_in_page_requests);
+        }
+    }
+
+    mod retrieve_members {
+        use crate::blocking::{authenticate_into_uda, build_client, retrieve_members};
+        use crate::error::UdaError;
+        use crate::fake_uda::FakeUda;
+
+        #[tokio::test]
+        async fn success() {
+            let fake_uda = FakeUda::start().await;
+            let credentials = fake_uda.credentials().clone();
+
+            let members = tokio::task::spawn_blocking(move || {
+                let client = build_client().unwrap();
+                authenticate_into_uda(
+                    &client,
+                    credentials.uda_url(),
+                    credentials.login(),
+                    credentials.password(),
+                )?;
+                retrieve_members(&client, credentials.uda_url())
+            })
+            .await
+            .unwrap()
+            .unwrap();
+
+            assert_eq!(fake_uda.expected_members(), members);
+        }
+
+        #[tokio::test]
+        async fn fail_when_export_fails() {
+            let fake_uda = FakeUda::start().await.with_export_failure(403).await;
+            let base_url = fake_uda.uri();
+
+            let error = tokio::task::spawn_blocking(move || {
+                retrieve_members(&build_client().unwrap(), &base_url)
+            })
+            .await
+            .unwrap()
+            .unwrap_err();
+
+            assert!(matches!(error, UdaError::LackOfPermissions));
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use crate::error::UdaError::{CircuitOpen, ConnectionFailed, OrganizationMembershipsAccessFailed};
+use crate::Result;
+use derive_getters::Getters;
+use log::{debug, warn};
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// When to stop calling a UDA instance failing to answer, and for how long.
+#[derive(Debug, Clone, PartialEq, Getters)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive connection failures opening the circuit.
+    failure_threshold: u32,
+    /// Time within which failures must happen to be counted together.
+    window: Duration,
+    /// Time calls are short-circuited for once the circuit is open,
+    /// before a single call is let through to test whether UDA has recovered.
+    cooldown: Duration,
+}
+
+impl CircuitBreakerConfig {
+    pub fn new(failure_threshold: u32, window: Duration, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            window,
+            cooldown,
+        }
+    }
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self::new(5, Duration::from_secs(60), Duration::from_secs(30))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Closed {
+        failures: u32,
+        first_failure_at: Option<Instant>,
+    },
+    Open {
+        until: Instant,
+    },
+    /// A call has been let through to test recovery: others are short-circuited until it ends,
+    /// or until the cooldown elapses in case it never does, e.g. when cancelled.
+    HalfOpen {
+        since: Instant,
+    },
+}
+
+impl State {
+    const CLOSED: State = State::Closed {
+        failures: 0,
+        first_failure_at: None,
+    };
+}
+
+/// Stop calling UDA for a while once it keeps failing to answer,
+/// so that the retries of many concurrent calls don't make an outage worse.
+/// Clones share the same state, so that all the clones of a session are stopped together.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    state: Arc<Mutex<State>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            state: Arc::new(Mutex::new(State::CLOSED)),
+        }
+    }
+
+    /// Run the call and record its outcome, unless the circuit is open,
+    /// in which case it fails right away with [`CircuitOpen`].
+    pub async fn call<T>(&self, call: impl Future<Output = Result<T>>) -> Result<T> {
+        self.acquire()?;
+        let result = call.await;
+        self.record(&result);
+        result
+    }
+
+    fn acquire(&self) -> Result<()> {
+        let mut state = self.lock_state();
+        let now = Instant::now();
+        let reopen_at = match *state {
+            State::Closed { .. } => return Ok(()),
+            State::Open { until } => until,
+            State::HalfOpen { since } => since + self.config.cooldown,
+        };
+
+        if now < reopen_at {
+            let retry_after = reopen_at - now;
+            debug!("Circuit open, short-circuiting call [retry_after: {retry_after:?}]");
+            return Err(CircuitOpen { retry_after });
+        }
+        debug!("Circuit half-open, letting a call through to test recovery");
+        *state = State::HalfOpen { since: now };
+        Ok(())
+    }
+
+    /// Only connection failures count: any answer, even an error, tells UDA is reachable.
+    fn record<T>(&self, result: &Result<T>) {
+        let mut state = self.lock_state();
+        let now = Instant::now();
+        if !matches!(
+            result,
+            Err(ConnectionFailed(_) | OrganizationMembershipsAccessFailed(_))
+        ) {
+            if *state != State::CLOSED {
+                debug!("UDA answered, closing circuit");
+            }
+            *state = State::CLOSED;
+            return;
+        }
+
+        let failures = match *state {
+            State::Closed {
+                failures,
+                first_failure_at: Some(first_failure_at),
+            } if now.duration_since(first_failure_at) <= self.config.window => {
+                Some((failures + 1, first_failure_at))
+            }
+            State::Closed { .. } => Some((1, now)),
+            // Either the recovery test failed or UDA is still failing
+            State::Open { .. } | State::HalfOpen { .. } => None,
+        };
+        *state = match failures {
+            Some((failures, first_failure_at)) if failures < self.config.failure_threshold => {
+                State::Closed {
+                    failures,
+                    first_failure_at: Some(first_failure_at),
+                }
+            }
+            _ => {
+                warn!(
+                    "UDA keeps failing to answer. Opening circuit. [cooldown: {:?}]",
+                    self.config.cooldown
+                );
+                State::Open {
+                    until: now + self.config.cooldown,
+                }
+            }
+        };
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod call {
+        use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
+        use crate::error::UdaError;
+        use std::time::Duration;
+
+        const COOLDOWN: Duration = Duration::from_secs(30);
+
+        fn circuit_breaker() -> CircuitBreaker {
+            CircuitBreaker::new(CircuitBreakerConfig::new(
+                3,
+                Duration::from_secs(60),
+                COOLDOWN,
+            ))
+        }
+
+        async fn fail(circuit_breaker: &CircuitBreaker) -> UdaError {
+            circuit_breaker
+                .call(async { Err::<(), _>(UdaError::ConnectionFailed(None)) })
+                .await
+                .unwrap_err()
+        }
+
+        async fn succeed(circuit_breaker: &CircuitBreaker) -> crate::Result<()> {
+            circuit_breaker.call(async { Ok(()) }).await
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn open_after_consecutive_failures() {
+            let circuit_breaker = circuit_breaker();
+
+            for _ in 0..3 {
+                assert!(matches!(
+                    fail(&circuit_breaker).await,
+                    UdaError::ConnectionFailed(_)
+                ));
+            }
+
+            let error = succeed(&circuit_breaker).await.unwrap_err();
+            assert!(
+                matches!(error, UdaError::CircuitOpen { retry_after } if retry_after == COOLDOWN)
+            );
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn stay_closed_when_failures_are_interrupted() {
+            let circuit_breaker = circuit_breaker();
+
+            fail(&circuit_breaker).await;
+            fail(&circuit_breaker).await;
+            succeed(&circuit_breaker).await.unwrap();
+            fail(&circuit_breaker).await;
+            fail(&circuit_breaker).await;
+
+            succeed(&circuit_breaker).await.unwrap();
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn stay_closed_when_failures_are_spread_out() {
+            let circuit_breaker = circuit_breaker();
+
+            fail(&circuit_breaker).await;
+            fail(&circuit_breaker).await;
+            tokio::time::advance(Duration::from_secs(61)).await;
+            fail(&circuit_breaker).await;
+
+            succeed(&circuit_breaker).await.unwrap();
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn ignore_errors_from_answering_instance() {
+            let circuit_breaker = circuit_breaker();
+
+            for _ in 0..5 {
+                circuit_breaker
+                    .call(async { Err::<(), _>(UdaError::LackOfPermissions) })
+                    .await
+                    .unwrap_err();
+            }
+
+            succeed(&circuit_breaker).await.unwrap();
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn half_open_after_cooldown() {
+            let circuit_breaker = circuit_breaker();
+            for _ in 0..3 {
+                fail(&circuit_breaker).await;
+            }
+
+            tokio::time::advance(Duration::from_secs(10)).await;
+            let error = succeed(&circuit_breaker).await.unwrap_err();
+            assert!(
+                matches!(error, UdaError::CircuitOpen { retry_after } if retry_after == Duration::from_secs(20))
+            );
+
+            tokio::time::advance(Duration::from_secs(20)).await;
+            succeed(&circuit_breaker).await.unwrap();
+            succeed(&circuit_breaker).await.unwrap();
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn reopen_when_recovery_test_fails() {
+            let circuit_breaker = circuit_breaker();
+            for _ in 0..3 {
+                fail(&circuit_breaker).await;
+            }
+
+            tokio::time::advance(COOLDOWN).await;
+            // Let through as the recovery test
+            assert!(matches!(
+                fail(&circuit_breaker).await,
+                UdaError::ConnectionFailed(_)
+            ));
+
+            let error = succeed(&circuit_breaker).await.unwrap_err();
+            assert!(matches!(error, UdaError::CircuitOpen { .. }));
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn share_state_between_clones() {
+            let circuit_breaker = circuit_breaker();
+            let cloned_circuit_breaker = circuit_breaker.clone();
+
+            for _ in 0..3 {
+                fail(&circuit_breaker).await;
+            }
+
+            let error = succeed(&cloned_circuit_breaker).await.unwrap_err();
+            assert!(matches!(error, UdaError::CircuitOpen { .. }));
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::retrieve_members::retrieve_members;
+use crate::Result;
+use reqwest::Client;
+use std::collections::HashSet;
+use uda_dto::uda_member::UdaMember;
+
+/// Clubs the members belong to, sorted and without duplicates.
+/// Clubs are compared case-insensitively, as they are typed in by members:
+/// the casing of the first member of each club is kept.
+pub fn distinct_clubs(members: &[UdaMember]) -> Vec<String> {
+    let mut seen_clubs = HashSet::new();
+    let mut clubs = members
+        .iter()
+        .filter_map(|member| member.club().as_ref())
+        .filter(|club| seen_clubs.insert(club.to_lowercase()))
+        .cloned()
+        .collect::<Vec<_>>();
+    clubs.sort_by_key(|club| club.to_lowercase());
+    clubs
+}
+
+/// Retrieve the clubs the members of UDA belong to.
+pub async fn retrieve_clubs(client: &Client, base_url: &str) -> Result<Vec<String>> {
+    let members = retrieve_members(client, base_url).await?;
+    Ok(distinct_clubs(&members))
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod distinct_clubs {
+        use crate::clubs::distinct_clubs;
+        use crate::retrieve_members::get_expected_member;
+        use uda_dto::uda_member::UdaMember;
+
+        #[test]
+        fn success() {
+            let mut members = get_expected_member();
+            members.push(UdaMember::new(
+                3,
+                None,
+                "Jane".to_owned(),
+                "Doe".to_owned(),
+                "jane.doe@email.com".to_owned(),
+                Some("LE CLUB DE TEST".to_owned()),
+                true,
+            ));
+
+            assert_eq!(
+                vec!["KH Team".to_owned(), "Le club de test".to_owned()],
+                distinct_clubs(&members)
+            );
+        }
+
+        #[test]
+        fn empty_when_no_member() {
+            assert!(distinct_clubs(&[]).is_empty());
+        }
+    }
+
+    mod retrieve_clubs {
+        use crate::clubs::retrieve_clubs;
+        use crate::retrieve_members::setup_member_retrieval;
+        use crate::tools::build_client;
+        use wiremock::MockServer;
+
+        #[tokio::test]
+        async fn success() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+            setup_member_retrieval(&mock_server).await;
+
+            let clubs = retrieve_clubs(&client, &mock_server.uri()).await.unwrap();
+
+            assert_eq!(
+                vec!["KH Team".to_owned(), "Le club de test".to_owned()],
+                clubs
+            );
+        }
+    }
+}
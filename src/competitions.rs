@@ -0,0 +1,203 @@
+use crate::error::UdaError;
+use crate::error::UdaError::{CantReadPageContent, UnexpectedStatus};
+use crate::error::{log_error_and_return, log_message_and_return};
+use crate::ids::CompetitionId;
+use crate::locale::Locale;
+use crate::tools::build_url;
+use crate::Result;
+use derive_getters::Getters;
+use log::{error, warn};
+use reqwest::Client;
+use scraper::{ElementRef, Html, Selector};
+use std::sync::Arc;
+
+/// An event hosted on a UDA instance, as listed on its competitions page.
+#[derive(Debug, Clone, PartialEq, Getters)]
+pub struct Competition {
+    id: CompetitionId,
+    name: String,
+    date: String,
+}
+
+impl Competition {
+    pub fn new(id: CompetitionId, name: String, date: String) -> Self {
+        Self { id, name, date }
+    }
+}
+
+/// Retrieve the competitions hosted on a UDA instance.
+pub async fn retrieve_competitions(client: &Client, base_url: &str) -> Result<Vec<Competition>> {
+    let url = competitions_url(base_url, Locale::default())?;
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(log_message_and_return(
+            "Can't reach competitions page",
+            UdaError::connection,
+        ))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        error!("Can't reach competitions page: {status:?}");
+        return Err(UnexpectedStatus(status));
+    }
+
+    let body = response
+        .text()
+        .await
+        .map_err(log_error_and_return(|error| {
+            CantReadPageContent(Some(Arc::new(error)))
+        }))?;
+
+    get_competitions_from_html(&body)
+}
+
+fn competitions_url(base_url: &str, locale: Locale) -> Result<String> {
+    build_url(base_url, &format!("{}/competitions", locale.code()))
+}
+
+fn get_competitions_from_html(body: &str) -> Result<Vec<Competition>> {
+    let selector = Selector::parse(r"tr").map_err(UdaError::from)?;
+    let document = Html::parse_document(body);
+
+    let rows = document.select(&selector);
+
+    Ok(rows
+        .flat_map(|row| get_competition_from_row(&row))
+        .collect())
+}
+
+fn get_competition_from_row(row: &ElementRef) -> Option<Competition> {
+    let selector = Selector::parse("td").ok()?;
+    let cells = row.select(&selector).collect::<Vec<_>>();
+
+    let (name_cell, date_cell) = match cells[..] {
+        [name_cell, date_cell] => (name_cell, date_cell),
+        _ => {
+            warn!("Ignoring competition because wrongly formatted [row: {row:?}]");
+            return None;
+        }
+    };
+
+    let selector = Selector::parse("a").ok()?;
+    let link_element = name_cell.select(&selector).next()?;
+    let link = link_element.attr("href")?;
+    let id = link
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()?
+        .parse()
+        .ok()?;
+    let name = link_element.text().next()?.trim();
+    let date = date_cell.text().next()?.trim();
+
+    Some(Competition::new(id, name.to_owned(), date.to_owned()))
+}
+
+#[cfg(any(test, feature = "test"))]
+pub const COMPETITIONS_BODY: &str = r##"<html><head></head><body><div id="main"><h1>Competitions</h1><table class="sortable"><thead><tr><th>Name</th><th>Start Time</th></tr></thead><tbody><tr><td><a href="/en/competitions/12">100m - Track</a></td><td>Sat, 19 Jul 2025</td></tr><tr><td><a href="/en/competitions/15">Freestyle - Individual</a></td><td>Sun, 20 Jul 2025</td></tr></tbody></table></div></body></html>"##;
+
+#[cfg(any(test, feature = "test"))]
+pub fn get_expected_competitions() -> Vec<Competition> {
+    vec![
+        Competition::new(
+            CompetitionId(12),
+            "100m - Track".to_owned(),
+            "Sat, 19 Jul 2025".to_owned(),
+        ),
+        Competition::new(
+            CompetitionId(15),
+            "Freestyle - Individual".to_owned(),
+            "Sun, 20 Jul 2025".to_owned(),
+        ),
+    ]
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod retrieve_competitions {
+        use crate::competitions::{
+            get_expected_competitions, retrieve_competitions, COMPETITIONS_BODY,
+        };
+        use crate::error::UdaError;
+        use crate::tools::build_client;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn success() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/en/competitions"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(COMPETITIONS_BODY))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let competitions = retrieve_competitions(&client, &mock_server.uri())
+                .await
+                .unwrap();
+
+            assert_eq!(get_expected_competitions(), competitions);
+        }
+
+        #[tokio::test]
+        async fn fail_when_unexpected_status() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/en/competitions"))
+                .respond_with(ResponseTemplate::new(500))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let error = retrieve_competitions(&client, &mock_server.uri())
+                .await
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::UnexpectedStatus(status) if status == 500));
+        }
+    }
+
+    mod get_competition_from_row {
+        use crate::competitions::{get_competition_from_row, Competition};
+        use crate::ids::CompetitionId;
+        use scraper::{Html, Selector};
+
+        fn get_competition(body: &str) -> Option<Competition> {
+            let selector = Selector::parse(r"tr").unwrap();
+            let document = Html::parse_document(body);
+            let row = document.select(&selector).next().unwrap();
+            get_competition_from_row(&row)
+        }
+
+        #[test]
+        fn success() {
+            let competition = get_competition(
+                r#"<html><body><table><tr><td><a href="/en/competitions/12">100m - Track</a></td><td>Sat, 19 Jul 2025</td></tr></table></body></html>"#,
+            )
+            .unwrap();
+
+            assert_eq!(CompetitionId(12), *competition.id());
+            assert_eq!("100m - Track", competition.name());
+            assert_eq!("Sat, 19 Jul 2025", competition.date());
+        }
+
+        #[test]
+        fn fail_when_missing_cells() {
+            assert!(get_competition(
+                r#"<html><body><table><tr><td><a href="/en/competitions/12">100m - Track</a></td></tr></table></body></html>"#,
+            )
+            .is_none());
+        }
+
+        #[test]
+        fn fail_when_id_is_not_a_number() {
+            assert!(get_competition(
+                r#"<html><body><table><tr><td><a href="/en/competitions/new">100m - Track</a></td><td>Sat, 19 Jul 2025</td></tr></table></body></html>"#,
+            )
+            .is_none());
+        }
+    }
+}
@@ -1,12 +1,19 @@
 use crate::error::UdaError::{
-    CantReadPageContent, ConnectionFailed, LackOfPermissions, MemberConfirmationFailed,
-    OrganizationMembershipsAccessFailed,
+    CantReadPageContent, LackOfPermissions, MemberConfirmationFailed,
+    OrganizationMembershipsAccessFailed, SessionExpired, UnexpectedStatus,
 };
 use crate::error::{log_error_and_return, log_message_and_return, UdaError};
+use crate::ids::MemberId;
+use crate::locale::Locale;
+use crate::rate_limit::RateLimiter;
+use crate::retrieve_members::is_sign_in_page;
+use crate::retry::{send_throttled_with_retry, RetryPolicy};
+use crate::tools::{authorize, build_url, BasicAuth};
 use crate::Result;
 use log::{error, trace, warn};
 use reqwest::{Client, StatusCode};
 use scraper::{Html, Selector};
+use std::sync::Arc;
 #[cfg(any(test, feature = "test"))]
 use wiremock::matchers::{header, method, path};
 #[cfg(any(test, feature = "test"))]
@@ -15,27 +22,81 @@ use wiremock::{Mock, MockServer, ResponseTemplate};
 /// Try and mark member as confirmed on UDA.
 /// If called on a member already confirmed, it marks them as unconfirmed before trying to mark it as confirmed again.
 pub async fn confirm_member(client: &Client, base_url: &str, id: u16) -> Result<()> {
-    let csrf_token = get_csrf_token(client, base_url).await?;
-    confirm_member_with_retry(client, base_url, id, &csrf_token, true).await
+    confirm_member_throttled(
+        client,
+        base_url,
+        Locale::default(),
+        id.into(),
+        &RetryPolicy::default(),
+        None,
+        None,
+    )
+    .await
 }
 
+/// Like [`confirm_member`], waiting for the rate limiter, if any, before each request,
+/// and sending requests again while they fail transiently, as long as the policy allows it.
+/// Should a retried toggle have gone through, the member is found unconfirmed and gets confirmed back.
+pub(crate) async fn confirm_member_throttled(
+    client: &Client,
+    base_url: &str,
+    locale: Locale,
+    id: MemberId,
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+) -> Result<()> {
+    let csrf_token = get_csrf_token(
+        client,
+        base_url,
+        locale,
+        retry_policy,
+        rate_limiter,
+        basic_auth,
+    )
+    .await?;
+    confirm_member_with_retry(
+        client,
+        base_url,
+        locale,
+        id,
+        &csrf_token,
+        retry_policy,
+        rate_limiter,
+        basic_auth,
+        true,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn confirm_member_with_retry(
     client: &Client,
     base_url: &str,
-    id: u16,
+    locale: Locale,
+    id: MemberId,
     csrf_token: &str,
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
     should_retry: bool,
 ) -> Result<()> {
-    let url = format!("{base_url}/en/organization_memberships/{id}/toggle_confirm");
-    let response = client
-        .put(url)
-        .header("Accept", "*/*;q=0.5, text/javascript, application/javascript, application/ecmascript, application/x-ecmascript")
-        .header("X-CSRF-Token", csrf_token)
-        .send()
-        .await
-        .map_err(log_message_and_return(
+    let url = build_url(
+        base_url,
+        &format!(
+            "{}/organization_memberships/{id}/toggle_confirm",
+            locale.code()
+        ),
+    )?;
+    let response = send_throttled_with_retry(retry_policy, rate_limiter, || {
+        authorize(client.put(&url), &url, basic_auth)
+            .header("Accept", "*/*;q=0.5, text/javascript, application/javascript, application/ecmascript, application/x-ecmascript")
+            .header("X-CSRF-Token", csrf_token)
+    })
+    .await
+    .map_err(log_message_and_return(
             "Can't mark as confirmed on UDA",
-            ConnectionFailed,
+            UdaError::connection,
         ))?;
 
     let status = response.status();
@@ -43,20 +104,23 @@ async fn confirm_member_with_retry(
         warn!("Can't mark as confirmed on UDA [status: {status}]");
         return match status {
             StatusCode::NOT_FOUND => Err(LackOfPermissions), // If the user is not authorized to confirm members, then we get a 404...
-            _ => Err(ConnectionFailed),
+            _ => Err(UnexpectedStatus(status)),
         };
     }
 
     let body = response.text().await.map_err(log_message_and_return(
         "Can't read text after having marked user as confirmed",
-        CantReadPageContent,
+        |error| CantReadPageContent(Some(Arc::new(error))),
     ))?;
 
+    let code = locale.code();
     let unmarked_message = format!(
-        r#"href=\"/en/organization_memberships/{id}/toggle_confirm\">Mark as confirmed<\/a>"#
+        r#"href=\"/{code}/organization_memberships/{id}/toggle_confirm\">{}<\/a>"#,
+        locale.mark_as_confirmed_label()
     );
     let marked_message = format!(
-        r#"href=\"/en/organization_memberships/{id}/toggle_confirm\">Mark as unconfirmed<\/a>"#
+        r#"href=\"/{code}/organization_memberships/{id}/toggle_confirm\">{}<\/a>"#,
+        locale.mark_as_unconfirmed_label()
     );
     if body.contains(unmarked_message.as_str()) {
         if should_retry {
@@ -64,14 +128,22 @@ async fn confirm_member_with_retry(
                 "Member has been unconfirmed! Trying to confirm them back. [uda_url: {base_url}, id: {id}]"
             );
             Box::pin(confirm_member_with_retry(
-                client, base_url, id, csrf_token, false,
+                client,
+                base_url,
+                locale,
+                id,
+                csrf_token,
+                retry_policy,
+                rate_limiter,
+                basic_auth,
+                false,
             ))
             .await
         } else {
             error!(
                 "Member has been unconfirmed! NOT trying to confirm them back. [uda_url: {base_url}, id: {id}]"
             );
-            Err(MemberConfirmationFailed(id))?
+            Err(MemberConfirmationFailed(id.0))?
         }
     } else if body.contains(marked_message.as_str()) {
         trace!("Member has been confirmed on UDA! [uda_url: {base_url}, id: {id}]");
@@ -81,37 +153,50 @@ async fn confirm_member_with_retry(
     }
 }
 
-async fn get_csrf_token(client: &Client, base_url: &str) -> Result<String> {
-    let url = format!("{base_url}/en/organization_memberships");
-
-    let response = client
-        .get(url)
-        .send()
-        .await
-        .map_err(log_error_and_return(OrganizationMembershipsAccessFailed))?;
+async fn get_csrf_token(
+    client: &Client,
+    base_url: &str,
+    locale: Locale,
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+) -> Result<String> {
+    let url = build_url(
+        base_url,
+        &format!("{}/organization_memberships", locale.code()),
+    )?;
+    let response = send_throttled_with_retry(retry_policy, rate_limiter, || {
+        authorize(client.get(&url), &url, basic_auth)
+    })
+    .await
+    .map_err(log_error_and_return(|error| {
+        OrganizationMembershipsAccessFailed(Arc::new(error))
+    }))?;
 
     let status = response.status();
     if status.is_success() {
         let body = response.text().await.map_err(log_message_and_return(
             "Can't read organization_memberships content",
-            OrganizationMembershipsAccessFailed,
+            |error| OrganizationMembershipsAccessFailed(Arc::new(error)),
         ))?;
-        if body.contains("Unicycling Society/Federation Membership Management") {
+        if is_sign_in_page(body.as_bytes()) {
+            error!(
+                "Redirected to sign in page instead of organization_memberships. Session expired?"
+            );
+            Err(SessionExpired)
+        } else if body.contains(locale.membership_management_title()) {
             retrieve_csrf_from_html(&body).await
         } else {
             error!("Can't access organization_memberships page. Lack of permissions?");
             Err(LackOfPermissions)
         }
     } else {
-        error!(
-            "Can't reach organization_memberships page: {:?}",
-            response.status()
-        );
-        Err(OrganizationMembershipsAccessFailed)?
+        error!("Can't reach organization_memberships page: {status:?}");
+        Err(UnexpectedStatus(status))
     }
 }
 
-async fn retrieve_csrf_from_html(body: &str) -> Result<String> {
+pub(crate) async fn retrieve_csrf_from_html(body: &str) -> Result<String> {
     let selector = Selector::parse(r#"meta[name="csrf-token"]"#).map_err(UdaError::from)?;
     let document = Html::parse_document(body);
 
@@ -168,7 +253,7 @@ console.log("Updated {id}");"##
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use crate::tools::tests::build_client;
+    use crate::tools::build_client;
     use wiremock::matchers::{header, method, path};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
@@ -188,6 +273,43 @@ pub mod tests {
             .unwrap();
     }
 
+    #[tokio::test]
+    async fn should_confirm_member_in_locale() {
+        let id = 10_u16;
+
+        let mock_server = MockServer::start().await;
+        let client = Client::new();
+        let csrf_token = "PDKOFSqmdfjsdf3435dqs";
+        Mock::given(method("GET"))
+            .and(path("/fr/organization_memberships"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(format!(
+                r#"<html><head><meta name="csrf-token" content="{csrf_token}"></head><body>Gestion des adhésions</body></html>"#
+            )))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path(format!("/fr/organization_memberships/{id}/toggle_confirm")))
+            .and(header("X-CSRF-Token", csrf_token))
+            .respond_with(ResponseTemplate::new(200).set_body_string(format!(
+                r#"<a data-remote=\"true\" rel=\"nofollow\" data-method=\"put\" href=\"/fr/organization_memberships/{id}/toggle_confirm\">Marquer comme non confirmé<\/a>"#
+            )))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        confirm_member_throttled(
+            &client,
+            &mock_server.uri(),
+            Locale::Fr,
+            id.into(),
+            &RetryPolicy::default(),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    }
+
     #[tokio::test]
     async fn should_fail_to_confirm_member_when_lack_of_permissions() {
         let id = 10_u16;
@@ -239,7 +361,7 @@ I'm sorry, but the page you are searching for was not found.
         let error = confirm_member(&client, &mock_server.uri(), id)
             .await
             .unwrap_err();
-        assert!(matches!(error, ConnectionFailed));
+        assert!(matches!(error, UnexpectedStatus(status) if status == 500));
     }
 
     #[tokio::test]
@@ -340,7 +462,16 @@ I'm sorry, but the page you are searching for was not found.
 
         let expected_csrf_token = setup_csrf_token(&mock_server).await;
 
-        let result = get_csrf_token(&client, &mock_server.uri()).await.unwrap();
+        let result = get_csrf_token(
+            &client,
+            &mock_server.uri(),
+            Locale::default(),
+            &RetryPolicy::default(),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
         assert_eq!(expected_csrf_token, result);
     }
 
@@ -354,10 +485,17 @@ I'm sorry, but the page you are searching for was not found.
             .mount(&mock_server)
             .await;
 
-        let error = get_csrf_token(&client, &mock_server.uri())
-            .await
-            .unwrap_err();
-        assert!(matches!(error, OrganizationMembershipsAccessFailed));
+        let error = get_csrf_token(
+            &client,
+            &mock_server.uri(),
+            Locale::default(),
+            &RetryPolicy::default(),
+            None,
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(error, UnexpectedStatus(status) if status == 500));
     }
 
     #[tokio::test]
@@ -372,11 +510,43 @@ I'm sorry, but the page you are searching for was not found.
             .mount(&mock_server)
             .await;
 
-        let error = get_csrf_token(&client, &mock_server.uri())
-            .await
-            .unwrap_err();
+        let error = get_csrf_token(
+            &client,
+            &mock_server.uri(),
+            Locale::default(),
+            &RetryPolicy::default(),
+            None,
+            None,
+        )
+        .await
+        .unwrap_err();
         assert!(matches!(error, LackOfPermissions));
     }
+
+    #[tokio::test]
+    async fn should_fail_to_get_csrf_token_when_session_expired() {
+        let body = r#"<form><input name="user[password]"></form>"#;
+
+        let mock_server = MockServer::start().await;
+        let client = build_client().unwrap();
+        Mock::given(method("GET"))
+            .and(path("/en/organization_memberships"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .mount(&mock_server)
+            .await;
+
+        let error = get_csrf_token(
+            &client,
+            &mock_server.uri(),
+            Locale::default(),
+            &RetryPolicy::default(),
+            None,
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(error, SessionExpired));
+    }
     // endregion
 
     //region retrieve_csrf_from_html
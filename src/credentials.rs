@@ -1,7 +1,15 @@
+use crate::error::UdaError::MissingCredentials;
+use crate::tools::normalize_base_url;
+use crate::Result;
 use derive_getters::Getters;
+use log::error;
 use std::fmt::{Debug, Formatter};
 use serde::{Deserialize, Serialize};
 
+const UDA_URL_VARIABLE: &str = "UDA_URL";
+const UDA_LOGIN_VARIABLE: &str = "UDA_LOGIN";
+const UDA_PASSWORD_VARIABLE: &str = "UDA_PASSWORD";
+
 #[derive(Serialize, Deserialize, Getters, PartialEq, Clone, Default)]
 pub struct UdaCredentials {
     /// Should be something like `https://cfm2019training.reg.unicycling-software.com`
@@ -10,21 +18,74 @@ pub struct UdaCredentials {
     uda_url: String,
     login: String,
     password: String,
+    /// Username and password of the HTTP Basic Auth some deployments require in front of UDA.
+    #[serde(default)]
+    basic_auth: Option<(String, String)>,
 }
 
 impl UdaCredentials {
     pub fn uda_url(&self) -> &String {
         &self.uda_url
     }
+
+    /// Build credentials, checking the URL of the instance and removing its trailing slashes.
+    pub fn try_new(uda_url: &str, login: String, password: String) -> Result<Self> {
+        Ok(Self {
+            uda_url: normalize_base_url(uda_url)?,
+            login,
+            password,
+            basic_auth: None,
+        })
+    }
+
+    /// Authenticate every request with HTTP Basic Auth, besides logging into UDA.
+    pub fn with_basic_auth(mut self, username: String, password: String) -> Self {
+        self.basic_auth = Some((username, password));
+        self
+    }
+
+    /// Read credentials from the `UDA_URL`, `UDA_LOGIN` and `UDA_PASSWORD` environment variables.
+    pub fn from_env() -> Result<Self> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// Read credentials from the `UDA_URL`, `UDA_LOGIN` and `UDA_PASSWORD` variables given by the lookup,
+    /// e.g. from a secret store. The URL and the login are trimmed, the password is kept as is.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let uda_url = read_variable(&lookup, UDA_URL_VARIABLE)?;
+        let login = read_variable(&lookup, UDA_LOGIN_VARIABLE)?;
+        Ok(Self {
+            uda_url: normalize_base_url(uda_url.trim())?,
+            login: login.trim().to_owned(),
+            password: read_variable(&lookup, UDA_PASSWORD_VARIABLE)?,
+            basic_auth: None,
+        })
+    }
 }
 
+/// Read the value of the variable, considering a blank value as missing.
+fn read_variable(lookup: &impl Fn(&str) -> Option<String>, name: &str) -> Result<String> {
+    match lookup(name) {
+        Some(value) if !value.trim().is_empty() => Ok(value),
+        _ => {
+            error!("Missing credentials [variable: {name}]");
+            Err(MissingCredentials(name.to_owned()))
+        }
+    }
+}
+
+/// The password is redacted, so that credentials can't leak into logs.
 impl Debug for UdaCredentials {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Uda Credentials {{uda={}, login={}, password=MASKED}}",
+            "Uda Credentials {{uda={}, login={}, password=MASKED",
             self.uda_url, self.login
-        )
+        )?;
+        if let Some((username, _)) = &self.basic_auth {
+            write!(f, ", basic_auth={username}:MASKED")?;
+        }
+        write!(f, "}}")
     }
 }
 
@@ -35,6 +96,155 @@ impl UdaCredentials {
             uda_url,
             login,
             password,
+            basic_auth: None,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod debug {
+        use crate::credentials::UdaCredentials;
+
+        #[test]
+        fn redact_password() {
+            let credentials = UdaCredentials::new(
+                "https://uda.example.com".to_owned(),
+                "login".to_owned(),
+                "secret-password".to_owned(),
+            );
+
+            let debug = format!("{credentials:?}");
+
+            assert!(debug.contains("login"));
+            assert!(debug.contains("password=MASKED"));
+            assert!(!debug.contains("secret-password"));
+        }
+    }
+
+    mod try_new {
+        use crate::credentials::UdaCredentials;
+        use crate::error::UdaError;
+
+        #[test]
+        fn success() {
+            let credentials = UdaCredentials::try_new(
+                "https://cfm2025.reg.unicycling-software.com",
+                "login".to_owned(),
+                "password".to_owned(),
+            )
+            .unwrap();
+            assert_eq!(
+                "https://cfm2025.reg.unicycling-software.com",
+                credentials.uda_url()
+            );
+        }
+
+        #[test]
+        fn remove_trailing_slash() {
+            let credentials = UdaCredentials::try_new(
+                "https://cfm2025.reg.unicycling-software.com/",
+                "login".to_owned(),
+                "password".to_owned(),
+            )
+            .unwrap();
+            assert_eq!(
+                "https://cfm2025.reg.unicycling-software.com",
+                credentials.uda_url()
+            );
+        }
+
+        #[test]
+        fn encode_internationalized_domain_name() {
+            let credentials = UdaCredentials::try_new(
+                "https://monocycle-été.example",
+                "login".to_owned(),
+                "password".to_owned(),
+            )
+            .unwrap();
+            assert_eq!("https://xn--monocycle-t-kbbb.example", credentials.uda_url());
+        }
+
+        #[test]
+        fn fail_when_missing_scheme() {
+            let error = UdaCredentials::try_new(
+                "cfm2025.reg.unicycling-software.com",
+                "login".to_owned(),
+                "password".to_owned(),
+            )
+            .unwrap_err();
+            assert!(matches!(error, UdaError::InvalidUrl(_)));
+        }
+    }
+
+    mod from_lookup {
+        use crate::credentials::UdaCredentials;
+        use crate::error::UdaError;
+
+        /// Lookup of the given variables, the others being missing.
+        fn lookup(variables: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+            let variables: Vec<_> = variables
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect();
+            move |name| {
+                variables
+                    .iter()
+                    .find(|(variable, _)| variable == name)
+                    .map(|(_, value)| value.clone())
+            }
+        }
+
+        #[test]
+        fn success() {
+            let credentials = UdaCredentials::from_lookup(lookup(&[
+                ("UDA_URL", " https://uda.example.com "),
+                ("UDA_LOGIN", "login\n"),
+                ("UDA_PASSWORD", "password"),
+            ]))
+            .unwrap();
+
+            assert_eq!(
+                UdaCredentials::new(
+                    "https://uda.example.com".to_owned(),
+                    "login".to_owned(),
+                    "password".to_owned()
+                ),
+                credentials
+            );
+        }
+
+        #[test]
+        fn keep_password_as_is() {
+            let credentials = UdaCredentials::from_lookup(lookup(&[
+                ("UDA_URL", "https://uda.example.com"),
+                ("UDA_LOGIN", "login"),
+                ("UDA_PASSWORD", " pass word "),
+            ]))
+            .unwrap();
+
+            assert_eq!(" pass word ", credentials.password());
+        }
+
+        #[test]
+        fn fail_when_variable_missing() {
+            let error =
+                UdaCredentials::from_lookup(lookup(&[("UDA_URL", "https://uda.example.com")]))
+                    .unwrap_err();
+
+            assert!(matches!(error, UdaError::MissingCredentials(name) if name == "UDA_LOGIN"));
+        }
+
+        #[test]
+        fn fail_when_variable_empty() {
+            let error = UdaCredentials::from_lookup(lookup(&[
+                ("UDA_URL", "https://uda.example.com"),
+                ("UDA_LOGIN", "login"),
+                ("UDA_PASSWORD", "  "),
+            ]))
+            .unwrap_err();
+
+            assert!(matches!(error, UdaError::MissingCredentials(name) if name == "UDA_PASSWORD"));
         }
     }
 }
@@ -1,27 +1,111 @@
 use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
 use log::error;
-use crate::error::UdaError::MalformedSelector;
+use crate::error::UdaError::{ConnectionFailed, MalformedSelector, MalformedXlsFile, ProtectedWorkbook};
+use calamine::{OdsError, XlsError, XlsbError, XlsxError};
+use reqwest::StatusCode;
 use scraper::error::SelectorErrorKind;
 use thiserror::Error;
 
-#[derive(Debug, Error)]
+/// Errors raised when talking to UDA.
+/// New variants may come with minor versions: match it with a wildcard arm,
+/// or rely on [`UdaError::is_retryable`] to tell transient errors apart.
+#[derive(Debug, Clone, Error)]
+#[non_exhaustive]
 pub enum UdaError {
     #[error("The connection to UDA has failed.")]
-    ConnectionFailed,
+    ConnectionFailed(#[source] Option<Arc<reqwest::Error>>),
     #[error("The page content couldn't be read.")]
-    CantReadPageContent,
+    CantReadPageContent(#[source] Option<Arc<reqwest::Error>>),
     #[error("The organisation memberships is inaccessible.")]
-    OrganizationMembershipsAccessFailed,
+    OrganizationMembershipsAccessFailed(#[source] Arc<reqwest::Error>),
     #[error("Missing permissions to read the page")]
     LackOfPermissions,
     #[error("Wrong credentials to log into UDA")]
     WrongCredentials,
+    #[error("The UDA account is locked after too many failed logins, it must be unlocked first")]
+    AccountLocked,
     #[error("Provided selector is malformed [selector: {0}]")]
     MalformedSelector(String),
     #[error("The member can't be marked as confirmed [id: {0}]")]
     MemberConfirmationFailed(u16),
     #[error("The exported XLS file is malformed")]
-    MalformedXlsFile,
+    MalformedXlsFile(#[source] Option<Arc<calamine::Error>>),
+    #[error("The UDA session has expired, a new login is required")]
+    SessionExpired,
+    #[error("The members can't be serialized")]
+    MemberSerializationFailed(#[source] Arc<serde_json::Error>),
+    #[error("UDA answered with an unexpected status [status: {0}]")]
+    UnexpectedStatus(StatusCode),
+    #[error("The UDA URL is invalid, it should look like https://xxx.reg.unicycling-software.com [url: {0}]")]
+    InvalidUrl(String),
+    #[error("Credentials are missing [variable: {0}]")]
+    MissingCredentials(String),
+    #[error("The worksheet can't be found in the exported file [name: {0}]")]
+    WorksheetNotFound(String),
+    #[error("A required field of the member is missing [field: {0}]")]
+    MissingMemberField(String),
+    #[error("The session cookies can't be saved")]
+    CookiePersistenceFailed(#[source] Arc<dyn std::error::Error + Send + Sync>),
+    #[error("UDA is rate limiting requests [retry_after: {retry_after:?}]")]
+    RateLimited { retry_after: Option<Duration> },
+    #[error("The feature isn't available on this UDA instance, it may run an unsupported version [feature: {0}]")]
+    FeatureNotAvailable(String),
+    #[error("The UDA instance is under maintenance")]
+    InstanceUnavailable,
+    #[error("UDA rejected the authenticity token of the sign-in form")]
+    InvalidAuthenticityToken,
+    #[error("The exported CSV file is malformed")]
+    MalformedCsvFile(#[source] Arc<csv::Error>),
+    #[error("The workbook is password protected, it must be exported without protection")]
+    ProtectedWorkbook,
+    #[error("UDA answered with an unexpected content type instead of an export [content_type: {0}]")]
+    UnexpectedContentType(String),
+    #[error("UDA keeps failing to answer, calls are suspended for a while [retry_after: {retry_after:?}]")]
+    CircuitOpen { retry_after: Duration },
+    #[error("The operation has been cancelled")]
+    Cancelled,
+}
+
+impl UdaError {
+    /// Whether the same request may succeed if sent again later,
+    /// i.e. the error comes from the network, from an overloaded or rate-limiting server.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            UdaError::ConnectionFailed(_)
+            | UdaError::CantReadPageContent(_)
+            | UdaError::OrganizationMembershipsAccessFailed(_)
+            | UdaError::RateLimited { .. }
+            | UdaError::InstanceUnavailable
+            | UdaError::InvalidAuthenticityToken
+            | UdaError::CircuitOpen { .. } => true,
+            UdaError::UnexpectedStatus(status) => status.is_server_error(),
+            UdaError::LackOfPermissions
+            | UdaError::WrongCredentials
+            | UdaError::AccountLocked
+            | UdaError::MalformedSelector(_)
+            | UdaError::MemberConfirmationFailed(_)
+            | UdaError::MalformedXlsFile(_)
+            | UdaError::SessionExpired
+            | UdaError::MemberSerializationFailed(_)
+            | UdaError::InvalidUrl(_)
+            | UdaError::MissingCredentials(_)
+            | UdaError::WorksheetNotFound(_)
+            | UdaError::MissingMemberField(_)
+            | UdaError::CookiePersistenceFailed(_)
+            | UdaError::FeatureNotAvailable(_)
+            | UdaError::MalformedCsvFile(_)
+            | UdaError::ProtectedWorkbook
+            | UdaError::UnexpectedContentType(_)
+            | UdaError::Cancelled => false,
+        }
+    }
+
+    /// Wrap a failure of the HTTP client into a connection failure, keeping it as the source.
+    pub fn connection(error: reqwest::Error) -> Self {
+        ConnectionFailed(Some(Arc::new(error)))
+    }
 }
 
 impl From<SelectorErrorKind<'_>> for UdaError {
@@ -30,19 +114,219 @@ impl From<SelectorErrorKind<'_>> for UdaError {
     }
 }
 
-pub fn log_error_and_return<E: Debug, T>(value_to_return: T) -> impl FnOnce(E) -> T {
+/// Failures of the HTTP client are considered connection failures, see [`UdaError::connection`].
+/// Nothing is logged: wrap the conversion into [`log_error_and_return`] to do so.
+impl From<reqwest::Error> for UdaError {
+    fn from(value: reqwest::Error) -> Self {
+        UdaError::connection(value)
+    }
+}
+
+/// Spreadsheets that can't be read are considered malformed, the error being kept as the source.
+/// Password protection is told apart, as it can be fixed by exporting again.
+impl From<calamine::Error> for UdaError {
+    fn from(value: calamine::Error) -> Self {
+        match value {
+            calamine::Error::Xls(XlsError::Password)
+            | calamine::Error::Xlsx(XlsxError::Password)
+            | calamine::Error::Xlsb(XlsbError::Password)
+            | calamine::Error::Ods(OdsError::Password) => ProtectedWorkbook,
+            error => MalformedXlsFile(Some(Arc::new(error))),
+        }
+    }
+}
+
+/// Log the error, then wrap it into the value to return so that it stays available as a source.
+pub fn log_error_and_return<E: Debug, T>(wrap: impl FnOnce(E) -> T) -> impl FnOnce(E) -> T {
     |e| {
         error!("{e:#?}");
-        value_to_return
+        wrap(e)
     }
 }
 
+/// Log the message along with the error, then wrap the error into the value to return.
 pub fn log_message_and_return<E: Debug, T>(
     message: &str,
-    value_to_return: T,
+    wrap: impl FnOnce(E) -> T,
 ) -> impl FnOnce(E) -> T {
     move |e| {
         error!("{message}\n{e:#?}");
-        value_to_return
+        wrap(e)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    mod is_retryable {
+        use crate::error::UdaError;
+        use reqwest::StatusCode;
+        use std::time::Duration;
+
+        #[test]
+        fn retry_transient_errors() {
+            let reqwest_error = reqwest::Client::new().get("not a url").build().unwrap_err();
+
+            assert!(UdaError::ConnectionFailed(None).is_retryable());
+            assert!(UdaError::CantReadPageContent(None).is_retryable());
+            assert!(
+                UdaError::OrganizationMembershipsAccessFailed(reqwest_error.into()).is_retryable()
+            );
+            assert!(UdaError::RateLimited { retry_after: None }.is_retryable());
+            assert!(UdaError::InstanceUnavailable.is_retryable());
+            assert!(UdaError::InvalidAuthenticityToken.is_retryable());
+            assert!(UdaError::CircuitOpen {
+                retry_after: Duration::from_secs(30)
+            }
+            .is_retryable());
+            assert!(UdaError::UnexpectedStatus(StatusCode::SERVICE_UNAVAILABLE).is_retryable());
+        }
+
+        #[test]
+        fn not_retry_permanent_errors() {
+            let serde_error = serde_json::from_str::<u8>("not a number").unwrap_err();
+
+            assert!(!UdaError::LackOfPermissions.is_retryable());
+            assert!(!UdaError::WrongCredentials.is_retryable());
+            assert!(!UdaError::AccountLocked.is_retryable());
+            assert!(!UdaError::MalformedSelector("td".to_owned()).is_retryable());
+            assert!(!UdaError::MemberConfirmationFailed(1).is_retryable());
+            assert!(!UdaError::MalformedXlsFile(None).is_retryable());
+            assert!(!UdaError::SessionExpired.is_retryable());
+            assert!(!UdaError::MemberSerializationFailed(serde_error.into()).is_retryable());
+            assert!(!UdaError::UnexpectedStatus(StatusCode::NOT_FOUND).is_retryable());
+            assert!(!UdaError::InvalidUrl("url".to_owned()).is_retryable());
+            assert!(!UdaError::MissingCredentials("UDA_URL".to_owned()).is_retryable());
+            assert!(!UdaError::WorksheetNotFound("Members".to_owned()).is_retryable());
+            assert!(!UdaError::ProtectedWorkbook.is_retryable());
+            assert!(!UdaError::UnexpectedContentType("text/html".to_owned()).is_retryable());
+            assert!(!UdaError::MissingMemberField("id".to_owned()).is_retryable());
+            assert!(
+                !UdaError::FeatureNotAvailable("organization_memberships".to_owned())
+                    .is_retryable()
+            );
+        }
+    }
+
+    mod clone {
+        use crate::error::UdaError;
+        use reqwest::StatusCode;
+        use std::error::Error;
+        use std::sync::{mpsc, Arc};
+        use std::thread;
+        use std::time::Duration;
+
+        fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+        fn all_variants() -> Vec<UdaError> {
+            let reqwest_error = reqwest::Client::new().get("not a url").build().unwrap_err();
+            let serde_error = serde_json::from_str::<u8>("not a number").unwrap_err();
+            let csv_error = csv::Reader::from_reader("id\nnot a number".as_bytes())
+                .deserialize::<u8>()
+                .find_map(Result::err)
+                .unwrap();
+            let io_error = std::io::Error::other("disk full");
+
+            vec![
+                UdaError::ConnectionFailed(Some(reqwest_error.into())),
+                UdaError::CantReadPageContent(None),
+                UdaError::OrganizationMembershipsAccessFailed(
+                    reqwest::Client::new()
+                        .get("not a url")
+                        .build()
+                        .unwrap_err()
+                        .into(),
+                ),
+                UdaError::LackOfPermissions,
+                UdaError::WrongCredentials,
+                UdaError::AccountLocked,
+                UdaError::MalformedSelector("td".to_owned()),
+                UdaError::MemberConfirmationFailed(1),
+                UdaError::MalformedXlsFile(None),
+                UdaError::SessionExpired,
+                UdaError::MemberSerializationFailed(serde_error.into()),
+                UdaError::UnexpectedStatus(StatusCode::NOT_FOUND),
+                UdaError::InvalidUrl("url".to_owned()),
+                UdaError::MissingCredentials("UDA_URL".to_owned()),
+                UdaError::WorksheetNotFound("Members".to_owned()),
+                UdaError::MissingMemberField("id".to_owned()),
+                UdaError::CookiePersistenceFailed(Arc::new(io_error)),
+                UdaError::RateLimited {
+                    retry_after: Some(Duration::from_secs(1)),
+                },
+                UdaError::FeatureNotAvailable("organization_memberships".to_owned()),
+                UdaError::InstanceUnavailable,
+                UdaError::InvalidAuthenticityToken,
+                UdaError::MalformedCsvFile(csv_error.into()),
+                UdaError::ProtectedWorkbook,
+                UdaError::UnexpectedContentType("text/html".to_owned()),
+                UdaError::CircuitOpen {
+                    retry_after: Duration::from_secs(30),
+                },
+                UdaError::Cancelled,
+            ]
+        }
+
+        #[test]
+        fn send_clones_across_threads() {
+            assert_send_sync::<UdaError>();
+
+            let errors = all_variants();
+            let (sender, receiver) = mpsc::channel();
+            let clones = errors.clone();
+            thread::spawn(move || {
+                for error in clones {
+                    sender.send(error).unwrap();
+                }
+            })
+            .join()
+            .unwrap();
+
+            let received = receiver.iter().collect::<Vec<_>>();
+            assert_eq!(errors.len(), received.len());
+            for (error, received) in errors.iter().zip(&received) {
+                assert_eq!(error.to_string(), received.to_string());
+                assert_eq!(
+                    error.source().map(ToString::to_string),
+                    received.source().map(ToString::to_string)
+                );
+            }
+        }
+    }
+
+    mod from {
+        use crate::error::UdaError;
+        use calamine::XlsxError;
+        use std::error::Error;
+
+        #[test]
+        fn convert_reqwest_error_into_connection_failed() {
+            let reqwest_error = reqwest::Client::new().get("not a url").build().unwrap_err();
+            let message = reqwest_error.to_string();
+
+            let error = UdaError::from(reqwest_error);
+
+            let UdaError::ConnectionFailed(Some(source)) = &error else {
+                panic!("Unexpected error: {error:?}");
+            };
+            assert!(source.is_builder());
+            assert_eq!(message, error.source().unwrap().to_string());
+        }
+
+        #[test]
+        fn convert_calamine_error_into_malformed_xls_file() {
+            let calamine_error = calamine::Error::Msg("not a workbook");
+
+            let error = UdaError::from(calamine_error);
+
+            assert!(matches!(error, UdaError::MalformedXlsFile(Some(_))));
+            assert_eq!("not a workbook", error.source().unwrap().to_string());
+        }
+
+        #[test]
+        fn convert_password_error_into_protected_workbook() {
+            let error = UdaError::from(calamine::Error::Xlsx(XlsxError::Password));
+
+            assert!(matches!(error, UdaError::ProtectedWorkbook));
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use crate::credentials::UdaCredentials;
+use crate::login::setup_authentication;
+use crate::retrieve_members::setup_member_retrieval;
+use uda_dto::uda_member::UdaMember;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Priority of the mocks simulating failures, so that they take precedence over the working ones.
+const FAILURE_PRIORITY: u8 = 1;
+
+const MAINTENANCE_PAGE: &str = r#"<!DOCTYPE html><html><head><title>Registration</title></head><body><h1>Site Maintenance</h1><p>The registration site is currently down for scheduled maintenance.</p></body></html>"#;
+
+/// A fake UDA instance, for tests to log into and retrieve members from.
+/// It works out of the box, failures being simulated on demand, e.g.
+/// `FakeUda::start().await.with_export_failure(500).await`.
+pub struct FakeUda {
+    mock_server: MockServer,
+    credentials: UdaCredentials,
+    expected_members: Vec<UdaMember>,
+}
+
+impl FakeUda {
+    /// Start an instance accepting the credentials it provides and exporting the test members.
+    pub async fn start() -> Self {
+        let mock_server = MockServer::start().await;
+        let credentials = setup_authentication(&mock_server).await;
+        let expected_members = setup_member_retrieval(&mock_server).await;
+
+        Self {
+            mock_server,
+            credentials,
+            expected_members,
+        }
+    }
+
+    /// Reject any credentials, as UDA does with a flash message.
+    pub async fn with_wrong_credentials(self) -> Self {
+        Mock::given(method("POST"))
+            .and(path("/en/users/sign_in"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "<html><body>Invalid User Account Email or password</body></html>",
+            ))
+            .with_priority(FAILURE_PRIORITY)
+            .mount(&self.mock_server)
+            .await;
+        self
+    }
+
+    /// Answer requests for the members export with the given status.
+    pub async fn with_export_failure(self, status: u16) -> Self {
+        Mock::given(method("GET"))
+            .and(path("/en/organization_memberships/export.xls"))
+            .respond_with(ResponseTemplate::new(status))
+            .with_priority(FAILURE_PRIORITY)
+            .mount(&self.mock_server)
+            .await;
+        self
+    }
+
+    /// Serve the maintenance page instead of the sign-in form.
+    pub async fn with_maintenance(self) -> Self {
+        Mock::given(method("GET"))
+            .and(path("/en/users/sign_in"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(MAINTENANCE_PAGE))
+            .with_priority(FAILURE_PRIORITY)
+            .mount(&self.mock_server)
+            .await;
+        self
+    }
+
+    pub fn uri(&self) -> String {
+        self.mock_server.uri()
+    }
+
+    /// Credentials the instance accepts, unless told otherwise.
+    pub fn credentials(&self) -> &UdaCredentials {
+        &self.credentials
+    }
+
+    /// Members the instance exports.
+    pub fn expected_members(&self) -> &[UdaMember] {
+        &self.expected_members
+    }
+
+    /// Underlying server, e.g. to mount mocks for other endpoints or inspect received requests.
+    pub fn mock_server(&self) -> &MockServer {
+        &self.mock_server
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod fake_uda {
+        use crate::error::UdaError;
+        use crate::fake_uda::FakeUda;
+        use crate::session::UdaSession;
+
+        async fn authenticate(fake_uda: &FakeUda) -> crate::Result<UdaSession> {
+            let credentials = fake_uda.credentials();
+            UdaSession::authenticate(
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+        }
+
+        #[tokio::test]
+        async fn success() {
+            let fake_uda = FakeUda::start().await;
+
+            let members = authenticate(&fake_uda)
+                .await
+                .unwrap()
+                .retrieve_members()
+                .await
+                .unwrap();
+
+            assert_eq!(fake_uda.expected_members(), members);
+        }
+
+        #[tokio::test]
+        async fn fail_when_wrong_credentials() {
+            let fake_uda = FakeUda::start().await.with_wrong_credentials().await;
+
+            let error = authenticate(&fake_uda).await.unwrap_err();
+
+            assert!(matches!(error, UdaError::WrongCredentials));
+        }
+
+        #[tokio::test]
+        async fn fail_when_export_fails() {
+            let fake_uda = FakeUda::start().await.with_export_failure(500).await;
+
+            let error = authenticate(&fake_uda)
+                .await
+                .unwrap()
+                .retrieve_members()
+                .await
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::UnexpectedStatus(status) if status == 500));
+        }
+
+        #[tokio::test]
+        async fn fail_when_under_maintenance() {
+            let fake_uda = FakeUda::start().await.with_maintenance().await;
+
+            let error = authenticate(&fake_uda).await.unwrap_err();
+
+            assert!(matches!(error, UdaError::InstanceUnavailable));
+        }
+    }
+}
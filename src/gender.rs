@@ -0,0 +1,119 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::convert::Infallible;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Gender of a registrant, many events being split by gender.
+/// UDA exports it inconsistently, e.g. `M`, `Male` or `male`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+pub enum Gender {
+    Male,
+    Female,
+    Other,
+    /// Missing or unrecognized gender.
+    #[default]
+    Unknown,
+}
+
+impl From<&str> for Gender {
+    /// Unrecognized values are read as [`Gender::Unknown`] rather than rejected,
+    /// so that a single malformed cell doesn't cost the whole row.
+    fn from(value: &str) -> Self {
+        match value.trim().to_lowercase().as_str() {
+            "m" | "male" => Gender::Male,
+            "f" | "female" => Gender::Female,
+            "x" | "other" => Gender::Other,
+            _ => Gender::Unknown,
+        }
+    }
+}
+
+impl FromStr for Gender {
+    type Err = Infallible;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(Gender::from(value))
+    }
+}
+
+impl<'de> Deserialize<'de> for Gender {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Option::<String>::deserialize(deserializer)?;
+        Ok(value.as_deref().map(Gender::from).unwrap_or_default())
+    }
+}
+
+impl Display for Gender {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Gender::Male => "Male",
+            Gender::Female => "Female",
+            Gender::Other => "Other",
+            Gender::Unknown => "Unknown",
+        };
+        write!(f, "{label}")
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod from_str {
+        use crate::gender::Gender;
+
+        #[test]
+        fn parse_male() {
+            for value in ["M", "m", "Male", "male", "MALE", " Male "] {
+                assert_eq!(Gender::Male, value.parse().unwrap(), "{value}");
+            }
+        }
+
+        #[test]
+        fn parse_female() {
+            for value in ["F", "f", "Female", "female", "FEMALE"] {
+                assert_eq!(Gender::Female, value.parse().unwrap(), "{value}");
+            }
+        }
+
+        #[test]
+        fn parse_other() {
+            for value in ["X", "x", "Other", "other"] {
+                assert_eq!(Gender::Other, value.parse().unwrap(), "{value}");
+            }
+        }
+
+        #[test]
+        fn parse_unknown() {
+            for value in ["", "  ", "?", "Mixed"] {
+                assert_eq!(Gender::Unknown, value.parse().unwrap(), "{value}");
+            }
+        }
+    }
+
+    mod deserialize {
+        use crate::gender::Gender;
+
+        #[test]
+        fn deserialize_spelling_variants() {
+            assert_eq!(
+                Gender::Male,
+                serde_json::from_str::<Gender>(r#""m""#).unwrap()
+            );
+            assert_eq!(
+                Gender::Female,
+                serde_json::from_str::<Gender>(r#""Female""#).unwrap()
+            );
+        }
+
+        #[test]
+        fn deserialize_missing_as_unknown() {
+            assert_eq!(
+                Gender::Unknown,
+                serde_json::from_str::<Gender>("null").unwrap()
+            );
+            assert_eq!(
+                Gender::Unknown,
+                serde_json::from_str::<Gender>(r#""""#).unwrap()
+            );
+        }
+    }
+}
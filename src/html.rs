@@ -0,0 +1,55 @@
+use scraper::{Html, Selector};
+
+/// Items of the list Rails renders above a form it rejected, e.g. `Email can't be blank`.
+const RAILS_ERRORS_SELECTOR: &str = "#error_explanation li";
+
+/// Messages of the errors Rails lists on the page answering a rejected form,
+/// e.g. to tell users why a POST failed. Empty when the page lists none.
+pub fn extract_rails_errors(document: &Html) -> Vec<String> {
+    let Ok(selector) = Selector::parse(RAILS_ERRORS_SELECTOR) else {
+        return vec![];
+    };
+    document
+        .select(&selector)
+        .map(|item| {
+            item.text()
+                .collect::<String>()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|message| !message.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod extract_rails_errors {
+        use crate::html::extract_rails_errors;
+        use scraper::Html;
+
+        #[test]
+        fn success() {
+            let document = Html::parse_document(
+                r#"<html><body><div id="error_explanation"><h2>2 errors prohibited this user from being saved:</h2><ul><li>Email can't be blank</li><li>Password
+                is too short (minimum is 8 characters)</li></ul></div></body></html>"#,
+            );
+
+            assert_eq!(
+                vec![
+                    "Email can't be blank".to_owned(),
+                    "Password is too short (minimum is 8 characters)".to_owned()
+                ],
+                extract_rails_errors(&document)
+            );
+        }
+
+        #[test]
+        fn empty_when_no_errors() {
+            let document =
+                Html::parse_document("<html><body><ul><li>Not an error</li></ul></body></html>");
+
+            assert!(extract_rails_errors(&document).is_empty());
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::num::ParseIntError;
+use std::str::FromStr;
+use uda_dto::uda_member::UdaMember;
+
+/// Id of a competition on UDA, as found in its URLs, e.g. `/en/competitions/12`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CompetitionId(pub u32);
+
+/// Id of an event of a competition, e.g. the 100m of a convention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct EventId(pub u32);
+
+/// Id of a member, shared with their registrant page, e.g. `/en/registrants/42`.
+/// Same as [`UdaMember::id`], which stays a bare integer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MemberId(pub u16);
+
+/// Id of an organization a UDA admin belongs to, e.g. `/en/organizations/7`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct OrganizationId(pub u32);
+
+impl From<u32> for CompetitionId {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<CompetitionId> for u32 {
+    fn from(value: CompetitionId) -> Self {
+        value.0
+    }
+}
+
+impl FromStr for CompetitionId {
+    type Err = ParseIntError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        value.trim().parse().map(Self)
+    }
+}
+
+impl Display for CompetitionId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<u32> for EventId {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<EventId> for u32 {
+    fn from(value: EventId) -> Self {
+        value.0
+    }
+}
+
+impl FromStr for EventId {
+    type Err = ParseIntError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        value.trim().parse().map(Self)
+    }
+}
+
+impl Display for EventId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<u16> for MemberId {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&UdaMember> for MemberId {
+    fn from(member: &UdaMember) -> Self {
+        Self(*member.id())
+    }
+}
+
+impl From<MemberId> for u16 {
+    fn from(value: MemberId) -> Self {
+        value.0
+    }
+}
+
+impl FromStr for MemberId {
+    type Err = ParseIntError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        value.trim().parse().map(Self)
+    }
+}
+
+impl Display for MemberId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<u32> for OrganizationId {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<OrganizationId> for u32 {
+    fn from(value: OrganizationId) -> Self {
+        value.0
+    }
+}
+
+impl FromStr for OrganizationId {
+    type Err = ParseIntError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        value.trim().parse().map(Self)
+    }
+}
+
+impl Display for OrganizationId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod serialize {
+        use crate::ids::{CompetitionId, EventId, MemberId, OrganizationId};
+
+        #[test]
+        fn serialize_as_bare_integers() {
+            assert_eq!("12", serde_json::to_string(&CompetitionId(12)).unwrap());
+            assert_eq!("4", serde_json::to_string(&EventId(4)).unwrap());
+            assert_eq!("42", serde_json::to_string(&MemberId(42)).unwrap());
+            assert_eq!("7", serde_json::to_string(&OrganizationId(7)).unwrap());
+        }
+
+        #[test]
+        fn deserialize_bare_integers() {
+            assert_eq!(
+                CompetitionId(12),
+                serde_json::from_str::<CompetitionId>("12").unwrap()
+            );
+            assert_eq!(EventId(4), serde_json::from_str::<EventId>("4").unwrap());
+            assert_eq!(
+                MemberId(42),
+                serde_json::from_str::<MemberId>("42").unwrap()
+            );
+            assert_eq!(
+                OrganizationId(7),
+                serde_json::from_str::<OrganizationId>("7").unwrap()
+            );
+        }
+    }
+
+    mod from_str {
+        use crate::ids::{CompetitionId, EventId, MemberId, OrganizationId};
+
+        #[test]
+        fn parse_ids() {
+            assert_eq!(CompetitionId(12), " 12 ".parse().unwrap());
+            assert_eq!(EventId(4), "4".parse().unwrap());
+            assert_eq!(MemberId(42), "42".parse().unwrap());
+            assert_eq!(OrganizationId(7), "7".parse().unwrap());
+        }
+
+        #[test]
+        fn reject_invalid_ids() {
+            assert!("twelve".parse::<CompetitionId>().is_err());
+            assert!("-4".parse::<EventId>().is_err());
+            // Member ids don't go beyond u16
+            assert!("70000".parse::<MemberId>().is_err());
+        }
+
+        #[test]
+        fn display_as_parsed() {
+            assert_eq!("12", CompetitionId(12).to_string());
+            assert_eq!("4", EventId(4).to_string());
+            assert_eq!("42", MemberId(42).to_string());
+            assert_eq!("7", OrganizationId(7).to_string());
+        }
+    }
+
+    mod member_id {
+        use crate::ids::MemberId;
+        use crate::uda_member_builder::UdaMemberBuilder;
+
+        #[test]
+        fn round_trip_member_id() {
+            let member = UdaMemberBuilder::new()
+                .id(42)
+                .first_name("Jon")
+                .last_name("Doe")
+                .email("jon@doe.com")
+                .build()
+                .unwrap();
+
+            let id = MemberId::from(&member);
+
+            assert_eq!(MemberId(42), id);
+            assert_eq!(*member.id(), u16::from(id));
+        }
+    }
+}
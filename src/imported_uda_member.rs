@@ -1,7 +1,93 @@
+use crate::membership_number::{MembershipNumber, MembershipNumberFormat};
+use crate::spreadsheet::HeaderAliases;
+use crate::uda_member_builder::required_field;
+use crate::Result;
+use chrono::NaiveDate;
 use derive_getters::Getters;
+use log::{debug, warn};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::LazyLock;
 use uda_dto::uda_member::UdaMember;
 
+/// Format of the birthdays exported by UDA, e.g. `01.02.1983`.
+const BIRTHDAY_FORMAT: &str = "%d.%m.%Y";
+
+/// ISO 3166 alpha-2 codes of the countries some instances export by name, in English or French.
+const COUNTRY_CODES: &[(&str, &str)] = &[
+    ("algeria", "DZ"),
+    ("algérie", "DZ"),
+    ("australia", "AU"),
+    ("australie", "AU"),
+    ("austria", "AT"),
+    ("autriche", "AT"),
+    ("belgium", "BE"),
+    ("belgique", "BE"),
+    ("brazil", "BR"),
+    ("brésil", "BR"),
+    ("canada", "CA"),
+    ("china", "CN"),
+    ("chine", "CN"),
+    ("denmark", "DK"),
+    ("danemark", "DK"),
+    ("finland", "FI"),
+    ("finlande", "FI"),
+    ("france", "FR"),
+    ("germany", "DE"),
+    ("allemagne", "DE"),
+    ("hong kong", "HK"),
+    ("italy", "IT"),
+    ("italie", "IT"),
+    ("japan", "JP"),
+    ("japon", "JP"),
+    ("netherlands", "NL"),
+    ("pays-bas", "NL"),
+    ("new zealand", "NZ"),
+    ("nouvelle-zélande", "NZ"),
+    ("norway", "NO"),
+    ("norvège", "NO"),
+    ("south korea", "KR"),
+    ("corée du sud", "KR"),
+    ("spain", "ES"),
+    ("espagne", "ES"),
+    ("sweden", "SE"),
+    ("suède", "SE"),
+    ("switzerland", "CH"),
+    ("suisse", "CH"),
+    ("united kingdom", "GB"),
+    ("royaume-uni", "GB"),
+    ("united states", "US"),
+    ("united states of america", "US"),
+    ("états-unis", "US"),
+];
+
+/// International calling codes of the countries listed above, by ISO 3166 alpha-2 code.
+const CALLING_CODES: &[(&str, &str)] = &[
+    ("AT", "43"),
+    ("AU", "61"),
+    ("BE", "32"),
+    ("BR", "55"),
+    ("CA", "1"),
+    ("CH", "41"),
+    ("CN", "86"),
+    ("DE", "49"),
+    ("DK", "45"),
+    ("DZ", "213"),
+    ("ES", "34"),
+    ("FI", "358"),
+    ("FR", "33"),
+    ("GB", "44"),
+    ("HK", "852"),
+    ("IT", "39"),
+    ("JP", "81"),
+    ("KR", "82"),
+    ("NL", "31"),
+    ("NO", "47"),
+    ("NZ", "64"),
+    ("SE", "46"),
+    ("US", "1"),
+];
+
 #[derive(Debug, Getters, Serialize, Deserialize, PartialEq)]
 pub struct ImportedUdaMember {
     #[serde(rename = "Id")]
@@ -36,13 +122,421 @@ pub struct ImportedUdaMember {
     confirmed: bool,
 }
 
+/// Where to send mail to a member, e.g. a participant pack.
+#[derive(Debug, Clone, PartialEq, Getters)]
+pub struct PostalAddress {
+    address: String,
+    city: String,
+    /// State, province or region, which not every country uses.
+    region: Option<String>,
+    zip_code: String,
+    /// ISO 3166 alpha-2 code, as given by [`ImportedUdaMember::country_code`].
+    country: String,
+}
+
+/// Fields of an [`ImportedUdaMember`], whose columns some UDA versions export under other headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Field {
+    Id,
+    ManualOrganizationMembership,
+    SystemOrganizationMembership,
+    FirstName,
+    LastName,
+    Birthday,
+    AddressLine,
+    City,
+    State,
+    Zip,
+    Country,
+    Phone,
+    Email,
+    Club,
+    Confirmed,
+}
+
+impl Field {
+    /// Header the field is deserialized from, as named by the `serde` attributes.
+    fn header(self) -> &'static str {
+        match self {
+            Field::Id => "Id",
+            Field::ManualOrganizationMembership => "Manual Organization Membership#",
+            Field::SystemOrganizationMembership => "System Organization Membership#",
+            Field::FirstName => "First Name",
+            Field::LastName => "Last Name",
+            Field::Birthday => "Birthday",
+            Field::AddressLine => "Address Line1",
+            Field::City => "City",
+            Field::State => "State",
+            Field::Zip => "Zip",
+            Field::Country => "Country",
+            Field::Phone => "Phone",
+            Field::Email => "Email",
+            Field::Club => "Club",
+            Field::Confirmed => "Confirmed already a member",
+        }
+    }
+}
+
+/// Headers the fields have been exported under across UDA versions, in lowercase.
+static HEADER_ALIASES: LazyLock<HashMap<&str, Field>> = LazyLock::new(|| {
+    HashMap::from([
+        ("id", Field::Id),
+        ("member id", Field::Id),
+        (
+            "manual organization membership#",
+            Field::ManualOrganizationMembership,
+        ),
+        (
+            "manual organization membership #",
+            Field::ManualOrganizationMembership,
+        ),
+        (
+            "manual organization membership number",
+            Field::ManualOrganizationMembership,
+        ),
+        (
+            "system organization membership#",
+            Field::SystemOrganizationMembership,
+        ),
+        (
+            "system organization membership #",
+            Field::SystemOrganizationMembership,
+        ),
+        (
+            "system organization membership number",
+            Field::SystemOrganizationMembership,
+        ),
+        ("first name", Field::FirstName),
+        ("firstname", Field::FirstName),
+        ("last name", Field::LastName),
+        ("lastname", Field::LastName),
+        ("birthday", Field::Birthday),
+        ("birthdate", Field::Birthday),
+        ("birth date", Field::Birthday),
+        ("date of birth", Field::Birthday),
+        ("address line1", Field::AddressLine),
+        ("address line 1", Field::AddressLine),
+        ("address", Field::AddressLine),
+        ("city", Field::City),
+        ("state", Field::State),
+        ("state/province", Field::State),
+        ("province", Field::State),
+        ("zip", Field::Zip),
+        ("zip code", Field::Zip),
+        ("postal code", Field::Zip),
+        ("country", Field::Country),
+        ("phone", Field::Phone),
+        ("phone number", Field::Phone),
+        ("email", Field::Email),
+        ("e-mail", Field::Email),
+        ("email address", Field::Email),
+        ("club", Field::Club),
+        ("club name", Field::Club),
+        ("confirmed already a member", Field::Confirmed),
+        ("confirmed", Field::Confirmed),
+        ("already a member", Field::Confirmed),
+    ])
+});
+
+impl HeaderAliases for ImportedUdaMember {
+    fn canonical_header(header: &str) -> Option<&'static str> {
+        HEADER_ALIASES
+            .get(header.trim().to_lowercase().as_str())
+            .map(|field| field.header())
+    }
+}
+
+impl ImportedUdaMember {
+    /// Birthday parsed as a date, or `None` when it is empty or malformed.
+    pub fn birthdate(&self) -> Option<NaiveDate> {
+        parse_birthdate(&self.birthday)
+    }
+
+    /// Country as an uppercase ISO 3166 alpha-2 code, e.g. `FR`,
+    /// whether the instance exported a code or a country name.
+    /// Unrecognized countries are kept as is.
+    pub fn country_code(&self) -> String {
+        normalize_country(&self.country)
+    }
+
+    /// Phone in the international format, e.g. `+33123456789`, using the country of the member
+    /// when the number is written in its national format.
+    /// When the number can't be normalized, the raw phone is returned as is.
+    pub fn normalized_phone(&self) -> Option<String> {
+        self.phone
+            .as_deref()
+            .map(|phone| normalize_phone(phone, &self.country_code()))
+    }
+
+    /// Membership number as exported, the manual one taking precedence over the one set by UDA
+    /// unless left blank.
+    pub fn raw_membership_number(&self) -> Option<&str> {
+        self.manual_organization_membership
+            .as_deref()
+            .filter(|membership_number| !membership_number.trim().is_empty())
+            .or(self.system_organization_membership.as_deref())
+    }
+
+    /// Membership number checked against the default format, i.e. digits only.
+    /// Invalid numbers are logged and read as `None`, the raw one staying available for auditing.
+    pub fn membership_number(&self) -> Option<MembershipNumber> {
+        self.membership_number_with_format(&MembershipNumberFormat::default())
+    }
+
+    /// Membership number checked against the given format, like [`Self::membership_number`].
+    /// When the manual number is invalid, the one set by UDA is used instead, if valid.
+    pub fn membership_number_with_format(
+        &self,
+        format: &MembershipNumberFormat,
+    ) -> Option<MembershipNumber> {
+        pick_membership_number(
+            self.id,
+            self.manual_organization_membership.as_deref(),
+            self.system_organization_membership.as_deref(),
+            format,
+        )
+    }
+
+    /// Postal fields of the member, gathered to ship them something.
+    pub fn postal_address(&self) -> PostalAddress {
+        PostalAddress {
+            address: self.address_line.clone(),
+            city: self.city.clone(),
+            region: self.state.clone(),
+            zip_code: self.zip.clone(),
+            country: self.country_code(),
+        }
+    }
+
+    pub fn builder() -> ImportedUdaMemberBuilder {
+        ImportedUdaMemberBuilder::default()
+    }
+}
+
+/// Build an [`ImportedUdaMember`] field by field, rather than through positional arguments.
+/// Optional fields default to `None` and the member to not confirmed.
+#[derive(Debug, Clone, Default)]
+pub struct ImportedUdaMemberBuilder {
+    id: Option<u16>,
+    manual_organization_membership: Option<String>,
+    system_organization_membership: Option<String>,
+    first_name: Option<String>,
+    last_name: Option<String>,
+    birthday: Option<String>,
+    address_line: Option<String>,
+    city: Option<String>,
+    state: Option<String>,
+    zip: Option<String>,
+    country: Option<String>,
+    phone: Option<String>,
+    email: Option<String>,
+    club: Option<String>,
+    confirmed: bool,
+}
+
+impl ImportedUdaMemberBuilder {
+    pub fn id(mut self, id: u16) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    pub fn manual_organization_membership(
+        mut self,
+        manual_organization_membership: impl Into<String>,
+    ) -> Self {
+        self.manual_organization_membership = Some(manual_organization_membership.into());
+        self
+    }
+
+    pub fn system_organization_membership(
+        mut self,
+        system_organization_membership: impl Into<String>,
+    ) -> Self {
+        self.system_organization_membership = Some(system_organization_membership.into());
+        self
+    }
+
+    pub fn first_name(mut self, first_name: impl Into<String>) -> Self {
+        self.first_name = Some(first_name.into());
+        self
+    }
+
+    pub fn last_name(mut self, last_name: impl Into<String>) -> Self {
+        self.last_name = Some(last_name.into());
+        self
+    }
+
+    pub fn birthday(mut self, birthday: impl Into<String>) -> Self {
+        self.birthday = Some(birthday.into());
+        self
+    }
+
+    pub fn address_line(mut self, address_line: impl Into<String>) -> Self {
+        self.address_line = Some(address_line.into());
+        self
+    }
+
+    pub fn city(mut self, city: impl Into<String>) -> Self {
+        self.city = Some(city.into());
+        self
+    }
+
+    pub fn state(mut self, state: impl Into<String>) -> Self {
+        self.state = Some(state.into());
+        self
+    }
+
+    pub fn zip(mut self, zip: impl Into<String>) -> Self {
+        self.zip = Some(zip.into());
+        self
+    }
+
+    pub fn country(mut self, country: impl Into<String>) -> Self {
+        self.country = Some(country.into());
+        self
+    }
+
+    pub fn phone(mut self, phone: impl Into<String>) -> Self {
+        self.phone = Some(phone.into());
+        self
+    }
+
+    pub fn email(mut self, email: impl Into<String>) -> Self {
+        self.email = Some(email.into());
+        self
+    }
+
+    pub fn club(mut self, club: impl Into<String>) -> Self {
+        self.club = Some(club.into());
+        self
+    }
+
+    pub fn confirmed(mut self, confirmed: bool) -> Self {
+        self.confirmed = confirmed;
+        self
+    }
+
+    /// Build the member, failing when a field UDA always exports is missing.
+    pub fn build(self) -> Result<ImportedUdaMember> {
+        Ok(ImportedUdaMember {
+            id: required_field(self.id, "id")?,
+            manual_organization_membership: self.manual_organization_membership,
+            system_organization_membership: self.system_organization_membership,
+            first_name: required_field(self.first_name, "first_name")?,
+            last_name: required_field(self.last_name, "last_name")?,
+            birthday: required_field(self.birthday, "birthday")?,
+            address_line: required_field(self.address_line, "address_line")?,
+            city: required_field(self.city, "city")?,
+            state: self.state,
+            zip: required_field(self.zip, "zip")?,
+            country: required_field(self.country, "country")?,
+            phone: self.phone,
+            email: required_field(self.email, "email")?,
+            club: self.club,
+            confirmed: self.confirmed,
+        })
+    }
+}
+
+/// Members living in the country of the given ISO 3166 alpha-2 code, e.g. `FR`, whatever its casing,
+/// e.g. for customs or visa paperwork.
+pub fn filter_by_country<'a>(
+    members: &'a [ImportedUdaMember],
+    code: &str,
+) -> Vec<&'a ImportedUdaMember> {
+    let code = code.trim();
+    members
+        .iter()
+        .filter(|member| member.country_code().eq_ignore_ascii_case(code))
+        .collect()
+}
+
+/// First valid number among the manual membership number and the one set by UDA, in that order.
+/// Blank numbers are skipped, invalid ones are logged before being skipped.
+pub(crate) fn pick_membership_number(
+    id: u16,
+    manual_organization_membership: Option<&str>,
+    system_organization_membership: Option<&str>,
+    format: &MembershipNumberFormat,
+) -> Option<MembershipNumber> {
+    [manual_organization_membership, system_organization_membership]
+        .into_iter()
+        .flatten()
+        .filter(|raw_membership_number| !raw_membership_number.trim().is_empty())
+        .find_map(|raw_membership_number| {
+            MembershipNumber::parse(raw_membership_number, format)
+                .inspect_err(|error| {
+                    warn!(
+                        "Invalid membership number. Ignoring it. [id: {id}, membership_number: {raw_membership_number}] {error}"
+                    )
+                })
+                .ok()
+        })
+}
+
+pub(crate) fn parse_birthdate(birthday: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(birthday.trim(), BIRTHDAY_FORMAT).ok()
+}
+
+fn normalize_country(country: &str) -> String {
+    let country = country.trim();
+    if country.len() == 2 && country.chars().all(|c| c.is_ascii_alphabetic()) {
+        return country.to_ascii_uppercase();
+    }
+
+    let name = country.to_lowercase();
+    match COUNTRY_CODES
+        .iter()
+        .find(|(country_name, _)| *country_name == name)
+    {
+        Some((_, code)) => (*code).to_owned(),
+        None => {
+            // Called for each lookup of the country, e.g. when filtering, so a warning would flood the logs
+            debug!("Unrecognized country. Keeping it as is. [country: {country}]");
+            country.to_owned()
+        }
+    }
+}
+
+fn normalize_phone(phone: &str, country_code: &str) -> String {
+    let digits = phone
+        .chars()
+        .filter(|c| c.is_ascii_digit())
+        .collect::<String>();
+    let is_allowed = |c: char| c.is_ascii_digit() || c.is_whitespace() || "+.-/()".contains(c);
+    if digits.is_empty() || !phone.chars().all(is_allowed) {
+        warn!("Can't normalize phone. Keeping it as is. [phone: {phone}]");
+        return phone.to_owned();
+    }
+
+    if phone.trim_start().starts_with('+') {
+        return format!("+{digits}");
+    }
+    if let Some(international_number) = digits.strip_prefix("00") {
+        return format!("+{international_number}");
+    }
+
+    match CALLING_CODES.iter().find(|(code, _)| *code == country_code) {
+        Some((_, calling_code)) => {
+            // The trunk prefix is dropped when dialing from abroad
+            let national_number = digits.strip_prefix('0').unwrap_or(&digits);
+            format!("+{calling_code}{national_number}")
+        }
+        None => {
+            warn!("Unknown calling code. Keeping phone as is. [phone: {phone}, country: {country_code}]");
+            phone.to_owned()
+        }
+    }
+}
+
 impl From<ImportedUdaMember> for UdaMember {
+    /// Invalid membership numbers are dropped, see [`ImportedUdaMember::membership_number`].
     fn from(imported_member: ImportedUdaMember) -> Self {
         UdaMember::new(
             imported_member.id,
             imported_member
-                .manual_organization_membership
-                .or(imported_member.system_organization_membership),
+                .membership_number()
+                .map(|membership_number| membership_number.to_string()),
             imported_member.first_name,
             imported_member.last_name,
             imported_member.email,
@@ -91,3 +585,364 @@ impl ImportedUdaMember {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    mod postal_address {
+        use crate::imported_uda_member::{ImportedUdaMember, PostalAddress};
+        use crate::retrieve_members::{get_test_file_content, parse_imported_members};
+
+        fn postal_address(id: u16) -> PostalAddress {
+            parse_imported_members(&get_test_file_content())
+                .unwrap()
+                .iter()
+                .find(|member| *member.id() == id)
+                .map(ImportedUdaMember::postal_address)
+                .unwrap()
+        }
+
+        #[test]
+        fn success() {
+            let address = postal_address(1);
+
+            assert_eq!("42, Le Village", address.address());
+            assert_eq!("Cartuin", address.city());
+            assert_eq!(&Some("Creuse".to_owned()), address.region());
+            assert_eq!("23340", address.zip_code());
+            assert_eq!("FR", address.country());
+        }
+
+        #[test]
+        fn normalize_country() {
+            let member = ImportedUdaMember::builder()
+                .id(1)
+                .first_name("Jon")
+                .last_name("Doe")
+                .birthday("01.02.1983")
+                .address_line("42, Le Village")
+                .city("Cartuin")
+                .zip("23340")
+                .country("France")
+                .email("jon.doe@email.com")
+                .build()
+                .unwrap();
+
+            let address = member.postal_address();
+
+            assert_eq!(&None, address.region());
+            assert_eq!("FR", address.country());
+        }
+    }
+
+    mod filter_by_country {
+        use crate::imported_uda_member::filter_by_country;
+        use crate::retrieve_members::{get_test_file_content, parse_imported_members};
+
+        fn filter_ids(code: &str) -> Vec<u16> {
+            let members = parse_imported_members(&get_test_file_content()).unwrap();
+            filter_by_country(&members, code)
+                .into_iter()
+                .map(|member| *member.id())
+                .collect()
+        }
+
+        #[test]
+        fn keep_members_of_country() {
+            assert_eq!(vec![1], filter_ids("FR"));
+            assert_eq!(vec![2], filter_ids("DZ"));
+        }
+
+        #[test]
+        fn ignore_casing() {
+            assert_eq!(vec![1], filter_ids("fr"));
+        }
+
+        #[test]
+        fn empty_when_no_member_of_country() {
+            assert!(filter_ids("JP").is_empty());
+        }
+    }
+
+    mod canonical_header {
+        use crate::imported_uda_member::ImportedUdaMember;
+        use crate::spreadsheet::HeaderAliases;
+
+        #[test]
+        fn keep_current_headers() {
+            assert_eq!(
+                Some("Birthday"),
+                ImportedUdaMember::canonical_header("Birthday")
+            );
+            assert_eq!(
+                Some("Confirmed already a member"),
+                ImportedUdaMember::canonical_header("Confirmed already a member")
+            );
+        }
+
+        #[test]
+        fn map_aliases() {
+            assert_eq!(
+                Some("Birthday"),
+                ImportedUdaMember::canonical_header("Date of birth")
+            );
+            assert_eq!(
+                Some("Zip"),
+                ImportedUdaMember::canonical_header(" POSTAL CODE ")
+            );
+        }
+
+        #[test]
+        fn none_when_unknown() {
+            assert_eq!(None, ImportedUdaMember::canonical_header("Shirt size"));
+        }
+    }
+
+    mod parse_birthdate {
+        use crate::imported_uda_member::parse_birthdate;
+        use chrono::NaiveDate;
+
+        #[test]
+        fn success() {
+            assert_eq!(
+                NaiveDate::from_ymd_opt(1983, 2, 1),
+                parse_birthdate("01.02.1983")
+            );
+        }
+
+        #[test]
+        fn none_when_empty() {
+            assert_eq!(None, parse_birthdate(""));
+        }
+
+        #[test]
+        fn none_when_malformed() {
+            assert_eq!(None, parse_birthdate("31.31.9999"));
+        }
+    }
+
+    mod builder {
+        use crate::error::UdaError;
+        use crate::imported_uda_member::ImportedUdaMember;
+
+        #[test]
+        fn success() {
+            let member = ImportedUdaMember::builder()
+                .id(1)
+                .manual_organization_membership("123456")
+                .first_name("Jon")
+                .last_name("Doe")
+                .birthday("01.02.1983")
+                .address_line("1 rue du Test")
+                .city("Paris")
+                .zip("75000")
+                .country("FR")
+                .email("jon.doe@email.com")
+                .club("Le club de test")
+                .confirmed(true)
+                .build()
+                .unwrap();
+
+            assert_eq!(
+                ImportedUdaMember::new(
+                    1,
+                    Some("123456".to_owned()),
+                    None,
+                    "Jon".to_owned(),
+                    "Doe".to_owned(),
+                    "01.02.1983".to_owned(),
+                    "1 rue du Test".to_owned(),
+                    "Paris".to_owned(),
+                    None,
+                    "75000".to_owned(),
+                    "FR".to_owned(),
+                    None,
+                    "jon.doe@email.com".to_owned(),
+                    Some("Le club de test".to_owned()),
+                    true,
+                ),
+                member
+            );
+        }
+
+        #[test]
+        fn fail_when_missing_required_field() {
+            let error = ImportedUdaMember::builder()
+                .id(1)
+                .first_name("Jon")
+                .last_name("Doe")
+                .build()
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::MissingMemberField(field) if field == "birthday"));
+        }
+    }
+
+    mod normalize_country {
+        use crate::imported_uda_member::normalize_country;
+
+        #[test]
+        fn keep_uppercase_code() {
+            assert_eq!("DZ", normalize_country("DZ"));
+        }
+
+        #[test]
+        fn uppercase_lowercase_code() {
+            assert_eq!("FR", normalize_country("fr"));
+        }
+
+        #[test]
+        fn map_country_name() {
+            assert_eq!("FR", normalize_country("France"));
+            assert_eq!("CA", normalize_country(" canada "));
+        }
+
+        #[test]
+        fn keep_unknown_country() {
+            assert_eq!("Atlantis", normalize_country("Atlantis"));
+        }
+    }
+
+    mod normalize_phone {
+        use crate::imported_uda_member::normalize_phone;
+
+        #[test]
+        fn normalize_french_number() {
+            assert_eq!("+33123456789", normalize_phone("0123456789", "FR"));
+            assert_eq!("+33123456789", normalize_phone("01 23 45 67 89", "FR"));
+            assert_eq!("+33123456789", normalize_phone("01.23.45.67.89", "FR"));
+        }
+
+        #[test]
+        fn normalize_algerian_number() {
+            assert_eq!("+213987654321", normalize_phone("987654321", "DZ"));
+            assert_eq!("+213987654321", normalize_phone("0987654321", "DZ"));
+        }
+
+        #[test]
+        fn keep_international_number() {
+            assert_eq!("+33123456789", normalize_phone("+33 1 23 45 67 89", "DZ"));
+            assert_eq!("+33123456789", normalize_phone("0033123456789", "DZ"));
+        }
+
+        #[test]
+        fn keep_raw_phone_when_impossible() {
+            assert_eq!("0123456789", normalize_phone("0123456789", "Atlantis"));
+            assert_eq!("call me", normalize_phone("call me", "FR"));
+        }
+    }
+
+    mod normalized_phone {
+        use crate::imported_uda_member::ImportedUdaMember;
+
+        fn member(country: &str, phone: Option<&str>) -> ImportedUdaMember {
+            let builder = ImportedUdaMember::builder()
+                .id(1)
+                .first_name("Jon")
+                .last_name("Doe")
+                .birthday("01.02.1983")
+                .address_line("Somewhere")
+                .city("Paris")
+                .zip("75000")
+                .country(country)
+                .email("jon.doe@email.com");
+            match phone {
+                Some(phone) => builder.phone(phone),
+                None => builder,
+            }
+            .build()
+            .unwrap()
+        }
+
+        #[test]
+        fn use_country_of_member() {
+            let member = member("France", Some("0123456789"));
+
+            assert_eq!(Some("+33123456789".to_owned()), member.normalized_phone());
+            assert_eq!(&Some("0123456789".to_owned()), member.phone());
+        }
+
+        #[test]
+        fn none_when_no_phone() {
+            assert_eq!(None, member("France", None).normalized_phone());
+        }
+    }
+
+    mod membership_number {
+        use crate::imported_uda_member::ImportedUdaMember;
+        use crate::membership_number::{MembershipNumberCharset, MembershipNumberFormat};
+        use uda_dto::uda_member::UdaMember;
+
+        fn member(membership_number: &str) -> ImportedUdaMember {
+            ImportedUdaMember::builder()
+                .id(1)
+                .manual_organization_membership(membership_number)
+                .first_name("Jon")
+                .last_name("Doe")
+                .birthday("01.02.1983")
+                .address_line("Somewhere")
+                .city("Paris")
+                .zip("75000")
+                .country("France")
+                .email("jon.doe@email.com")
+                .build()
+                .unwrap()
+        }
+
+        #[test]
+        fn keep_valid_number() {
+            let member = member("012048");
+
+            assert_eq!("012048", member.membership_number().unwrap().as_str());
+            let member = UdaMember::from(member);
+            assert_eq!(&Some("012048".to_owned()), member.membership_number());
+        }
+
+        #[test]
+        fn drop_alphabetic_value() {
+            let member = member("ID #012048");
+
+            assert_eq!(None, member.membership_number());
+            assert_eq!(Some("ID #012048"), member.raw_membership_number());
+            let member = UdaMember::from(member);
+            assert_eq!(&None, member.membership_number());
+        }
+
+        #[test]
+        fn none_when_empty() {
+            let member = member(" ");
+
+            assert_eq!(None, member.membership_number());
+            assert_eq!(&None, UdaMember::from(member).membership_number());
+        }
+
+        #[test]
+        fn fall_back_to_system_number_when_manual_one_invalid() {
+            let mut member = member("ID #012048");
+            member.system_organization_membership = Some("012049".to_owned());
+
+            assert_eq!("012049", member.membership_number().unwrap().as_str());
+            assert_eq!(Some("ID #012048"), member.raw_membership_number());
+            let member = UdaMember::from(member);
+            assert_eq!(&Some("012049".to_owned()), member.membership_number());
+        }
+
+        #[test]
+        fn fall_back_to_system_number_when_manual_one_empty() {
+            let mut member = member(" ");
+            member.system_organization_membership = Some("012049".to_owned());
+
+            assert_eq!("012049", member.membership_number().unwrap().as_str());
+            assert_eq!(Some("012049"), member.raw_membership_number());
+        }
+
+        #[test]
+        fn check_custom_format() {
+            let format = MembershipNumberFormat::new(Some(5), MembershipNumberCharset::Digits);
+
+            assert_eq!(
+                None,
+                member("012048").membership_number_with_format(&format)
+            );
+        }
+    }
+}
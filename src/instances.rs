@@ -1,9 +1,10 @@
 use crate::error::UdaError;
-use crate::error::UdaError::{CantReadPageContent, ConnectionFailed};
+use crate::error::UdaError::CantReadPageContent;
 use crate::Result;
 use log::warn;
 use reqwest::Client;
 use scraper::{ElementRef, Html, Selector};
+use std::sync::Arc;
 use uda_dto::uda_instance::Instance;
 
 /// Retrieve a list of all UDA instances.
@@ -15,13 +16,16 @@ pub async fn retrieve_uda_instances(
         .get(instances_list_url)
         .send()
         .await
-        .map_err(|_| ConnectionFailed)?;
+        .map_err(UdaError::connection)?;
     let status = response.status();
     if !status.is_success() {
-        Err(CantReadPageContent)?;
+        Err(CantReadPageContent(None))?;
     }
 
-    let body = response.text().await.map_err(|_| CantReadPageContent)?;
+    let body = response
+        .text()
+        .await
+        .map_err(|error| CantReadPageContent(Some(Arc::new(error))))?;
 
     let instances = get_uda_instances_from_html(&body)?;
     Ok(instances)
@@ -86,7 +90,7 @@ pub mod tests {
     mod retrieve_uda_instances {
         use crate::error::UdaError;
         use crate::instances::{get_expected_instances, retrieve_uda_instances, BODY};
-        use crate::tools::tests::build_client;
+        use crate::tools::build_client;
         use reqwest::header::LOCATION;
         use wiremock::matchers::{method, path};
         use wiremock::{Mock, MockServer, ResponseTemplate};
@@ -130,7 +134,7 @@ pub mod tests {
 
             println!("{error:?}");
 
-            assert!(matches!(error, UdaError::ConnectionFailed));
+            assert!(matches!(error, UdaError::ConnectionFailed(_)));
         }
 
         #[tokio::test]
@@ -149,12 +153,14 @@ pub mod tests {
                 .await
                 .unwrap_err();
 
-            assert!(matches!(error, UdaError::CantReadPageContent));
+            assert!(matches!(error, UdaError::CantReadPageContent(_)));
         }
 
         mod get_uda_instances_from_html {
-            use crate::instances::{get_expected_instances, get_uda_instances_from_html, Instance, BODY};
             use crate::instances::tests::MALFORMED_BODY;
+            use crate::instances::{
+                get_expected_instances, get_uda_instances_from_html, Instance, BODY,
+            };
 
             #[test]
             fn success() {
@@ -1,13 +1,40 @@
 use crate::error::UdaError;
 
+pub mod age_groups;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod circuit_breaker;
+pub mod clubs;
+pub mod competitions;
 pub mod configuration;
 pub mod confirm_member;
 pub mod credentials;
 pub mod error;
+#[cfg(any(test, feature = "test"))]
+pub mod fake_uda;
+pub mod gender;
+pub mod html;
+pub mod ids;
 pub mod imported_uda_member;
 pub mod instances;
+pub mod locale;
 pub mod login;
+pub mod member_display;
+pub mod member_photo;
+pub mod membership_number;
+pub mod organizations;
+pub mod payments;
+pub mod probe;
+pub mod rate_limit;
 pub mod retrieve_members;
-mod tools;
+pub mod retrieve_registrants;
+pub mod retry;
+#[cfg(any(test, feature = "test"))]
+pub mod sample_data;
+pub mod session;
+pub mod spreadsheet;
+pub mod tools;
+pub mod uda_member_builder;
+pub mod uda_registrant;
 
 pub type Result<T, E = UdaError> = std::result::Result<T, E>;
@@ -0,0 +1,147 @@
+/// Locales UDA pages can be served in.
+/// Pages are requested in this locale, and their content is expected to be translated accordingly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    Fr,
+    De,
+    Es,
+    Ja,
+}
+
+impl Locale {
+    /// Code used as the first path segment of UDA URLs.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Fr => "fr",
+            Locale::De => "de",
+            Locale::Es => "es",
+            Locale::Ja => "ja",
+        }
+    }
+
+    /// Locale matching the code, e.g. `fr` or `fr-CA`, if UDA supports it.
+    pub fn from_code(code: &str) -> Option<Self> {
+        let language = code.split(['-', '_']).next()?.trim().to_ascii_lowercase();
+        [Locale::En, Locale::Fr, Locale::De, Locale::Es, Locale::Ja]
+            .into_iter()
+            .find(|locale| locale.code() == language)
+    }
+
+    /// Message displayed by UDA once freshly logged in.
+    pub(crate) fn signed_in_marker(&self) -> &'static str {
+        match self {
+            Locale::En => "Signed in successfully",
+            Locale::Fr => "Connecté(e) avec succès",
+            Locale::De => "Erfolgreich angemeldet",
+            Locale::Es => "Sesión iniciada",
+            Locale::Ja => "ログインしました",
+        }
+    }
+
+    /// Message displayed by UDA when the session was already logged in.
+    pub(crate) fn already_signed_in_marker(&self) -> &'static str {
+        match self {
+            Locale::En => "You are already signed in",
+            Locale::Fr => "Vous êtes déjà connecté(e)",
+            Locale::De => "Sie sind bereits angemeldet",
+            Locale::Es => "Ya has iniciado sesión",
+            Locale::Ja => "すでにログインしています",
+        }
+    }
+
+    /// Message displayed by UDA when the login or the password is wrong.
+    pub(crate) fn invalid_credentials_marker(&self) -> &'static str {
+        match self {
+            Locale::En => "Invalid User Account Email or password",
+            Locale::Fr => "ou mot de passe incorrect",
+            Locale::De => "oder Passwort ist ungültig",
+            Locale::Es => "o contraseña inválidos",
+            Locale::Ja => "またはパスワードが違います",
+        }
+    }
+
+    /// Messages displayed by UDA once the account has been locked after too many failed logins,
+    /// either when signing in or when asking for the unlock instructions.
+    pub(crate) fn account_locked_markers(&self) -> &'static [&'static str] {
+        match self {
+            Locale::En => &[
+                "Your account is locked.",
+                "instructions for how to unlock your account",
+            ],
+            Locale::Fr => &[
+                "Votre compte est verrouillé.",
+                "instructions pour déverrouiller votre compte",
+            ],
+            Locale::De => &["Ihr Konto ist gesperrt.", "wie Sie Ihr Konto entsperren"],
+            Locale::Es => &[
+                "Tu cuenta está bloqueada.",
+                "instrucciones para desbloquear tu cuenta",
+            ],
+            Locale::Ja => &["アカウントはロックされています。", "アカウントのロック解除"],
+        }
+    }
+
+    /// Title of the page listing the members of the organization.
+    pub(crate) fn membership_management_title(&self) -> &'static str {
+        match self {
+            Locale::En => "Membership Management",
+            Locale::Fr => "Gestion des adhésions",
+            Locale::De => "Mitgliederverwaltung",
+            Locale::Es => "Gestión de membresías",
+            Locale::Ja => "会員管理",
+        }
+    }
+
+    /// Label of the link marking an unconfirmed member as confirmed.
+    pub(crate) fn mark_as_confirmed_label(&self) -> &'static str {
+        match self {
+            Locale::En => "Mark as confirmed",
+            Locale::Fr => "Marquer comme confirmé",
+            Locale::De => "Als bestätigt markieren",
+            Locale::Es => "Marcar como confirmado",
+            Locale::Ja => "確認済みにする",
+        }
+    }
+
+    /// Label of the link marking a confirmed member as unconfirmed.
+    pub(crate) fn mark_as_unconfirmed_label(&self) -> &'static str {
+        match self {
+            Locale::En => "Mark as unconfirmed",
+            Locale::Fr => "Marquer comme non confirmé",
+            Locale::De => "Als unbestätigt markieren",
+            Locale::Es => "Marcar como no confirmado",
+            Locale::Ja => "未確認にする",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_to_english() {
+        assert_eq!(Locale::En, Locale::default());
+        assert_eq!("en", Locale::default().code());
+    }
+
+    #[test]
+    fn get_locale_from_code() {
+        assert_eq!(Some(Locale::Fr), Locale::from_code("fr"));
+        assert_eq!(Some(Locale::De), Locale::from_code("de-CH"));
+        assert_eq!(None, Locale::from_code("pt"));
+    }
+
+    #[test]
+    fn get_french_markers() {
+        assert_eq!("fr", Locale::Fr.code());
+        assert_eq!("Connecté(e) avec succès", Locale::Fr.signed_in_marker());
+        assert_eq!(
+            "Vous êtes déjà connecté(e)",
+            Locale::Fr.already_signed_in_marker()
+        );
+    }
+}
@@ -1,115 +1,882 @@
+use crate::confirm_member::retrieve_csrf_from_html;
 #[cfg(any(test, feature = "test"))]
 use crate::credentials::UdaCredentials;
-use crate::error::{log_error_and_return, log_message_and_return};
+use crate::error::{log_error_and_return, log_message_and_return, UdaError};
+use crate::html::extract_rails_errors;
+use crate::locale::Locale;
+use crate::rate_limit::{throttle, RateLimiter};
+use crate::retrieve_members::is_sign_in_page;
+use crate::retry::{parse_retry_after, send_throttled_with_retry, RetryPolicy};
+use crate::tools::{authorize, build_client, build_url, BasicAuth};
 use crate::Result;
-use crate::UdaError::{ConnectionFailed, WrongCredentials};
-use log::{debug, error};
-use reqwest::Client;
+use crate::UdaError::{
+    AccountLocked, ConnectionFailed, InstanceUnavailable, InvalidAuthenticityToken, RateLimited,
+    UnexpectedStatus, WrongCredentials,
+};
+use derive_getters::Getters;
+use log::{debug, error, warn};
+use reqwest::header::HeaderMap;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
 use scraper::{Html, Selector};
+use std::convert::identity;
+use url::Url;
 #[cfg(any(test, feature = "test"))]
-use wiremock::matchers::{body_string, method, path};
+use wiremock::matchers::{body_string, header, method, path};
 #[cfg(any(test, feature = "test"))]
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
+/// Only pages displayed to a signed-in user include a link to sign out.
+const SIGNED_IN_MARKER: &str = "/users/sign_out";
+
+/// Elements UDA displays its flash messages in, e.g. the outcome of signing in.
+const FLASH_SELECTOR: &str = ".flash, .alert, .notice, #notice, #alert";
+
+/// Warning displayed on the last attempt before the account gets locked,
+/// which mentions the lock although the account isn't locked yet.
+const LAST_ATTEMPT_MARKER: &str = "one more attempt before your account is locked";
+
+/// Word of the splash page UDA displays instead of the sign-in form while under maintenance.
+const MAINTENANCE_MARKER: &str = "maintenance";
+
+/// Texts of the page Rails answers with when the authenticity token of a form has expired,
+/// depending on whether the exception or the production error page is shown.
+const INVALID_AUTHENTICITY_TOKEN_MARKERS: [&str; 3] = [
+    "invalidauthenticitytoken",
+    "invalid authenticity token",
+    "the change you wanted was rejected",
+];
+
+/// Where the sign-in form is, relative to the base URL of the instance.
+/// Only needed when the instance is mounted under a sub-path or behind a proxy changing its routes,
+/// e.g. `uda/en/users/sign_in` for `https://host/uda/en/users/sign_in`.
+#[derive(Debug, Clone, PartialEq, Getters)]
+pub struct LoginConfig {
+    sign_in_path: String,
+    /// Whether the sign-in form is sent with the `utf8=✓` parameter older Rails versions add to forms.
+    /// Rails 6+ no longer does, and some strict setups reject it as unexpected.
+    send_utf8_param: bool,
+    /// Whether the authenticity token is also sent in the `X-CSRF-Token` header,
+    /// as expected by builds whose controllers run in API mode.
+    send_csrf_header: bool,
+    /// Whether every hidden input of the sign-in form is sent back, rather than the authenticity token only,
+    /// as customized instances may add their own, e.g. a tenant id.
+    send_hidden_fields: bool,
+    /// Whether logging in is told successful from the sign-in form redirecting away,
+    /// rather than from flash messages only known in a few languages.
+    detect_redirect: bool,
+    /// Language the answers to the sign-in form are read in, e.g. to spot wrong credentials.
+    locale: Locale,
+}
+
+impl LoginConfig {
+    pub fn new(sign_in_path: &str) -> Self {
+        Self {
+            sign_in_path: sign_in_path.trim_matches('/').to_owned(),
+            send_utf8_param: true,
+            send_csrf_header: false,
+            send_hidden_fields: false,
+            detect_redirect: false,
+            locale: Locale::default(),
+        }
+    }
+
+    /// Send the sign-in form with or without the `utf8=✓` parameter. It is sent by default.
+    pub fn with_utf8_param(mut self, send_utf8_param: bool) -> Self {
+        self.send_utf8_param = send_utf8_param;
+        self
+    }
+
+    /// Send the authenticity token in the `X-CSRF-Token` header too, the form still holding it.
+    /// It is only sent in the form by default.
+    pub fn with_csrf_in_header(mut self, send_csrf_header: bool) -> Self {
+        self.send_csrf_header = send_csrf_header;
+        self
+    }
+
+    /// Send back the hidden inputs of the sign-in form along with the credentials.
+    /// Only the authenticity token is sent by default.
+    pub fn with_hidden_fields(mut self, send_hidden_fields: bool) -> Self {
+        self.send_hidden_fields = send_hidden_fields;
+        self
+    }
+
+    /// Tell whether logging in succeeded from where the sign-in form led, whatever the language
+    /// of the instance: Devise redirects away from the form once logged in, and renders it again otherwise.
+    /// Known flash messages are still read first, e.g. to tell a locked account apart.
+    /// Only flash messages are read by default.
+    pub fn with_redirect_detection(mut self, detect_redirect: bool) -> Self {
+        self.detect_redirect = detect_redirect;
+        self
+    }
+
+    /// Read the answers to the sign-in form in the given locale, the form staying where it is.
+    /// They are read in English by default.
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Sign-in form served in the given locale.
+    pub fn for_locale(locale: Locale) -> Self {
+        Self::new(&format!("{}/users/sign_in", locale.code())).with_locale(locale)
+    }
+
+    pub(crate) fn sign_in_url(&self, base_url: &str) -> Result<String> {
+        build_url(base_url, &self.sign_in_path)
+    }
+
+    /// Devise serves the sign-out route next to the sign-in one, e.g. `en/users/sign_out`.
+    pub(crate) fn sign_out_url(&self, base_url: &str) -> Result<String> {
+        let sign_out_path = match self.sign_in_path.rsplit_once('/') {
+            Some((parent_path, _)) => format!("{parent_path}/sign_out"),
+            None => "sign_out".to_owned(),
+        };
+        build_url(base_url, &sign_out_path)
+    }
+
+    /// Home page of the locale the sign-in form is served in, e.g. `en` for `en/users/sign_in`,
+    /// which links to the sign-out route once signed in.
+    pub(crate) fn home_url(&self, base_url: &str) -> Result<String> {
+        let home_path = self.sign_in_path.rsplitn(3, '/').nth(2).unwrap_or_default();
+        build_url(base_url, home_path)
+    }
+}
+
+impl Default for LoginConfig {
+    fn default() -> Self {
+        Self::for_locale(Locale::default())
+    }
+}
+
+/// Fields of the sign-in form as served by UDA, to be sent back along with the credentials.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SignInForm {
+    /// Where the form was served, and is sent back to.
+    pub(crate) url: String,
+    pub(crate) authenticity_token: String,
+    /// Hidden inputs of the form, including the authenticity token.
+    pub(crate) hidden_fields: Vec<(String, String)>,
+}
+
+/// How logging into UDA went, e.g. for diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Getters)]
+pub struct LoginOutcome {
+    /// Whether the user has just been logged in, rather than being already logged in
+    /// with the session the client held.
+    fresh: bool,
+}
+
 /// Log into UDA and makes given client able to request pages that require authentication.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip(client, password), fields(endpoint = "users/sign_in"))
+)]
 pub async fn authenticate_into_uda(
     client: &Client,
     base_url: &str,
     login: &str,
     password: &str,
 ) -> Result<()> {
-    let authenticity_token = get_authenticity_token(client, base_url)
-        .await
-        .map_err(log_error_and_return(ConnectionFailed))?;
+    authenticate_into_uda_with_locale(client, base_url, login, password, Locale::default()).await
+}
 
-    check_credentials(client, base_url, &authenticity_token, login, password)
-        .await
-        .map_err(log_error_and_return(WrongCredentials))
+/// Log into UDA like [`authenticate_into_uda`], telling whether a fresh login occurred
+/// or the session held by the client was already active.
+pub async fn authenticate_into_uda_verbose(
+    client: &Client,
+    base_url: &str,
+    login: &str,
+    password: &str,
+) -> Result<LoginOutcome> {
+    authenticate(
+        client,
+        base_url,
+        login,
+        password,
+        &LoginConfig::default(),
+        &RetryPolicy::default(),
+        None,
+        None,
+    )
+    .await
 }
 
-async fn get_authenticity_token(client: &Client, base_url: &str) -> Result<String> {
-    let url = format!("{base_url}/en/users/sign_in");
-    let response = client
-        .get(url)
-        .send()
-        .await
-        .map_err(log_message_and_return(
-            "Can't get authenticity token from UDA",
-            ConnectionFailed,
-        ))?;
+/// Check the credentials let log into UDA, e.g. for a credential-checker tool.
+/// A throwaway client is used, so that no session is kept afterward.
+/// Returns `false` when the credentials are wrong,
+/// and fails only when the check can't be done, e.g. because UDA can't be reached.
+pub async fn verify_credentials(base_url: &str, login: &str, password: &str) -> Result<bool> {
+    let client = build_client()?;
+    match authenticate_into_uda(&client, base_url, login, password).await {
+        Ok(()) => Ok(true),
+        Err(WrongCredentials) => Ok(false),
+        Err(error) => Err(error),
+    }
+}
+
+/// Log into UDA through the pages of the given locale.
+/// Required when the instance doesn't answer in English.
+pub async fn authenticate_into_uda_with_locale(
+    client: &Client,
+    base_url: &str,
+    login: &str,
+    password: &str,
+    locale: Locale,
+) -> Result<()> {
+    authenticate(
+        client,
+        base_url,
+        login,
+        password,
+        &LoginConfig::for_locale(locale),
+        &RetryPolicy::default(),
+        None,
+        None,
+    )
+    .await
+    .map(|_outcome| ())
+}
+
+/// Log into UDA through a sign-in form that isn't at its usual place.
+pub async fn authenticate_into_uda_with_config(
+    client: &Client,
+    base_url: &str,
+    login: &str,
+    password: &str,
+    login_config: &LoginConfig,
+) -> Result<()> {
+    authenticate(
+        client,
+        base_url,
+        login,
+        password,
+        login_config,
+        &RetryPolicy::default(),
+        None,
+        None,
+    )
+    .await
+    .map(|_outcome| ())
+}
+
+/// Log into UDA, retrying requests that fail transiently according to the given policy.
+/// Wrong credentials are never retried.
+pub async fn authenticate_into_uda_with_retry(
+    client: &Client,
+    base_url: &str,
+    login: &str,
+    password: &str,
+    retry_policy: &RetryPolicy,
+) -> Result<()> {
+    authenticate(
+        client,
+        base_url,
+        login,
+        password,
+        &LoginConfig::default(),
+        retry_policy,
+        None,
+        None,
+    )
+    .await
+    .map(|_outcome| ())
+}
+
+/// Log into UDA through the pages of the given locale, waiting for the rate limiter, if any,
+/// before each request, and sending the HTTP Basic Auth of the deployment, if any.
+pub(crate) async fn authenticate_into_uda_throttled(
+    client: &Client,
+    base_url: &str,
+    login: &str,
+    password: &str,
+    locale: Locale,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+) -> Result<()> {
+    authenticate(
+        client,
+        base_url,
+        login,
+        password,
+        &LoginConfig::for_locale(locale),
+        &RetryPolicy::default(),
+        rate_limiter,
+        basic_auth,
+    )
+    .await
+    .map(|_outcome| ())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn authenticate(
+    client: &Client,
+    base_url: &str,
+    login: &str,
+    password: &str,
+    login_config: &LoginConfig,
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+) -> Result<LoginOutcome> {
+    let sign_in_url = login_config.sign_in_url(base_url)?;
+    let mut attempts = SignInAttempts::default();
+    loop {
+        let sign_in_form =
+            get_sign_in_form(client, &sign_in_url, retry_policy, rate_limiter, basic_auth).await?;
+        let result = check_credentials(
+            client,
+            &sign_in_form,
+            login,
+            password,
+            login_config,
+            retry_policy,
+            rate_limiter,
+            basic_auth,
+        )
+        .await;
+        if !attempts.should_retry(&result, login) {
+            return result;
+        }
+    }
+}
+
+/// Attempts at sending the sign-in form, whatever the client sending it.
+#[derive(Debug, Default)]
+pub(crate) struct SignInAttempts {
+    token_renewed: bool,
+}
 
+impl SignInAttempts {
+    /// Whether to get a fresh sign-in form and send it again, given how sending it went.
+    /// Rails rotates the token, it may have expired before the form was sent: it's renewed once.
+    pub(crate) fn should_retry(&mut self, result: &Result<LoginOutcome>, login: &str) -> bool {
+        if self.token_renewed || !matches!(result, Err(InvalidAuthenticityToken)) {
+            return false;
+        }
+        warn!("Authenticity token has been rejected. Retrying with a new one. [user: {login}]");
+        self.token_renewed = true;
+        true
+    }
+}
+
+async fn get_sign_in_form(
+    client: &Client,
+    sign_in_url: &str,
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+) -> Result<SignInForm> {
+    let response = send_throttled_with_retry(retry_policy, rate_limiter, || {
+        authorize(client.get(sign_in_url), sign_in_url, basic_auth)
+    })
+    .await
+    .map_err(log_message_and_return(
+        "Can't get authenticity token from UDA",
+        UdaError::connection,
+    ))?;
+
+    let status = response.status();
     let body = response
         .text()
         .await
-        .map_err(log_error_and_return(ConnectionFailed))?;
+        .map_err(log_error_and_return(UdaError::connection))?;
+    read_sign_in_form(sign_in_url, status, &body)
+}
 
-    let document = Html::parse_document(&body);
+/// Sign-in form of the page, whatever the client it was downloaded with.
+pub(crate) fn read_sign_in_form(
+    sign_in_url: &str,
+    status: StatusCode,
+    body: &str,
+) -> Result<SignInForm> {
+    if status == StatusCode::SERVICE_UNAVAILABLE || is_maintenance_page(body) {
+        error!(
+            "Can't get authenticity token from UDA. Instance under maintenance [status: {status}]"
+        );
+        return Err(InstanceUnavailable);
+    }
+
+    let document = Html::parse_document(body);
     let authenticity_token = get_authenticity_token_from_html(&document).map_err(
-        log_message_and_return("Can't get authenticity token from UDA", ConnectionFailed),
+        log_message_and_return("Can't get authenticity token from UDA", identity),
     )?;
 
-    Ok(authenticity_token.to_owned())
+    Ok(SignInForm {
+        url: sign_in_url.to_owned(),
+        authenticity_token: authenticity_token.to_owned(),
+        hidden_fields: get_hidden_fields_from_html(&document)?,
+    })
+}
+
+/// While under maintenance, UDA serves a splash page mentioning it rather than the sign-in form.
+fn is_maintenance_page(body: &str) -> bool {
+    !is_sign_in_page(body.as_bytes())
+        && normalize_text(
+            &Html::parse_document(body)
+                .root_element()
+                .text()
+                .collect::<String>(),
+        )
+        .contains(MAINTENANCE_MARKER)
 }
 
-fn get_authenticity_token_from_html(document: &Html) -> Result<&str> {
+pub(crate) fn get_authenticity_token_from_html(document: &Html) -> Result<&str> {
     let token_selector = Selector::parse(r#"input[name="authenticity_token"]"#)?;
     let element = document.select(&token_selector).next().ok_or_else(|| {
         error!("Authenticity token not found");
-        ConnectionFailed
+        ConnectionFailed(None)
+    })?;
+    let authenticity_token = element.value().attr("value").ok_or_else(|| {
+        error!("Authenticity token has no value");
+        ConnectionFailed(None)
     })?;
-    let authenticity_token = element.value().attr("value").unwrap();
     Ok(authenticity_token)
 }
 
+/// Hidden inputs of the form holding the authenticity token, in the order of the page.
+fn get_hidden_fields_from_html(document: &Html) -> Result<Vec<(String, String)>> {
+    let form_selector = Selector::parse("form")?;
+    let token_selector = Selector::parse(r#"input[name="authenticity_token"]"#)?;
+    let hidden_selector = Selector::parse(r#"input[type="hidden"][name]"#)?;
+
+    let hidden_fields = document
+        .select(&form_selector)
+        .find(|form| form.select(&token_selector).next().is_some())
+        .map(|form| {
+            form.select(&hidden_selector)
+                .filter_map(|input| {
+                    let name = input.value().attr("name")?;
+                    let value = input.value().attr("value").unwrap_or_default();
+                    Some((name.to_owned(), value.to_owned()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(hidden_fields)
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn check_credentials(
     client: &Client,
-    base_url: &str,
-    authenticity_token: &str,
+    sign_in_form: &SignInForm,
     login: &str,
     password: &str,
-) -> Result<()> {
-    let url = format!("{}/en/users/sign_in", base_url);
-    let params = [
+    login_config: &LoginConfig,
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+) -> Result<LoginOutcome> {
+    let submission = SignInSubmission::new(login, password, sign_in_form, login_config);
+    let response = send_throttled_with_retry(retry_policy, rate_limiter, || {
+        let request = client.post(submission.url).form(&submission.params);
+        let request = authorize(request, submission.url, basic_auth);
+        match submission.csrf_token {
+            Some(csrf_token) => request.header("X-CSRF-Token", csrf_token),
+            None => request,
+        }
+    })
+    .await
+    .map_err(log_message_and_return(
+        &format!("Failed to authenticate to UDA [user: {login}]"),
+        UdaError::connection,
+    ))?;
+
+    let status = response.status();
+    #[cfg(feature = "tracing")]
+    tracing::debug!(%status, "Sign-in form answered");
+    let headers = response.headers().clone();
+    let landing_url = response.url().clone();
+    let body = response.text().await.map_err(log_message_and_return(
+        &format!("Failed to authenticate to UDA [user: {login}]"),
+        UdaError::connection,
+    ))?;
+    read_sign_in_answer(
+        &SignInAnswer {
+            status,
+            headers: &headers,
+            landing_url: &landing_url,
+            body: &body,
+        },
+        sign_in_form,
+        login,
+        login_config,
+    )
+}
+
+/// Sign-in form filled in with the credentials, whatever the client sending it.
+pub(crate) struct SignInSubmission<'a> {
+    pub(crate) url: &'a str,
+    pub(crate) params: Vec<(&'a str, &'a str)>,
+    /// Authenticity token to send in the `X-CSRF-Token` header too, if the config asks for it.
+    pub(crate) csrf_token: Option<&'a str>,
+}
+
+impl<'a> SignInSubmission<'a> {
+    pub(crate) fn new(
+        login: &'a str,
+        password: &'a str,
+        sign_in_form: &'a SignInForm,
+        login_config: &LoginConfig,
+    ) -> Self {
+        Self {
+            url: &sign_in_form.url,
+            params: sign_in_params(login, password, sign_in_form, login_config),
+            csrf_token: login_config
+                .send_csrf_header
+                .then_some(sign_in_form.authenticity_token.as_str()),
+        }
+    }
+}
+
+/// Answer of UDA to the sign-in form, whatever the client it was received with.
+pub(crate) struct SignInAnswer<'a> {
+    pub(crate) status: StatusCode,
+    pub(crate) headers: &'a HeaderMap,
+    /// Where the redirections after signing in ended, e.g. to spot an SSO intercepting the sign-in.
+    pub(crate) landing_url: &'a Url,
+    pub(crate) body: &'a str,
+}
+
+/// Tell from the answer to the sign-in form whether the user is now logged in,
+/// reading it as told by the config.
+pub(crate) fn read_sign_in_answer(
+    answer: &SignInAnswer,
+    sign_in_form: &SignInForm,
+    login: &str,
+    login_config: &LoginConfig,
+) -> Result<LoginOutcome> {
+    check_not_rate_limited(answer.status, answer.headers, login)?;
+    let outcome = if login_config.detect_redirect {
+        classify_login_redirect(
+            answer.status,
+            !is_same_page(answer.landing_url, &sign_in_form.url),
+            answer.body,
+            login_config.locale,
+        )?
+    } else {
+        classify_login_response(answer.status, answer.body, login_config.locale)?
+    };
+    debug!(
+        "Logged in UDA [user: {login}, landing_url: {}, fresh: {}]",
+        answer.landing_url, outcome.fresh
+    );
+    Ok(outcome)
+}
+
+/// Whether both URLs lead to the same page, whatever their query, fragment or trailing slash.
+fn is_same_page(landing_url: &Url, sign_in_url: &str) -> bool {
+    let Ok(sign_in_url) = Url::parse(sign_in_url) else {
+        return landing_url.as_str() == sign_in_url;
+    };
+    landing_url.scheme() == sign_in_url.scheme()
+        && landing_url.host() == sign_in_url.host()
+        && landing_url.port_or_known_default() == sign_in_url.port_or_known_default()
+        && landing_url.path().trim_end_matches('/') == sign_in_url.path().trim_end_matches('/')
+}
+
+/// Fields of the sign-in form, whatever the client sending it.
+/// Hidden fields never override the credentials, the authenticity token nor the `utf8` parameter,
+/// which has its own option.
+fn sign_in_params<'a>(
+    login: &'a str,
+    password: &'a str,
+    sign_in_form: &'a SignInForm,
+    login_config: &LoginConfig,
+) -> Vec<(&'a str, &'a str)> {
+    let mut params = vec![
         ("user[email]", login),
         ("user[password]", password),
-        ("authenticity_token", authenticity_token),
-        ("utf8", "✓"),
+        (
+            "authenticity_token",
+            sign_in_form.authenticity_token.as_str(),
+        ),
     ];
-    let response = client
-        .post(url)
-        .form(&params)
+    if login_config.send_utf8_param {
+        params.push(("utf8", "✓"));
+    }
+    if login_config.send_hidden_fields {
+        for (name, value) in &sign_in_form.hidden_fields {
+            if name != "utf8" && params.iter().all(|(param, _)| param != name) {
+                params.push((name, value));
+            }
+        }
+    }
+    params
+}
+
+fn check_not_rate_limited(status: StatusCode, headers: &HeaderMap, login: &str) -> Result<()> {
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = parse_retry_after(headers);
+        error!(
+            "Rate limited when authenticating to UDA [user: {login}, retry_after: {retry_after:?}]"
+        );
+        return Err(RateLimited { retry_after });
+    }
+    Ok(())
+}
+
+/// Tell from the answer to the sign-in form whether the user is now logged in.
+fn classify_login_response(status: StatusCode, body: &str, locale: Locale) -> Result<LoginOutcome> {
+    if status.is_success() {
+        classify_flash(&get_flash_text(body)?, locale).unwrap_or_else(|| {
+            error!(
+                "Failed to authenticate to UDA. Unknown error [errors: {:?}]. See response body: {}",
+                rails_errors(body),
+                body
+            );
+            Err(ConnectionFailed(None))
+        })
+    } else if status == StatusCode::UNPROCESSABLE_ENTITY && is_invalid_authenticity_token_page(body)
+    {
+        error!("Failed to authenticate to UDA. Authenticity token rejected [status: {status}]");
+        Err(InvalidAuthenticityToken)
+    } else if status == StatusCode::UNPROCESSABLE_ENTITY {
+        // Rails rejects the sign-in form with this status, whatever the wording of the page
+        error!(
+            "Failed to authenticate to UDA. Wrong credentials? [status: {status}, errors: {:?}]",
+            rails_errors(body)
+        );
+        Err(WrongCredentials)
+    } else {
+        error!("Failed to authenticate to UDA. Is the instance up? [status: {status}]");
+        Err(UnexpectedStatus(status))
+    }
+}
+
+/// Tell from the answer to the sign-in form whether the user is now logged in, like [`classify_login_response`],
+/// relying on where the form led when the flash messages aren't known, e.g. on a non-English instance.
+fn classify_login_redirect(
+    status: StatusCode,
+    redirected: bool,
+    body: &str,
+    locale: Locale,
+) -> Result<LoginOutcome> {
+    if status.is_success() {
+        if let Some(outcome) = classify_flash(&get_flash_text(body)?, locale) {
+            return outcome;
+        }
+        let shows_sign_in_form = is_sign_in_page(body.as_bytes());
+        if redirected && !shows_sign_in_form {
+            debug!("Redirected away from the sign-in form, logged in");
+            return Ok(LoginOutcome { fresh: true });
+        }
+        if !redirected && shows_sign_in_form {
+            error!("Failed to authenticate to UDA. Wrong credentials? The sign-in form is shown again.");
+            return Err(WrongCredentials);
+        }
+    }
+    classify_login_response(status, body, locale)
+}
+
+/// Outcome the flash messages tell, `None` when none of them is known.
+fn classify_flash(flash: &str, locale: Locale) -> Option<Result<LoginOutcome>> {
+    if flash.contains(&normalize_text(locale.signed_in_marker())) {
+        Some(Ok(LoginOutcome { fresh: true }))
+    } else if flash.contains(&normalize_text(locale.already_signed_in_marker())) {
+        Some(Ok(LoginOutcome { fresh: false }))
+    } else if flash.contains(&normalize_text(locale.invalid_credentials_marker())) {
+        error!("Failed to authenticate to UDA. Wrong credentials?");
+        Some(Err(WrongCredentials))
+    } else if flash.contains(LAST_ATTEMPT_MARKER) {
+        error!("Failed to authenticate to UDA. Wrong credentials? The account gets locked on the next failure.");
+        Some(Err(WrongCredentials))
+    } else if locale
+        .account_locked_markers()
+        .iter()
+        .any(|marker| flash.contains(&normalize_text(marker)))
+    {
+        error!("Failed to authenticate to UDA. Account locked after too many failed logins.");
+        Some(Err(AccountLocked))
+    } else {
+        None
+    }
+}
+
+fn rails_errors(body: &str) -> Vec<String> {
+    extract_rails_errors(&Html::parse_document(body))
+}
+
+fn is_invalid_authenticity_token_page(body: &str) -> bool {
+    let text = normalize_text(body);
+    INVALID_AUTHENTICITY_TOKEN_MARKERS
+        .iter()
+        .any(|marker| text.contains(marker))
+}
+
+/// Whether the cookies held by the client still grant access to UDA, waiting for the rate limiter, if any.
+/// A signed-in user is redirected away from the sign-in form, so the form only shows up once logged out.
+pub(crate) async fn is_signed_in(
+    client: &Client,
+    base_url: &str,
+    locale: Locale,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+) -> Result<bool> {
+    let url = LoginConfig::for_locale(locale).sign_in_url(base_url)?;
+    throttle(rate_limiter).await;
+    let response = authorize(client.get(&url), &url, basic_auth)
         .send()
         .await
         .map_err(log_message_and_return(
-            "Failed to authenticate to UDA [user: {login}]",
-            ConnectionFailed,
+            "Can't check whether signed in UDA",
+            UdaError::connection,
         ))?;
 
     let status = response.status();
-    if status.is_success() {
-        let text = response.text().await.map_err(log_message_and_return(
-            "Failed to authenticate to UDA",
-            ConnectionFailed,
-        ))?;
-        if text.contains("Signed in successfully") || text.contains("You are already signed in") {
-            debug!("Logged in UDA [user: {login}]");
-            Ok(())
-        } else if text.contains("Invalid User Account Email or password") {
-            error!("Failed to authenticate to UDA. Wrong credentials? [user: {login}]");
-            Err(WrongCredentials)
-        } else {
-            error!(
-                "Failed to authenticate to UDA. Unknown error. See response body: {}",
-                text
-            );
-            Err(ConnectionFailed)
-        }
+    if !status.is_success() {
+        error!("Can't check whether signed in UDA. Is the instance up? [status: {status}]");
+        return Err(UnexpectedStatus(status));
+    }
+
+    let body = response.bytes().await.map_err(log_message_and_return(
+        "Can't check whether signed in UDA",
+        UdaError::connection,
+    ))?;
+    Ok(!is_sign_in_page(&body))
+}
+
+/// Text of the flash messages of the page, or of the whole page when it has none, normalized.
+fn get_flash_text(body: &str) -> Result<String> {
+    let selector = Selector::parse(FLASH_SELECTOR)?;
+    let document = Html::parse_document(body);
+    let flash_messages = document
+        .select(&selector)
+        .map(|element| element.text().collect::<String>())
+        .collect::<Vec<_>>();
+
+    let text = if flash_messages.is_empty() {
+        document.root_element().text().collect::<String>()
+    } else {
+        flash_messages.join(" ")
+    };
+    Ok(normalize_text(&text))
+}
+
+/// Ignore casing and whitespace, as they change with UDA versions and markup.
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Log out of UDA so that the cookies held by the client no longer grant access.
+/// Succeeds as well when the session was already invalid.
+pub async fn sign_out(client: &Client, base_url: &str) -> Result<()> {
+    sign_out_with_config(client, base_url, &LoginConfig::default()).await
+}
+
+/// Log out of UDA through the sign-out route next to the configured sign-in form.
+/// Recent UDA versions expect a DELETE, older ones a GET, which is sent when the DELETE isn't routed.
+/// The CSRF token of the home page is sent along, as Rails rejects a DELETE without it.
+pub async fn sign_out_with_config(
+    client: &Client,
+    base_url: &str,
+    login_config: &LoginConfig,
+) -> Result<()> {
+    sign_out_throttled(client, base_url, login_config, None, None).await
+}
+
+/// Log out of UDA like [`sign_out_with_config`], waiting for the rate limiter, if any,
+/// before each request, and sending the HTTP Basic Auth of the deployment, if any.
+pub(crate) async fn sign_out_throttled(
+    client: &Client,
+    base_url: &str,
+    login_config: &LoginConfig,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+) -> Result<()> {
+    let Some(csrf_token) =
+        get_sign_out_token(client, base_url, login_config, rate_limiter, basic_auth).await?
+    else {
+        debug!("Already signed out from UDA");
+        return Ok(());
+    };
+
+    let url = login_config.sign_out_url(base_url)?;
+    let request = authorize(client.delete(&url), &url, basic_auth)
+        .header("X-CSRF-Token", &csrf_token)
+        .form(&[("authenticity_token", &csrf_token)]);
+    let mut response = send_sign_out(request, rate_limiter).await?;
+    if matches!(
+        response.status(),
+        StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED
+    ) {
+        debug!(
+            "DELETE not routed, signing out with GET instead [status: {}]",
+            response.status()
+        );
+        let request =
+            authorize(client.get(&url), &url, basic_auth).header("X-CSRF-Token", &csrf_token);
+        response = send_sign_out(request, rate_limiter).await?;
+    }
+
+    let status = response.status();
+    if status == StatusCode::UNAUTHORIZED {
+        // No session left to end
+        debug!("Already signed out from UDA");
+        return Ok(());
+    }
+    if !status.is_success() {
+        error!("Failed to sign out from UDA. Is the instance up? [status: {status}]");
+        return Err(UnexpectedStatus(status));
+    }
+
+    let text = response.text().await.map_err(log_message_and_return(
+        "Failed to sign out from UDA",
+        UdaError::connection,
+    ))?;
+    if text.contains(SIGNED_IN_MARKER) {
+        error!("Failed to sign out from UDA. Still signed in after sign out.");
+        Err(ConnectionFailed(None))
     } else {
-        error!("Failed to authenticate to UDA. Is the instance up? [user: {login}]");
-        Err(ConnectionFailed)
+        debug!("Signed out from UDA");
+        Ok(())
     }
 }
 
+/// CSRF token of the home page, `None` when it doesn't link to the sign-out route,
+/// i.e. there is no session to end.
+async fn get_sign_out_token(
+    client: &Client,
+    base_url: &str,
+    login_config: &LoginConfig,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+) -> Result<Option<String>> {
+    let url = login_config.home_url(base_url)?;
+    let response =
+        send_sign_out(authorize(client.get(&url), &url, basic_auth), rate_limiter).await?;
+    let status = response.status();
+    if !status.is_success() {
+        error!("Failed to sign out from UDA. Can't reach home page [status: {status}]");
+        return Err(UnexpectedStatus(status));
+    }
+
+    let text = response.text().await.map_err(log_message_and_return(
+        "Failed to sign out from UDA",
+        UdaError::connection,
+    ))?;
+    if !text.contains(SIGNED_IN_MARKER) {
+        return Ok(None);
+    }
+    retrieve_csrf_from_html(&text).await.map(Some)
+}
+
+async fn send_sign_out(
+    request: RequestBuilder,
+    rate_limiter: Option<&RateLimiter>,
+) -> Result<Response> {
+    throttle(rate_limiter).await;
+    request.send().await.map_err(log_message_and_return(
+        "Failed to sign out from UDA",
+        UdaError::connection,
+    ))
+}
+
+#[cfg(test)]
+pub(crate) const INVALID_AUTHENTICITY_TOKEN_PAGE: &str = r#"<!DOCTYPE html><html><head><title>The change you wanted was rejected (422)</title></head><body><div class="dialog"><h1>The change you wanted was rejected.</h1><p>Maybe you tried to change something you didn't have access to.</p></div></body></html>"#;
+
 #[cfg(any(test, feature = "test"))]
 const AUTHENTICITY_TOKEN: &str =
     "BDv-07yMs8kMDnRn2hVgpSmqn88V_XhCZxImtcXr3u6OOmpnsy0WpFD49rTOuOEfJG_PptBBJag094Vd0uuyZg";
@@ -155,15 +922,61 @@ pub async fn setup_authenticity_token(mock_server: &MockServer) -> String {
     AUTHENTICITY_TOKEN.to_owned()
 }
 
+/// Home page of a signed-in user, holding the CSRF token to sign out with.
+#[cfg(any(test, feature = "test"))]
+pub async fn setup_signed_in_home_page(mock_server: &MockServer, home_path: &str) {
+    let body = format!(
+        r#"<html><head><meta name="csrf-token" content="{AUTHENTICITY_TOKEN}"></head><body><a href="/en/users/sign_out" data-method="delete">Sign out</a></body></html>"#
+    );
+    Mock::given(method("GET"))
+        .and(path(home_path))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .mount(mock_server)
+        .await;
+}
+
+#[cfg(any(test, feature = "test"))]
+pub async fn setup_sign_out(mock_server: &MockServer) {
+    setup_signed_in_home_page(mock_server, "/en").await;
+    Mock::given(method("DELETE"))
+        .and(path("/en/users/sign_out"))
+        .and(header("X-CSRF-Token", AUTHENTICITY_TOKEN))
+        .and(body_string(format!(
+            "authenticity_token={AUTHENTICITY_TOKEN}"
+        )))
+        .respond_with(
+            ResponseTemplate::new(303)
+                .append_header("Location", "/en/users/sign_in")
+                .append_header(
+                    "Set-Cookie",
+                    "_uda_session=; path=/; expires=Thu, 01 Jan 1970 00:00:00 GMT",
+                ),
+        )
+        .mount(mock_server)
+        .await;
+}
+
 #[cfg(test)]
 pub mod tests {
     mod authenticate_into_uda {
         use crate::credentials::UdaCredentials;
         use crate::error::UdaError;
-        use crate::login::authenticate_into_uda;
-        use crate::login::{setup_authentication, setup_authenticity_token};
+        use crate::locale::Locale;
+        use crate::login::{
+            authenticate_into_uda, authenticate_into_uda_with_config,
+            authenticate_into_uda_with_locale, authenticate_into_uda_with_retry, LoginConfig,
+        };
+        use crate::login::{
+            setup_authentication, setup_authenticity_token, AUTHENTICITY_TOKEN,
+            INVALID_AUTHENTICITY_TOKEN_PAGE,
+        };
+        use crate::retry::RetryPolicy;
+        use crate::tools::build_client;
         use reqwest::Client;
-        use wiremock::matchers::{body_string, method, path};
+        use std::time::Duration;
+        use wiremock::matchers::{
+            body_string, body_string_contains, header, header_exists, method, path,
+        };
         use wiremock::{Mock, MockServer, ResponseTemplate};
 
         #[tokio::test]
@@ -201,7 +1014,7 @@ pub mod tests {
             .await
             .unwrap_err();
 
-            assert!(matches!(error, UdaError::ConnectionFailed));
+            assert!(matches!(error, UdaError::ConnectionFailed(_)));
         }
 
         #[tokio::test]
@@ -230,98 +1043,1027 @@ pub mod tests {
 
             assert!(matches!(error, UdaError::WrongCredentials));
         }
-    }
-
-    mod get_authenticity_token {
-        use crate::error::UdaError;
-        use crate::login::get_authenticity_token;
-        use crate::login::setup_authenticity_token;
-        use crate::tools::tests::build_client;
-        use wiremock::matchers::{method, path};
-        use wiremock::{Mock, MockServer, ResponseTemplate};
-
-        #[tokio::test]
-        async fn should_get_authenticity_token() {
-            let mock_server = MockServer::start().await;
-            let client = build_client().unwrap();
-            let expected_token = setup_authenticity_token(&mock_server).await;
-
-            let token = get_authenticity_token(&client, &mock_server.uri())
-                .await
-                .unwrap();
-            assert_eq!(expected_token, token);
-        }
 
         #[tokio::test]
-        async fn should_not_get_authenticity_token_when_unreachable() {
+        async fn should_fail_to_authenticate_into_uda_when_redirected_to_another_host() {
             let mock_server = MockServer::start().await;
-            let client = build_client().unwrap();
-
-            Mock::given(method("GET"))
+            setup_authenticity_token(&mock_server).await;
+            Mock::given(method("POST"))
                 .and(path("/en/users/sign_in"))
-                .respond_with(ResponseTemplate::new(500))
+                .respond_with(
+                    ResponseTemplate::new(302)
+                        .insert_header("Location", "https://sso.example.com/login"),
+                )
                 .mount(&mock_server)
                 .await;
 
-            let error = get_authenticity_token(&client, &mock_server.uri())
+            let client = build_client().unwrap();
+            let error = authenticate_into_uda(&client, &mock_server.uri(), "login", "password")
                 .await
                 .unwrap_err();
-            assert!(matches!(error, UdaError::ConnectionFailed));
+
+            assert!(matches!(error, UdaError::ConnectionFailed(Some(_))));
         }
 
         #[tokio::test]
-        async fn should_not_get_authenticity_token_not_in_page() {
+        async fn should_authenticate_into_uda_after_invalid_authenticity_token() {
             let mock_server = MockServer::start().await;
-            let client = build_client().unwrap();
-
-            let body = "<html><body><div>What are ya lookin' for, son?</div></body></html>";
-            Mock::given(method("GET"))
+            Mock::given(method("POST"))
                 .and(path("/en/users/sign_in"))
-                .respond_with(ResponseTemplate::new(200).set_body_string(body))
+                .respond_with(
+                    ResponseTemplate::new(422).set_body_string(INVALID_AUTHENTICITY_TOKEN_PAGE),
+                )
+                .up_to_n_times(1)
+                .with_priority(1)
                 .mount(&mock_server)
                 .await;
+            let credentials = setup_authentication(&mock_server).await;
 
-            let error = get_authenticity_token(&client, &mock_server.uri())
-                .await
-                .unwrap_err();
-            assert!(matches!(error, UdaError::ConnectionFailed));
-        }
-    }
-
-    mod get_authenticity_token_from_html {
+            let client = Client::new();
+            authenticate_into_uda(
+                &client,
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap();
+
+            let requests = mock_server.received_requests().await.unwrap();
+            let sign_in_page_requests = requests
+                .iter()
+                .filter(|request| request.method.as_str() == "GET")
+                .count();
+            assert_eq!(2, sign_in_page_requests);
+        }
+
+        #[tokio::test]
+        async fn should_retry_only_once_when_invalid_authenticity_token() {
+            let mock_server = MockServer::start().await;
+            setup_authenticity_token(&mock_server).await;
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(
+                    ResponseTemplate::new(422).set_body_string(INVALID_AUTHENTICITY_TOKEN_PAGE),
+                )
+                .expect(2)
+                .mount(&mock_server)
+                .await;
+
+            let client = Client::new();
+            let error = authenticate_into_uda(&client, &mock_server.uri(), "login", "password")
+                .await
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::InvalidAuthenticityToken));
+        }
+
+        #[tokio::test]
+        async fn should_authenticate_into_uda_after_transient_failures() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(ResponseTemplate::new(503))
+                .up_to_n_times(2)
+                .with_priority(1)
+                .mount(&mock_server)
+                .await;
+            let credentials = setup_authentication(&mock_server).await;
+
+            let client = Client::new();
+            let retry_policy = RetryPolicy::new(
+                3,
+                Duration::from_millis(10),
+                Duration::from_millis(50),
+                false,
+            );
+            authenticate_into_uda_with_retry(
+                &client,
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+                &retry_policy,
+            )
+            .await
+            .unwrap();
+
+            let requests = mock_server.received_requests().await.unwrap();
+            let sign_in_page_requests = requests
+                .iter()
+                .filter(|request| request.method.as_str() == "GET")
+                .count();
+            assert_eq!(3, sign_in_page_requests);
+        }
+
+        #[tokio::test]
+        async fn should_not_retry_when_wrong_credentials() {
+            let mock_server = MockServer::start().await;
+            setup_authenticity_token(&mock_server).await;
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(
+                    "<html><body>Invalid User Account Email or password</body></html>",
+                ))
+                .mount(&mock_server)
+                .await;
+
+            let client = Client::new();
+            let retry_policy = RetryPolicy::new(
+                3,
+                Duration::from_millis(10),
+                Duration::from_millis(50),
+                false,
+            );
+            let error = authenticate_into_uda_with_retry(
+                &client,
+                &mock_server.uri(),
+                "login",
+                "password",
+                &retry_policy,
+            )
+            .await
+            .unwrap_err();
+
+            assert!(matches!(error, UdaError::WrongCredentials));
+            assert_eq!(2, mock_server.received_requests().await.unwrap().len());
+        }
+
+        #[tokio::test]
+        async fn should_authenticate_into_uda_in_french() {
+            let mock_server = MockServer::start().await;
+            let body = format!(
+                r#"<html><body><input name="authenticity_token" value="{AUTHENTICITY_TOKEN}"></body></html>"#
+            );
+            Mock::given(method("GET"))
+                .and(path("/fr/users/sign_in"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(&body))
+                .mount(&mock_server)
+                .await;
+            let params = format!(
+                "user%5Bemail%5D=login&user%5Bpassword%5D=password&authenticity_token={AUTHENTICITY_TOKEN}&utf8=%E2%9C%93"
+            );
+            Mock::given(method("POST"))
+                .and(path("/fr/users/sign_in"))
+                .and(body_string(&params))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_string("Connecté(e) avec succès."),
+                )
+                .mount(&mock_server)
+                .await;
+
+            let client = Client::new();
+            authenticate_into_uda_with_locale(
+                &client,
+                &mock_server.uri(),
+                "login",
+                "password",
+                Locale::Fr,
+            )
+            .await
+            .unwrap();
+        }
+
+        #[tokio::test]
+        async fn should_fail_with_wrong_credentials_in_config_locale() {
+            let mock_server = MockServer::start().await;
+            let body = format!(
+                r#"<html><body><input name="authenticity_token" value="{AUTHENTICITY_TOKEN}"></body></html>"#
+            );
+            Mock::given(method("GET"))
+                .and(path("/fr/users/sign_in"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(&body))
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/fr/users/sign_in"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(
+                    "<html><body>Courriel ou mot de passe incorrect.</body></html>",
+                ))
+                .mount(&mock_server)
+                .await;
+
+            let client = Client::new();
+            let error = authenticate_into_uda_with_config(
+                &client,
+                &mock_server.uri(),
+                "login",
+                "password",
+                &LoginConfig::for_locale(Locale::Fr),
+            )
+            .await
+            .unwrap_err();
+
+            assert!(matches!(error, UdaError::WrongCredentials));
+        }
+
+        #[tokio::test]
+        async fn should_authenticate_into_uda_under_sub_path() {
+            let mock_server = MockServer::start().await;
+            let body = format!(
+                r#"<html><body><input name="authenticity_token" value="{AUTHENTICITY_TOKEN}"></body></html>"#
+            );
+            Mock::given(method("GET"))
+                .and(path("/uda/en/users/sign_in"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(&body))
+                .mount(&mock_server)
+                .await;
+            let params = format!(
+                "user%5Bemail%5D=login&user%5Bpassword%5D=password&authenticity_token={AUTHENTICITY_TOKEN}&utf8=%E2%9C%93"
+            );
+            Mock::given(method("POST"))
+                .and(path("/uda/en/users/sign_in"))
+                .and(body_string(&params))
+                .respond_with(ResponseTemplate::new(200).set_body_string("Signed in successfully"))
+                .mount(&mock_server)
+                .await;
+
+            let client = Client::new();
+            authenticate_into_uda_with_config(
+                &client,
+                &mock_server.uri(),
+                "login",
+                "password",
+                &LoginConfig::new("/uda/en/users/sign_in"),
+            )
+            .await
+            .unwrap();
+        }
+
+        #[tokio::test]
+        async fn should_authenticate_into_uda_without_utf8_param() {
+            let mock_server = MockServer::start().await;
+            setup_authenticity_token(&mock_server).await;
+            let params = format!(
+                "user%5Bemail%5D=login&user%5Bpassword%5D=password&authenticity_token={AUTHENTICITY_TOKEN}"
+            );
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .and(body_string(&params))
+                .respond_with(ResponseTemplate::new(200).set_body_string("Signed in successfully"))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+
+            let client = Client::new();
+            authenticate_into_uda_with_config(
+                &client,
+                &mock_server.uri(),
+                "login",
+                "password",
+                &LoginConfig::default().with_utf8_param(false),
+            )
+            .await
+            .unwrap();
+        }
+
+        #[tokio::test]
+        async fn should_authenticate_into_uda_with_csrf_token_in_header() {
+            let mock_server = MockServer::start().await;
+            setup_authenticity_token(&mock_server).await;
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .and(header("X-CSRF-Token", AUTHENTICITY_TOKEN))
+                .and(body_string_contains("user%5Bemail%5D=login"))
+                .and(body_string_contains(format!(
+                    "authenticity_token={AUTHENTICITY_TOKEN}"
+                )))
+                .respond_with(ResponseTemplate::new(200).set_body_string("Signed in successfully"))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+
+            let client = Client::new();
+            authenticate_into_uda_with_config(
+                &client,
+                &mock_server.uri(),
+                "login",
+                "password",
+                &LoginConfig::default().with_csrf_in_header(true),
+            )
+            .await
+            .unwrap();
+        }
+
+        #[tokio::test]
+        async fn should_not_send_csrf_token_in_header_by_default() {
+            let mock_server = MockServer::start().await;
+            setup_authenticity_token(&mock_server).await;
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .and(header_exists("X-CSRF-Token"))
+                .respond_with(ResponseTemplate::new(422))
+                .with_priority(1)
+                .expect(0)
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("Signed in successfully"))
+                .mount(&mock_server)
+                .await;
+
+            let client = Client::new();
+            authenticate_into_uda_with_config(
+                &client,
+                &mock_server.uri(),
+                "login",
+                "password",
+                &LoginConfig::default(),
+            )
+            .await
+            .unwrap();
+        }
+
+        async fn setup_sign_in_form_with_hidden_fields(mock_server: &MockServer) {
+            let body = format!(
+                r#"<html><body><form action="/en/users/sign_in" method="post"><input type="hidden" name="utf8" value="&#x2713;"><input type="hidden" name="authenticity_token" value="{AUTHENTICITY_TOKEN}"><input type="hidden" name="tenant_id" value="cfm2025"><input type="email" name="user[email]"><input type="password" name="user[password]"></form></body></html>"#
+            );
+            Mock::given(method("GET"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(body))
+                .mount(mock_server)
+                .await;
+        }
+
+        #[tokio::test]
+        async fn should_authenticate_into_uda_with_hidden_fields() {
+            let mock_server = MockServer::start().await;
+            setup_sign_in_form_with_hidden_fields(&mock_server).await;
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .and(body_string(format!(
+                    "user%5Bemail%5D=login&user%5Bpassword%5D=password&authenticity_token={AUTHENTICITY_TOKEN}&utf8=%E2%9C%93&tenant_id=cfm2025"
+                )))
+                .respond_with(ResponseTemplate::new(200).set_body_string("Signed in successfully"))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+
+            let client = Client::new();
+            authenticate_into_uda_with_config(
+                &client,
+                &mock_server.uri(),
+                "login",
+                "password",
+                &LoginConfig::default().with_hidden_fields(true),
+            )
+            .await
+            .unwrap();
+        }
+
+        #[tokio::test]
+        async fn should_not_send_hidden_fields_by_default() {
+            let mock_server = MockServer::start().await;
+            setup_sign_in_form_with_hidden_fields(&mock_server).await;
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .and(body_string_contains("tenant_id"))
+                .respond_with(ResponseTemplate::new(422))
+                .with_priority(1)
+                .expect(0)
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("Signed in successfully"))
+                .mount(&mock_server)
+                .await;
+
+            let client = Client::new();
+            authenticate_into_uda_with_config(
+                &client,
+                &mock_server.uri(),
+                "login",
+                "password",
+                &LoginConfig::default(),
+            )
+            .await
+            .unwrap();
+        }
+
+        #[tokio::test]
+        async fn should_authenticate_when_redirected_to_root() {
+            let mock_server = MockServer::start().await;
+            setup_authenticity_token(&mock_server).await;
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(ResponseTemplate::new(302).insert_header("location", "/"))
+                .mount(&mock_server)
+                .await;
+            // The flash message isn't in a known language
+            Mock::given(method("GET"))
+                .and(path("/"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(
+                    r#"<html><body><div class="alert">Connexion établie.</div></body></html>"#,
+                ))
+                .mount(&mock_server)
+                .await;
+
+            let client = Client::new();
+            authenticate_into_uda_with_config(
+                &client,
+                &mock_server.uri(),
+                "login",
+                "password",
+                &LoginConfig::default().with_redirect_detection(true),
+            )
+            .await
+            .unwrap();
+        }
+
+        #[tokio::test]
+        async fn should_fail_when_sign_in_form_is_shown_again() {
+            let mock_server = MockServer::start().await;
+            setup_authenticity_token(&mock_server).await;
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(
+                    r#"<html><body><div class="alert">Ungültige Anmeldedaten.</div><form action="/en/users/sign_in" method="post"><input type="email" name="user[email]"><input type="password" name="user[password]"></form></body></html>"#,
+                ))
+                .mount(&mock_server)
+                .await;
+
+            let client = Client::new();
+            let error = authenticate_into_uda_with_config(
+                &client,
+                &mock_server.uri(),
+                "login",
+                "password",
+                &LoginConfig::default().with_redirect_detection(true),
+            )
+            .await
+            .unwrap_err();
+
+            assert!(matches!(error, UdaError::WrongCredentials));
+        }
+
+        #[tokio::test]
+        async fn should_fail_when_redirected_back_to_sign_in_form() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(format!(
+                    r#"<html><body><form action="/en/users/sign_in" method="post"><input name="authenticity_token" value="{AUTHENTICITY_TOKEN}"><input type="email" name="user[email]"><input type="password" name="user[password]"></form></body></html>"#
+                )))
+                .mount(&mock_server)
+                .await;
+            // Same page, only the query differs
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(
+                    ResponseTemplate::new(302)
+                        .insert_header("location", "/en/users/sign_in?retry=1"),
+                )
+                .mount(&mock_server)
+                .await;
+
+            let client = Client::new();
+            let error = authenticate_into_uda_with_config(
+                &client,
+                &mock_server.uri(),
+                "login",
+                "password",
+                &LoginConfig::default().with_redirect_detection(true),
+            )
+            .await
+            .unwrap_err();
+
+            assert!(matches!(error, UdaError::WrongCredentials));
+        }
+    }
+
+    mod authenticate_into_uda_verbose {
+        use crate::login::{authenticate_into_uda_verbose, setup_authenticity_token, LoginOutcome};
+        use reqwest::Client;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        async fn authenticate(sign_in_answer: &str) -> LoginOutcome {
+            let mock_server = MockServer::start().await;
+            setup_authenticity_token(&mock_server).await;
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(sign_in_answer))
+                .mount(&mock_server)
+                .await;
+
+            let client = Client::new();
+            authenticate_into_uda_verbose(&client, &mock_server.uri(), "login", "password")
+                .await
+                .unwrap()
+        }
+
+        #[tokio::test]
+        async fn should_report_fresh_login() {
+            let outcome = authenticate(
+                r#"<html><body><div class="flash notice">Signed in successfully.</div></body></html>"#,
+            )
+            .await;
+
+            assert!(outcome.fresh());
+        }
+
+        #[tokio::test]
+        async fn should_report_already_active_session() {
+            let outcome = authenticate(
+                r#"<html><body><div class="flash alert">You are already signed in.</div></body></html>"#,
+            )
+            .await;
+
+            assert!(!outcome.fresh());
+        }
+    }
+
+    mod verify_credentials {
+        use crate::error::UdaError;
+        use crate::fake_uda::FakeUda;
+        use crate::login::verify_credentials;
+
+        #[tokio::test]
+        async fn should_verify_right_credentials() {
+            let fake_uda = FakeUda::start().await;
+            let credentials = fake_uda.credentials();
+
+            let is_valid = verify_credentials(
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap();
+
+            assert!(is_valid);
+        }
+
+        #[tokio::test]
+        async fn should_verify_wrong_credentials() {
+            let fake_uda = FakeUda::start().await.with_wrong_credentials().await;
+
+            let is_valid = verify_credentials(&fake_uda.uri(), "login", "wrong")
+                .await
+                .unwrap();
+
+            assert!(!is_valid);
+        }
+
+        #[tokio::test]
+        async fn should_fail_to_verify_credentials_when_unreachable() {
+            let error = verify_credentials("http://localhost:0", "login", "password")
+                .await
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::ConnectionFailed(_)));
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    mod tracing_spans {
+        use crate::login::{authenticate_into_uda, setup_authentication};
+        use reqwest::Client;
+        use std::sync::{Arc, Mutex};
+        use tracing::span::{Attributes, Id};
+        use tracing::Subscriber;
+        use tracing_subscriber::layer::{Context, SubscriberExt};
+        use tracing_subscriber::Layer;
+        use wiremock::MockServer;
+
+        /// Record the name and fields of each span created.
+        struct SpanRecorder(Arc<Mutex<Vec<String>>>);
+
+        impl<S: Subscriber> Layer<S> for SpanRecorder {
+            fn on_new_span(&self, attributes: &Attributes<'_>, _id: &Id, _context: Context<'_, S>) {
+                let span = format!("{} {:?}", attributes.metadata().name(), attributes.values());
+                self.0.lock().unwrap().push(span);
+            }
+        }
+
+        #[tokio::test]
+        async fn should_instrument_authentication_without_password() {
+            let spans = Arc::new(Mutex::new(vec![]));
+            let subscriber = tracing_subscriber::registry().with(SpanRecorder(spans.clone()));
+            let _guard = tracing::subscriber::set_default(subscriber);
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+
+            let client = Client::new();
+            authenticate_into_uda(
+                &client,
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap();
+
+            let spans = spans.lock().unwrap();
+            let span = spans
+                .iter()
+                .find(|span| span.starts_with("authenticate_into_uda "))
+                .unwrap();
+            assert!(span.contains(credentials.uda_url()));
+            assert!(span.contains(credentials.login()));
+            assert!(span.contains("users/sign_in"));
+            assert!(!span.contains(credentials.password()));
+        }
+    }
+
+    mod get_sign_in_form {
+        use crate::error::UdaError;
+        use crate::login::get_sign_in_form;
+        use crate::login::setup_authenticity_token;
+        use crate::retry::RetryPolicy;
+        use crate::tools::build_client;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn should_get_authenticity_token() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+            let expected_token = setup_authenticity_token(&mock_server).await;
+
+            let sign_in_form = get_sign_in_form(
+                &client,
+                &format!("{}/en/users/sign_in", mock_server.uri()),
+                &RetryPolicy::default(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+            assert_eq!(expected_token, sign_in_form.authenticity_token);
+        }
+
+        #[tokio::test]
+        async fn should_not_get_authenticity_token_when_unreachable() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+
+            Mock::given(method("GET"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(ResponseTemplate::new(500))
+                .mount(&mock_server)
+                .await;
+
+            let error = get_sign_in_form(
+                &client,
+                &format!("{}/en/users/sign_in", mock_server.uri()),
+                &RetryPolicy::default(),
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+            assert!(matches!(error, UdaError::ConnectionFailed(_)));
+        }
+
+        #[tokio::test]
+        async fn should_not_get_authenticity_token_not_in_page() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+
+            let body = "<html><body><div>What are ya lookin' for, son?</div></body></html>";
+            Mock::given(method("GET"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(body))
+                .mount(&mock_server)
+                .await;
+
+            let error = get_sign_in_form(
+                &client,
+                &format!("{}/en/users/sign_in", mock_server.uri()),
+                &RetryPolicy::default(),
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+            assert!(matches!(error, UdaError::ConnectionFailed(_)));
+        }
+
+        #[tokio::test]
+        async fn should_not_get_authenticity_token_when_under_maintenance() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+
+            let body = r#"<!DOCTYPE html><html><head><title>Registration</title></head><body><div class="container"><h1>Site Maintenance</h1><p>The registration site is currently down for scheduled Maintenance. We will be back shortly.</p></div></body></html>"#;
+            Mock::given(method("GET"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(body))
+                .mount(&mock_server)
+                .await;
+
+            let error = get_sign_in_form(
+                &client,
+                &format!("{}/en/users/sign_in", mock_server.uri()),
+                &RetryPolicy::default(),
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+            assert!(matches!(error, UdaError::InstanceUnavailable));
+        }
+
+        #[tokio::test]
+        async fn should_not_get_authenticity_token_when_unavailable() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+
+            Mock::given(method("GET"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(ResponseTemplate::new(503))
+                .mount(&mock_server)
+                .await;
+
+            let error = get_sign_in_form(
+                &client,
+                &format!("{}/en/users/sign_in", mock_server.uri()),
+                &RetryPolicy::default(),
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+            assert!(matches!(error, UdaError::InstanceUnavailable));
+        }
+    }
+
+    mod get_authenticity_token_from_html {
         use crate::error::UdaError;
         use crate::login::{get_authenticity_token_from_html, AUTHENTICITY_TOKEN};
         use scraper::Html;
 
         #[test]
-        fn should_get_authenticity_token_from_html() {
-            let body = format!(
-                r#"<html><body><input name="authenticity_token" value="{AUTHENTICITY_TOKEN}"></body></html>"#
-            );
-            let html = Html::parse_document(&body);
-            let token = get_authenticity_token_from_html(&html).unwrap();
+        fn should_get_authenticity_token_from_html() {
+            let body = format!(
+                r#"<html><body><input name="authenticity_token" value="{AUTHENTICITY_TOKEN}"></body></html>"#
+            );
+            let html = Html::parse_document(&body);
+            let token = get_authenticity_token_from_html(&html).unwrap();
+
+            assert_eq!(AUTHENTICITY_TOKEN, token);
+        }
+
+        #[test]
+        fn should_not_get_authenticity_token_from_html() {
+            let body = "<html><body><div>What are ya lookin' for, son?</div></body></html>";
+            let html = Html::parse_document(body);
+            let error = get_authenticity_token_from_html(&html).unwrap_err();
+
+            assert!(matches!(error, UdaError::ConnectionFailed(_)));
+        }
+
+        #[test]
+        fn should_not_get_authenticity_token_from_html_without_value() {
+            let body = r#"<html><body><input name="authenticity_token"></body></html>"#;
+            let html = Html::parse_document(body);
+            let error = get_authenticity_token_from_html(&html).unwrap_err();
+
+            assert!(matches!(error, UdaError::ConnectionFailed(None)));
+        }
+    }
+
+    mod classify_login_redirect {
+        use crate::error::UdaError;
+        use crate::locale::Locale;
+        use crate::login::classify_login_redirect;
+        use reqwest::StatusCode;
+
+        const SIGN_IN_FORM: &str = r#"<html><body><form action="/en/users/sign_in" method="post"><input type="email" name="user[email]"><input type="password" name="user[password]"></form></body></html>"#;
+
+        #[test]
+        fn should_accept_redirection_away_from_form() {
+            let outcome = classify_login_redirect(
+                StatusCode::OK,
+                true,
+                "<html><body>Bienvenue</body></html>",
+                Locale::En,
+            )
+            .unwrap();
+
+            assert!(outcome.fresh());
+        }
+
+        #[test]
+        fn should_reject_form_shown_again() {
+            let error = classify_login_redirect(StatusCode::OK, false, SIGN_IN_FORM, Locale::En)
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::WrongCredentials));
+        }
+
+        #[test]
+        fn should_read_known_flash_first() {
+            let body = SIGN_IN_FORM.replace(
+                "<form",
+                "<div class=\"alert\">Your account is locked.</div><form",
+            );
+
+            let error =
+                classify_login_redirect(StatusCode::OK, false, &body, Locale::En).unwrap_err();
+
+            assert!(matches!(error, UdaError::AccountLocked));
+        }
+
+        #[test]
+        fn should_fail_when_neither_redirected_nor_form() {
+            let error = classify_login_redirect(
+                StatusCode::OK,
+                false,
+                "<html><body>Bienvenue</body></html>",
+                Locale::En,
+            )
+            .unwrap_err();
+
+            assert!(matches!(error, UdaError::ConnectionFailed(None)));
+        }
+    }
+
+    mod classify_login_response {
+        use crate::error::UdaError;
+        use crate::locale::Locale;
+        use crate::login::{classify_login_response, INVALID_AUTHENTICITY_TOKEN_PAGE};
+        use reqwest::StatusCode;
+
+        #[test]
+        fn should_accept_signed_in_page() {
+            classify_login_response(
+                StatusCode::OK,
+                "<html><body>Signed in successfully</body></html>",
+                Locale::En,
+            )
+            .unwrap();
+        }
+
+        #[test]
+        fn should_accept_already_signed_in_page() {
+            classify_login_response(
+                StatusCode::OK,
+                "<html><body>You are already signed in</body></html>",
+                Locale::En,
+            )
+            .unwrap();
+        }
+
+        #[test]
+        fn should_accept_signed_in_flash_message() {
+            classify_login_response(
+                StatusCode::OK,
+                r#"<html><body><nav>Sign in</nav><div class="flash notice">Signed in successfully.</div></body></html>"#,
+                Locale::En,
+            )
+            .unwrap();
+        }
+
+        #[test]
+        fn should_accept_flash_message_with_whitespace_and_casing() {
+            classify_login_response(
+                StatusCode::OK,
+                "<html><body><div class=\"flash notice\">\n    <span>Signed   in\n  Successfully.</span>\n  </div></body></html>",
+                Locale::En,
+            )
+            .unwrap();
+        }
+
+        #[test]
+        fn should_reject_invalid_credentials_flash_message() {
+            let error = classify_login_response(
+                StatusCode::OK,
+                r#"<html><body><div class="flash alert"> Invalid User Account  email or password. </div></body></html>"#,
+                Locale::En,
+            )
+            .unwrap_err();
 
-            assert_eq!(AUTHENTICITY_TOKEN, token);
+            assert!(matches!(error, UdaError::WrongCredentials));
         }
 
         #[test]
-        fn should_not_get_authenticity_token_from_html() {
-            let body = "<html><body><div>What are ya lookin' for, son?</div></body></html>";
-            let html = Html::parse_document(body);
-            let error = get_authenticity_token_from_html(&html).unwrap_err();
+        fn should_reject_invalid_credentials_page() {
+            let error = classify_login_response(
+                StatusCode::OK,
+                "<html><body>Invalid User Account Email or password</body></html>",
+                Locale::En,
+            )
+            .unwrap_err();
+
+            assert!(matches!(error, UdaError::WrongCredentials));
+        }
+
+        #[test]
+        fn should_reject_locked_account_flash_message() {
+            let error = classify_login_response(
+                StatusCode::OK,
+                r#"<html><body><div class="flash alert">Your account is locked.</div></body></html>"#,
+                Locale::En,
+            )
+            .unwrap_err();
+
+            assert!(matches!(error, UdaError::AccountLocked));
+        }
+
+        #[test]
+        fn should_reject_unlock_instructions_page() {
+            let error = classify_login_response(
+                StatusCode::OK,
+                r#"<html><body><div class="flash notice">You will receive an email with instructions for how to unlock your account in a few minutes.</div></body></html>"#,
+                Locale::En,
+            )
+            .unwrap_err();
+
+            assert!(matches!(error, UdaError::AccountLocked));
+        }
+
+        #[test]
+        fn should_reject_locked_account_flash_message_in_french() {
+            let error = classify_login_response(
+                StatusCode::OK,
+                r#"<html><body><div class="flash alert">Votre compte est verrouillé.</div></body></html>"#,
+                Locale::Fr,
+            )
+            .unwrap_err();
+
+            assert!(matches!(error, UdaError::AccountLocked));
+        }
+
+        #[test]
+        fn should_reject_last_attempt_as_wrong_credentials() {
+            let error = classify_login_response(
+                StatusCode::OK,
+                r#"<html><body><div class="flash alert">You have one more attempt before your account is locked.</div></body></html>"#,
+                Locale::En,
+            )
+            .unwrap_err();
+
+            assert!(matches!(error, UdaError::WrongCredentials));
+        }
+
+        #[test]
+        fn should_reject_unknown_page() {
+            let error = classify_login_response(
+                StatusCode::OK,
+                "<html><body>What are ya lookin' for, son?</body></html>",
+                Locale::En,
+            )
+            .unwrap_err();
+
+            assert!(matches!(error, UdaError::ConnectionFailed(None)));
+        }
+
+        #[test]
+        fn should_reject_unprocessable_status() {
+            let error = classify_login_response(StatusCode::UNPROCESSABLE_ENTITY, "", Locale::En)
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::WrongCredentials));
+        }
+
+        #[test]
+        fn should_reject_invalid_authenticity_token() {
+            let error = classify_login_response(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                INVALID_AUTHENTICITY_TOKEN_PAGE,
+                Locale::En,
+            )
+            .unwrap_err();
+
+            assert!(matches!(error, UdaError::InvalidAuthenticityToken));
+        }
+
+        #[test]
+        fn should_reject_unexpected_status() {
+            let error = classify_login_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Signed in successfully",
+                Locale::En,
+            )
+            .unwrap_err();
 
-            assert!(matches!(error, UdaError::ConnectionFailed));
+            assert!(
+                matches!(error, UdaError::UnexpectedStatus(status) if status == StatusCode::INTERNAL_SERVER_ERROR)
+            );
         }
     }
 
     mod check_credentials {
         use crate::error::UdaError;
-        use crate::login::check_credentials;
+        use crate::locale::Locale;
+        use crate::login::{check_credentials, LoginConfig, SignInForm};
         use crate::login::{setup_check_credentials, AUTHENTICITY_TOKEN};
-        use crate::tools::tests::build_client;
+        use crate::retry::RetryPolicy;
+        use crate::tools::build_client;
+        use std::time::Duration;
         use wiremock::matchers::{body_string, method, path};
         use wiremock::{Mock, MockServer, ResponseTemplate};
 
+        fn sign_in_form(sign_in_url: &str, authenticity_token: &str) -> SignInForm {
+            SignInForm {
+                url: sign_in_url.to_owned(),
+                authenticity_token: authenticity_token.to_owned(),
+                hidden_fields: vec![],
+            }
+        }
+
         #[tokio::test]
         async fn should_check_credentials() {
             let client = build_client().unwrap();
@@ -331,10 +2073,16 @@ pub mod tests {
 
             check_credentials(
                 &client,
-                &mock_server.uri(),
-                AUTHENTICITY_TOKEN,
+                &sign_in_form(
+                    &format!("{}/en/users/sign_in", mock_server.uri()),
+                    AUTHENTICITY_TOKEN,
+                ),
                 "login",
                 "password",
+                &LoginConfig::default(),
+                &RetryPolicy::default(),
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -359,10 +2107,47 @@ pub mod tests {
 
             let error = check_credentials(
                 &client,
-                &mock_server.uri(),
-                AUTHENTICITY_TOKEN,
+                &sign_in_form(
+                    &format!("{}/en/users/sign_in", mock_server.uri()),
+                    AUTHENTICITY_TOKEN,
+                ),
+                "login",
+                "password",
+                &LoginConfig::default(),
+                &RetryPolicy::default(),
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+            assert!(matches!(error, UdaError::WrongCredentials));
+        }
+
+        #[tokio::test]
+        async fn should_fail_to_check_credentials_when_wrong_credentials_in_french() {
+            let client = build_client().unwrap();
+            let mock_server = MockServer::start().await;
+
+            Mock::given(method("POST"))
+                .and(path("/fr/users/sign_in"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(
+                    "<html><body>Courriel ou mot de passe incorrect.</body></html>",
+                ))
+                .mount(&mock_server)
+                .await;
+
+            let error = check_credentials(
+                &client,
+                &sign_in_form(
+                    &format!("{}/fr/users/sign_in", mock_server.uri()),
+                    AUTHENTICITY_TOKEN,
+                ),
                 "login",
                 "password",
+                &LoginConfig::for_locale(Locale::Fr),
+                &RetryPolicy::default(),
+                None,
+                None,
             )
             .await
             .unwrap_err();
@@ -387,14 +2172,328 @@ pub mod tests {
 
             let error = check_credentials(
                 &client,
-                &mock_server.uri(),
-                authenticity_token,
+                &sign_in_form(
+                    &format!("{}/en/users/sign_in", mock_server.uri()),
+                    authenticity_token,
+                ),
+                "login",
+                "password",
+                &LoginConfig::default(),
+                &RetryPolicy::default(),
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+            assert!(matches!(error, UdaError::UnexpectedStatus(status) if status == 500));
+        }
+
+        #[tokio::test]
+        async fn should_fail_to_check_credentials_when_unprocessable() {
+            let client = build_client().unwrap();
+            let mock_server = MockServer::start().await;
+
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(
+                    ResponseTemplate::new(422)
+                        .set_body_string("<html><body>Something went wrong</body></html>"),
+                )
+                .mount(&mock_server)
+                .await;
+
+            let error = check_credentials(
+                &client,
+                &sign_in_form(
+                    &format!("{}/en/users/sign_in", mock_server.uri()),
+                    AUTHENTICITY_TOKEN,
+                ),
+                "login",
+                "password",
+                &LoginConfig::default(),
+                &RetryPolicy::default(),
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+            assert!(matches!(error, UdaError::WrongCredentials));
+        }
+
+        #[tokio::test]
+        async fn should_fail_to_check_credentials_when_unknown_body() {
+            let client = build_client().unwrap();
+            let mock_server = MockServer::start().await;
+
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_string("<html><body>Please slow down</body></html>"),
+                )
+                .mount(&mock_server)
+                .await;
+
+            let error = check_credentials(
+                &client,
+                &sign_in_form(
+                    &format!("{}/en/users/sign_in", mock_server.uri()),
+                    AUTHENTICITY_TOKEN,
+                ),
+                "login",
+                "password",
+                &LoginConfig::default(),
+                &RetryPolicy::default(),
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+            assert!(matches!(error, UdaError::ConnectionFailed(None)));
+        }
+
+        #[tokio::test]
+        async fn should_fail_to_check_credentials_when_rate_limited() {
+            let client = build_client().unwrap();
+            let mock_server = MockServer::start().await;
+
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "120"))
+                .mount(&mock_server)
+                .await;
+
+            let error = check_credentials(
+                &client,
+                &sign_in_form(
+                    &format!("{}/en/users/sign_in", mock_server.uri()),
+                    AUTHENTICITY_TOKEN,
+                ),
                 "login",
                 "password",
+                &LoginConfig::default(),
+                &RetryPolicy::default(),
+                None,
+                None,
             )
             .await
             .unwrap_err();
-            assert!(matches!(error, UdaError::ConnectionFailed));
+            assert!(matches!(
+                error,
+                UdaError::RateLimited { retry_after: Some(retry_after) } if retry_after == Duration::from_secs(120)
+            ));
+        }
+    }
+
+    mod sign_out {
+        use crate::error::UdaError;
+        use crate::login::{
+            authenticate_into_uda, get_sign_in_form, setup_authentication,
+            setup_authenticity_token, setup_sign_out, setup_signed_in_home_page, sign_out,
+            sign_out_with_config, LoginConfig, AUTHENTICITY_TOKEN,
+        };
+        use crate::retry::RetryPolicy;
+        use crate::tools::build_client;
+        use reqwest::header::COOKIE;
+        use wiremock::matchers::{body_string, header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn should_sign_out() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            setup_sign_out(&mock_server).await;
+
+            let client = build_client().unwrap();
+            authenticate_into_uda(
+                &client,
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap();
+
+            sign_out(&client, &mock_server.uri()).await.unwrap();
+        }
+
+        #[tokio::test]
+        async fn should_clear_session_cookie_when_signing_out() {
+            let mock_server = MockServer::start().await;
+            setup_authenticity_token(&mock_server).await;
+            setup_sign_out(&mock_server).await;
+            let params = format!(
+                "user%5Bemail%5D=login&user%5Bpassword%5D=password&authenticity_token={AUTHENTICITY_TOKEN}&utf8=%E2%9C%93"
+            );
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .and(body_string(&params))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .append_header("Set-Cookie", "_uda_session=abc; path=/")
+                        .set_body_string("Signed in successfully"),
+                )
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            authenticate_into_uda(&client, &mock_server.uri(), "login", "password")
+                .await
+                .unwrap();
+            sign_out(&client, &mock_server.uri()).await.unwrap();
+            get_sign_in_form(
+                &client,
+                &format!("{}/en/users/sign_in", mock_server.uri()),
+                &RetryPolicy::default(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+            let requests = mock_server.received_requests().await.unwrap();
+            let sign_out_request = requests
+                .iter()
+                .find(|request| request.method.as_str() == "DELETE")
+                .unwrap();
+            assert_eq!("_uda_session=abc", sign_out_request.headers[COOKIE]);
+            let next_request = requests.last().unwrap();
+            assert!(next_request.headers.get(COOKIE).is_none());
+        }
+
+        #[tokio::test]
+        async fn should_send_csrf_token_when_signing_out() {
+            let mock_server = MockServer::start().await;
+            setup_signed_in_home_page(&mock_server, "/en").await;
+            Mock::given(method("DELETE"))
+                .and(path("/en/users/sign_out"))
+                .and(header("X-CSRF-Token", AUTHENTICITY_TOKEN))
+                .respond_with(ResponseTemplate::new(204))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+            // Rails rejects a DELETE without the token
+            Mock::given(method("DELETE"))
+                .and(path("/en/users/sign_out"))
+                .respond_with(ResponseTemplate::new(422))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            sign_out(&client, &mock_server.uri()).await.unwrap();
+        }
+
+        #[tokio::test]
+        async fn should_sign_out_when_already_signed_out() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/en"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(
+                    r#"<html><body><a href="/en/users/sign_in">Sign in</a></body></html>"#,
+                ))
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("DELETE"))
+                .and(path("/en/users/sign_out"))
+                .respond_with(ResponseTemplate::new(204))
+                .expect(0)
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            sign_out(&client, &mock_server.uri()).await.unwrap();
+        }
+
+        #[tokio::test]
+        async fn should_fail_to_sign_out_when_still_signed_in() {
+            let mock_server = MockServer::start().await;
+            setup_signed_in_home_page(&mock_server, "/en").await;
+            Mock::given(method("DELETE"))
+                .and(path("/en/users/sign_out"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(
+                    r#"<html><body><a href="/en/users/sign_out">Sign out</a></body></html>"#,
+                ))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let error = sign_out(&client, &mock_server.uri()).await.unwrap_err();
+            assert!(matches!(error, UdaError::ConnectionFailed(_)));
+        }
+
+        #[tokio::test]
+        async fn should_fail_to_sign_out_when_unreachable() {
+            let mock_server = MockServer::start().await;
+            setup_signed_in_home_page(&mock_server, "/en").await;
+            Mock::given(method("DELETE"))
+                .and(path("/en/users/sign_out"))
+                .respond_with(ResponseTemplate::new(500))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let error = sign_out(&client, &mock_server.uri()).await.unwrap_err();
+            assert!(matches!(error, UdaError::UnexpectedStatus(status) if status == 500));
+        }
+
+        #[tokio::test]
+        async fn should_fall_back_to_get_when_delete_not_routed() {
+            for status in [404, 405] {
+                let mock_server = MockServer::start().await;
+                setup_signed_in_home_page(&mock_server, "/en").await;
+                Mock::given(method("DELETE"))
+                    .and(path("/en/users/sign_out"))
+                    .respond_with(ResponseTemplate::new(status))
+                    .mount(&mock_server)
+                    .await;
+                Mock::given(method("GET"))
+                    .and(path("/en/users/sign_out"))
+                    .respond_with(
+                        ResponseTemplate::new(200)
+                            .set_body_string("<html><body>Signed out successfully.</body></html>"),
+                    )
+                    .expect(1)
+                    .mount(&mock_server)
+                    .await;
+
+                let client = build_client().unwrap();
+                sign_out(&client, &mock_server.uri()).await.unwrap();
+            }
+        }
+
+        #[tokio::test]
+        async fn should_sign_out_when_session_already_invalid() {
+            let mock_server = MockServer::start().await;
+            setup_signed_in_home_page(&mock_server, "/en").await;
+            Mock::given(method("DELETE"))
+                .and(path("/en/users/sign_out"))
+                .respond_with(ResponseTemplate::new(401))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            sign_out(&client, &mock_server.uri()).await.unwrap();
+        }
+
+        #[tokio::test]
+        async fn should_sign_out_next_to_configured_sign_in_form() {
+            let mock_server = MockServer::start().await;
+            setup_signed_in_home_page(&mock_server, "/uda/fr").await;
+            Mock::given(method("DELETE"))
+                .and(path("/uda/fr/users/sign_out"))
+                .respond_with(ResponseTemplate::new(204))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            sign_out_with_config(
+                &client,
+                &mock_server.uri(),
+                &LoginConfig::new("uda/fr/users/sign_in"),
+            )
+            .await
+            .unwrap();
         }
     }
 }
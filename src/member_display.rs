@@ -0,0 +1,62 @@
+use std::fmt::{Display, Formatter};
+use uda_dto::uda_member::UdaMember;
+
+/// Human-readable line describing a member, e.g. `#1 Jon Doe <jon.doe@email.com> (Le club de test)`.
+/// The club is omitted when the member has none.
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayedMember<'a>(&'a UdaMember);
+
+impl Display for DisplayedMember<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let member = self.0;
+        write!(
+            f,
+            "#{} {} {} <{}>",
+            member.id(),
+            member.first_name(),
+            member.last_name(),
+            member.email()
+        )?;
+        if let Some(club) = member.club() {
+            write!(f, " ({club})")?;
+        }
+        Ok(())
+    }
+}
+
+/// `UdaMember` being defined in `uda-dto`, `Display` can't be implemented on it directly:
+/// `member.display()` provides it instead, like `Path::display`.
+pub trait DisplayMember {
+    fn display(&self) -> DisplayedMember<'_>;
+}
+
+impl DisplayMember for UdaMember {
+    fn display(&self) -> DisplayedMember<'_> {
+        DisplayedMember(self)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod display {
+        use crate::member_display::DisplayMember;
+        use crate::retrieve_members::get_expected_member;
+
+        #[test]
+        fn success() {
+            let lines = get_expected_member()
+                .iter()
+                .map(|member| member.display().to_string())
+                .collect::<Vec<_>>();
+
+            assert_eq!(
+                vec![
+                    "#1 Jon Doe <jon.doe@email.com> (Le club de test)",
+                    "#2 Jonette Snow <jonette.snow@email.com>",
+                    "#1999 Kris Holm <kris.holm@email.com> (KH Team)",
+                ],
+                lines
+            );
+        }
+    }
+}
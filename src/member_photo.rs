@@ -0,0 +1,261 @@
+use crate::error::UdaError::{
+    CantReadPageContent, InvalidUrl, LackOfPermissions, UnexpectedStatus,
+};
+use crate::error::{log_error_and_return, log_message_and_return, UdaError};
+use crate::ids::MemberId;
+use crate::locale::Locale;
+use crate::retrieve_members::registrant_page_url;
+use crate::Result;
+use bytes::Bytes;
+use derive_getters::Getters;
+use log::{debug, error};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, Response, StatusCode};
+use scraper::{Html, Selector};
+use std::sync::Arc;
+use url::Url;
+
+/// Elements registrant pages display the photo in, depending on the UDA version.
+const PHOTO_SELECTOR: &str = "img.registrant_photo, img.photo, .photo img";
+
+/// Signatures the content of the images UDA accepts as photos start with, along with their media type.
+const IMAGE_SIGNATURES: [(&[u8], &str); 4] = [
+    (b"\xFF\xD8\xFF", "image/jpeg"),
+    (b"\x89PNG\r\n\x1A\n", "image/png"),
+    (b"GIF8", "image/gif"),
+    (b"RIFF", "image/webp"),
+];
+
+/// Photo of a member, e.g. to print it on their ID badge.
+#[derive(Debug, Clone, PartialEq, Getters)]
+pub struct MemberPhoto {
+    bytes: Bytes,
+    /// Media type of the photo, e.g. `image/jpeg`, as served by UDA or else detected from its content.
+    content_type: Option<String>,
+}
+
+/// Retrieve the photo shown on the registrant page of the member, if any.
+/// Returns `None` when the page shows no photo, or when UDA doesn't know the member.
+pub async fn retrieve_member_photo(
+    client: &Client,
+    base_url: &str,
+    id: MemberId,
+) -> Result<Option<MemberPhoto>> {
+    let url = registrant_page_url(base_url, Locale::default(), id)?;
+    let response = get(client, &url, "registrant page").await?;
+    if response.status() == StatusCode::NOT_FOUND {
+        debug!("Registrant not found [id: {id}]");
+        return Ok(None);
+    }
+    let body = read(response).await?.1;
+    let Some(source) = find_photo_source(&String::from_utf8_lossy(&body))? else {
+        debug!("Registrant has no photo [id: {id}]");
+        return Ok(None);
+    };
+
+    let photo_url = Url::parse(&url)
+        .and_then(|page_url| page_url.join(&source))
+        .map_err(|error| {
+            error!("Can't resolve photo URL [id: {id}, src: {source}, error: {error}]");
+            InvalidUrl(source.clone())
+        })?;
+    let response = get(client, photo_url.as_str(), "photo").await?;
+    if response.status() == StatusCode::NOT_FOUND {
+        error!("Photo not found [id: {id}, url: {photo_url}]");
+        return Err(UnexpectedStatus(StatusCode::NOT_FOUND));
+    }
+    let (content_type, bytes) = read(response).await?;
+    let content_type = content_type.or_else(|| detect_image_type(&bytes).map(str::to_owned));
+    debug!("Retrieved photo [id: {id}, content_type: {content_type:?}]");
+
+    Ok(Some(MemberPhoto {
+        bytes,
+        content_type,
+    }))
+}
+
+/// Send a GET request, failing unless UDA answers successfully or doesn't know the resource.
+async fn get(client: &Client, url: &str, resource: &str) -> Result<Response> {
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(log_message_and_return(
+            &format!("Can't reach {resource}"),
+            UdaError::connection,
+        ))?;
+
+    let status = response.status();
+    match status {
+        status if status.is_success() || status == StatusCode::NOT_FOUND => Ok(response),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            error!("Can't access {resource}. Lack of permissions? [url: {url}, status: {status}]");
+            Err(LackOfPermissions)
+        }
+        _ => {
+            error!("Can't reach {resource} [url: {url}, status: {status}]");
+            Err(UnexpectedStatus(status))
+        }
+    }
+}
+
+/// Content of the response, along with its media type, if given.
+async fn read(response: Response) -> Result<(Option<String>, Bytes)> {
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map(str::to_owned);
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(log_error_and_return(|error| {
+            CantReadPageContent(Some(Arc::new(error)))
+        }))?;
+    Ok((content_type, bytes))
+}
+
+fn find_photo_source(body: &str) -> Result<Option<String>> {
+    let selector = Selector::parse(PHOTO_SELECTOR).map_err(UdaError::from)?;
+    let document = Html::parse_document(body);
+    let source = document
+        .select(&selector)
+        .filter_map(|image| image.attr("src"))
+        .map(str::trim)
+        .find(|source| !source.is_empty())
+        .map(str::to_owned);
+    Ok(source)
+}
+
+/// Media type of the image, told from the signature its content starts with.
+fn detect_image_type(content: &[u8]) -> Option<&'static str> {
+    IMAGE_SIGNATURES
+        .iter()
+        .find(|(signature, _)| content.starts_with(signature))
+        .map(|(_, content_type)| *content_type)
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod retrieve_member_photo {
+        use crate::error::UdaError;
+        use crate::ids::MemberId;
+        use crate::member_photo::retrieve_member_photo;
+        use crate::tools::build_client;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        const PNG_CONTENT: &[u8] = b"\x89PNG\r\n\x1A\nnot really a picture";
+
+        async fn setup_registrant_page(mock_server: &MockServer, body: &str) {
+            Mock::given(method("GET"))
+                .and(path("/en/registrants/1"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(body))
+                .mount(mock_server)
+                .await;
+        }
+
+        #[tokio::test]
+        async fn success() {
+            let mock_server = MockServer::start().await;
+            setup_registrant_page(
+                &mock_server,
+                r#"<html><body><h1>Jon Doe</h1><div class="photo"><img src="/system/photos/1/badge.png" alt="Jon Doe"></div></body></html>"#,
+            )
+            .await;
+            Mock::given(method("GET"))
+                .and(path("/system/photos/1/badge.png"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(PNG_CONTENT))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let photo = retrieve_member_photo(&client, &mock_server.uri(), MemberId(1))
+                .await
+                .unwrap()
+                .unwrap();
+
+            assert_eq!(PNG_CONTENT, photo.bytes().as_ref());
+            assert_eq!(&Some("image/png".to_owned()), photo.content_type());
+        }
+
+        #[tokio::test]
+        async fn keep_served_content_type() {
+            let mock_server = MockServer::start().await;
+            setup_registrant_page(
+                &mock_server,
+                r#"<html><body><img class="photo" src="photos/1.jpg"></body></html>"#,
+            )
+            .await;
+            Mock::given(method("GET"))
+                .and(path("/en/registrants/photos/1.jpg"))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_raw(PNG_CONTENT.to_vec(), "image/jpeg"),
+                )
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let photo = retrieve_member_photo(&client, &mock_server.uri(), MemberId(1))
+                .await
+                .unwrap()
+                .unwrap();
+
+            assert_eq!(&Some("image/jpeg".to_owned()), photo.content_type());
+        }
+
+        #[tokio::test]
+        async fn none_when_no_photo() {
+            let mock_server = MockServer::start().await;
+            setup_registrant_page(
+                &mock_server,
+                "<html><body><h1>Jon Doe</h1><img src=\"/logo.png\"></body></html>",
+            )
+            .await;
+
+            let client = build_client().unwrap();
+            let photo = retrieve_member_photo(&client, &mock_server.uri(), MemberId(1))
+                .await
+                .unwrap();
+
+            assert_eq!(None, photo);
+        }
+
+        #[tokio::test]
+        async fn fail_when_lack_of_permissions() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/en/registrants/1"))
+                .respond_with(ResponseTemplate::new(403))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let error = retrieve_member_photo(&client, &mock_server.uri(), MemberId(1))
+                .await
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::LackOfPermissions));
+        }
+    }
+
+    mod detect_image_type {
+        use crate::member_photo::detect_image_type;
+
+        #[test]
+        fn detect_known_signatures() {
+            assert_eq!(
+                Some("image/jpeg"),
+                detect_image_type(b"\xFF\xD8\xFF\xE0rest")
+            );
+            assert_eq!(Some("image/gif"), detect_image_type(b"GIF89a"));
+        }
+
+        #[test]
+        fn none_when_unknown() {
+            assert_eq!(None, detect_image_type(b"<html>"));
+            assert_eq!(None, detect_image_type(b""));
+        }
+    }
+}
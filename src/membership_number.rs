@@ -0,0 +1,153 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Characters a membership number may be made of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MembershipNumberCharset {
+    /// ASCII digits only, e.g. `012048`.
+    #[default]
+    Digits,
+    /// ASCII letters and digits, e.g. `FR12048`.
+    Alphanumeric,
+}
+
+impl MembershipNumberCharset {
+    fn contains(self, c: char) -> bool {
+        match self {
+            MembershipNumberCharset::Digits => c.is_ascii_digit(),
+            MembershipNumberCharset::Alphanumeric => c.is_ascii_alphanumeric(),
+        }
+    }
+}
+
+/// Rules membership numbers are checked against, e.g. the fixed length a federation expects.
+/// Defaults to digits only, whatever their number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MembershipNumberFormat {
+    length: Option<usize>,
+    charset: MembershipNumberCharset,
+}
+
+impl MembershipNumberFormat {
+    pub fn new(length: Option<usize>, charset: MembershipNumberCharset) -> Self {
+        Self { length, charset }
+    }
+}
+
+/// Reasons a value isn't a valid membership number.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum InvalidMembershipNumber {
+    #[error("The membership number is empty")]
+    Empty,
+    #[error("The membership number has the wrong length [expected: {expected}, actual: {actual}]")]
+    WrongLength { expected: usize, actual: usize },
+    #[error("The membership number contains an unexpected character [character: {0}]")]
+    UnexpectedCharacter(char),
+}
+
+/// Number of a member in their organization, checked against a [`MembershipNumberFormat`].
+/// Surrounding whitespaces are ignored, leading zeros are kept.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MembershipNumber(String);
+
+impl MembershipNumber {
+    /// Check the value against the format, rather than the default one used by [`FromStr`].
+    pub fn parse(
+        value: &str,
+        format: &MembershipNumberFormat,
+    ) -> Result<Self, InvalidMembershipNumber> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(InvalidMembershipNumber::Empty);
+        }
+        if let Some(c) = value.chars().find(|&c| !format.charset.contains(c)) {
+            return Err(InvalidMembershipNumber::UnexpectedCharacter(c));
+        }
+        let length = value.chars().count();
+        match format.length {
+            Some(expected) if expected != length => Err(InvalidMembershipNumber::WrongLength {
+                expected,
+                actual: length,
+            }),
+            _ => Ok(Self(value.to_owned())),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for MembershipNumber {
+    type Err = InvalidMembershipNumber;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::parse(value, &MembershipNumberFormat::default())
+    }
+}
+
+impl From<MembershipNumber> for String {
+    fn from(value: MembershipNumber) -> Self {
+        value.0
+    }
+}
+
+impl Display for MembershipNumber {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod parse {
+        use crate::membership_number::{
+            InvalidMembershipNumber, MembershipNumber, MembershipNumberCharset,
+            MembershipNumberFormat,
+        };
+
+        #[test]
+        fn accept_valid_number() {
+            let number: MembershipNumber = " 012048 ".parse().unwrap();
+
+            assert_eq!("012048", number.as_str());
+        }
+
+        #[test]
+        fn reject_alphabetic_value() {
+            assert_eq!(
+                Err(InvalidMembershipNumber::UnexpectedCharacter('I')),
+                "ID 012048".parse::<MembershipNumber>()
+            );
+        }
+
+        #[test]
+        fn reject_empty_value() {
+            assert_eq!(
+                Err(InvalidMembershipNumber::Empty),
+                "  ".parse::<MembershipNumber>()
+            );
+        }
+
+        #[test]
+        fn check_custom_format() {
+            let format =
+                MembershipNumberFormat::new(Some(7), MembershipNumberCharset::Alphanumeric);
+
+            assert_eq!(
+                "FR12048",
+                MembershipNumber::parse("FR12048", &format)
+                    .unwrap()
+                    .as_str()
+            );
+            assert_eq!(
+                Err(InvalidMembershipNumber::WrongLength {
+                    expected: 7,
+                    actual: 6
+                }),
+                MembershipNumber::parse("012048", &format)
+            );
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use crate::confirm_member::retrieve_csrf_from_html;
+use crate::error::UdaError::{
+    CantReadPageContent, FeatureNotAvailable, LackOfPermissions, UnexpectedStatus,
+};
+use crate::error::{log_error_and_return, log_message_and_return, UdaError};
+use crate::ids::OrganizationId;
+use crate::locale::Locale;
+use crate::tools::build_url;
+use crate::Result;
+use derive_getters::Getters;
+use log::{debug, error};
+use reqwest::{Client, StatusCode};
+use scraper::{Html, Selector};
+use std::sync::Arc;
+
+/// An organization a UDA admin belongs to.
+/// The members export only lists the members of the organization currently selected.
+#[derive(Debug, Clone, PartialEq, Getters)]
+pub struct Organization {
+    id: OrganizationId,
+    name: String,
+}
+
+impl Organization {
+    pub fn new(id: OrganizationId, name: String) -> Self {
+        Self { id, name }
+    }
+}
+
+/// Retrieve the organization the logged-in admin is currently acting for, as shown on the dashboard.
+pub async fn retrieve_current_organization(
+    client: &Client,
+    base_url: &str,
+) -> Result<Organization> {
+    let body = get_dashboard(client, base_url, Locale::default()).await?;
+    get_current_organization_from_html(&body)
+}
+
+/// Make the logged-in admin act for the given organization, e.g. before exporting its members.
+pub async fn select_organization(
+    client: &Client,
+    base_url: &str,
+    id: OrganizationId,
+) -> Result<()> {
+    let locale = Locale::default();
+    let body = get_dashboard(client, base_url, locale).await?;
+    let csrf_token = retrieve_csrf_from_html(&body).await?;
+
+    let url = build_url(
+        base_url,
+        &format!("{}/organizations/{id}/select", locale.code()),
+    )?;
+    let response = client
+        .post(url)
+        .header("X-CSRF-Token", csrf_token)
+        .send()
+        .await
+        .map_err(log_message_and_return(
+            "Can't select organization on UDA",
+            UdaError::connection,
+        ))?;
+
+    let status = response.status();
+    match status {
+        status if status.is_success() => {
+            debug!("Selected organization on UDA [id: {id}]");
+            Ok(())
+        }
+        // As for confirming members, UDA hides organizations the user doesn't belong to
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::NOT_FOUND => {
+            error!("Can't select organization. Lack of permissions? [id: {id}, status: {status}]");
+            Err(LackOfPermissions)
+        }
+        _ => {
+            error!("Can't select organization [id: {id}, status: {status}]");
+            Err(UnexpectedStatus(status))
+        }
+    }
+}
+
+async fn get_dashboard(client: &Client, base_url: &str, locale: Locale) -> Result<String> {
+    let url = build_url(base_url, &format!("{}/dashboard", locale.code()))?;
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(log_message_and_return(
+            "Can't reach dashboard",
+            UdaError::connection,
+        ))?;
+
+    let status = response.status();
+    if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+        error!("Can't access dashboard. Lack of permissions? [status: {status}]");
+        return Err(LackOfPermissions);
+    } else if !status.is_success() {
+        error!("Can't reach dashboard: {status:?}");
+        return Err(UnexpectedStatus(status));
+    }
+
+    response.text().await.map_err(log_error_and_return(|error| {
+        CantReadPageContent(Some(Arc::new(error)))
+    }))
+}
+
+fn get_current_organization_from_html(body: &str) -> Result<Organization> {
+    let selector = Selector::parse("#current_organization a").map_err(UdaError::from)?;
+    let document = Html::parse_document(body);
+
+    let organization = document.select(&selector).next().and_then(|link| {
+        let id = link
+            .attr("href")?
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()?
+            .parse::<OrganizationId>()
+            .ok()?;
+        let name = link.text().collect::<String>();
+        Some(Organization::new(id, name.trim().to_owned()))
+    });
+
+    organization.ok_or_else(|| {
+        error!("Can't find current organization on dashboard");
+        FeatureNotAvailable("organizations".to_owned())
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    const DASHBOARD_BODY: &str = r##"<html><head><meta name="csrf-token" content="csrf_token"></head><body><div id="current_organization">Acting for <a href="/en/organizations/7">Fédération Française de Monocycle</a></div></body></html>"##;
+
+    mod retrieve_current_organization {
+        use crate::error::UdaError;
+        use crate::ids::OrganizationId;
+        use crate::organizations::tests::DASHBOARD_BODY;
+        use crate::organizations::{retrieve_current_organization, Organization};
+        use crate::tools::build_client;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn success() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/en/dashboard"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(DASHBOARD_BODY))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let organization = retrieve_current_organization(&client, &mock_server.uri())
+                .await
+                .unwrap();
+
+            assert_eq!(
+                Organization::new(
+                    OrganizationId(7),
+                    "Fédération Française de Monocycle".to_owned()
+                ),
+                organization
+            );
+        }
+
+        #[tokio::test]
+        async fn fail_when_no_organization() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/en/dashboard"))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_string("<html><body></body></html>"),
+                )
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let error = retrieve_current_organization(&client, &mock_server.uri())
+                .await
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::FeatureNotAvailable(_)));
+        }
+    }
+
+    mod select_organization {
+        use crate::error::UdaError;
+        use crate::ids::OrganizationId;
+        use crate::organizations::select_organization;
+        use crate::organizations::tests::DASHBOARD_BODY;
+        use crate::tools::build_client;
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        async fn setup_dashboard(mock_server: &MockServer) {
+            Mock::given(method("GET"))
+                .and(path("/en/dashboard"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(DASHBOARD_BODY))
+                .mount(mock_server)
+                .await;
+        }
+
+        #[tokio::test]
+        async fn success() {
+            let mock_server = MockServer::start().await;
+            setup_dashboard(&mock_server).await;
+            Mock::given(method("POST"))
+                .and(path("/en/organizations/12/select"))
+                .and(header("X-CSRF-Token", "csrf_token"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            select_organization(&client, &mock_server.uri(), OrganizationId(12))
+                .await
+                .unwrap();
+        }
+
+        #[tokio::test]
+        async fn fail_when_lack_of_permissions() {
+            let mock_server = MockServer::start().await;
+            setup_dashboard(&mock_server).await;
+            Mock::given(method("POST"))
+                .and(path("/en/organizations/12/select"))
+                .respond_with(ResponseTemplate::new(403))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let error = select_organization(&client, &mock_server.uri(), OrganizationId(12))
+                .await
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::LackOfPermissions));
+        }
+    }
+}
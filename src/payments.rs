@@ -0,0 +1,179 @@
+use crate::error::UdaError;
+use crate::error::UdaError::{CantReadPageContent, LackOfPermissions, UnexpectedStatus};
+use crate::error::{log_error_and_return, log_message_and_return};
+use crate::ids::{CompetitionId, MemberId};
+use crate::locale::Locale;
+use crate::tools::build_url;
+use crate::Result;
+use derive_getters::Getters;
+use log::{error, warn};
+use reqwest::{Client, StatusCode};
+use scraper::{ElementRef, Html, Selector};
+use std::sync::Arc;
+
+/// Whether a registrant has paid for a competition, as listed on its payments page.
+/// Amounts are kept as displayed by UDA, currency included.
+#[derive(Debug, Clone, PartialEq, Getters)]
+pub struct PaymentStatus {
+    registrant_id: MemberId,
+    amount_due: String,
+    amount_paid: String,
+    status: String,
+}
+
+impl PaymentStatus {
+    pub fn new(
+        registrant_id: MemberId,
+        amount_due: String,
+        amount_paid: String,
+        status: String,
+    ) -> Self {
+        Self {
+            registrant_id,
+            amount_due,
+            amount_paid,
+            status,
+        }
+    }
+}
+
+/// Retrieve the payment status of each registrant of a competition.
+pub async fn retrieve_payment_status(
+    client: &Client,
+    base_url: &str,
+    competition_id: CompetitionId,
+) -> Result<Vec<PaymentStatus>> {
+    let url = payments_url(base_url, Locale::default(), competition_id)?;
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(log_message_and_return(
+            "Can't reach payments page",
+            UdaError::connection,
+        ))?;
+
+    let status = response.status();
+    if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+        error!("Can't access payments page. Lack of permissions? [status: {status}]");
+        return Err(LackOfPermissions);
+    } else if !status.is_success() {
+        error!("Can't reach payments page: {status:?}");
+        return Err(UnexpectedStatus(status));
+    }
+
+    let body = response
+        .text()
+        .await
+        .map_err(log_error_and_return(|error| {
+            CantReadPageContent(Some(Arc::new(error)))
+        }))?;
+
+    get_payment_status_from_html(&body)
+}
+
+fn payments_url(base_url: &str, locale: Locale, competition_id: CompetitionId) -> Result<String> {
+    build_url(
+        base_url,
+        &format!("{}/competitions/{competition_id}/payments", locale.code()),
+    )
+}
+
+fn get_payment_status_from_html(body: &str) -> Result<Vec<PaymentStatus>> {
+    let selector = Selector::parse(r"tbody tr").map_err(UdaError::from)?;
+    let document = Html::parse_document(body);
+
+    let rows = document.select(&selector);
+
+    Ok(rows
+        .flat_map(|row| get_payment_status_from_row(&row))
+        .collect())
+}
+
+fn get_payment_status_from_row(row: &ElementRef) -> Option<PaymentStatus> {
+    let selector = Selector::parse("td").ok()?;
+    let cells = row
+        .select(&selector)
+        .map(|cell| cell.text().collect::<String>().trim().to_owned())
+        .collect::<Vec<_>>();
+
+    let [registrant_id, amount_due, amount_paid, status] = &cells[..] else {
+        warn!("Ignoring payment because wrongly formatted [row: {row:?}]");
+        return None;
+    };
+    let Ok(registrant_id) = registrant_id.parse() else {
+        warn!("Ignoring payment because of invalid registrant id [id: {registrant_id}]");
+        return None;
+    };
+
+    Some(PaymentStatus::new(
+        registrant_id,
+        amount_due.to_owned(),
+        amount_paid.to_owned(),
+        status.to_owned(),
+    ))
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod retrieve_payment_status {
+        use crate::error::UdaError;
+        use crate::ids::{CompetitionId, MemberId};
+        use crate::payments::{retrieve_payment_status, PaymentStatus};
+        use crate::tools::build_client;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        const PAYMENTS_BODY: &str = r##"<html><head></head><body><div id="main"><h1>Payments</h1><table class="sortable"><thead><tr><th>ID</th><th>Amount Due</th><th>Amount Paid</th><th>Status</th></tr></thead><tbody><tr><td>1</td><td>$50.00</td><td>$50.00</td><td>Paid</td></tr><tr><td>2</td><td>$50.00</td><td>$0.00</td><td>Unpaid</td></tr><tr><td>Total</td><td>$100.00</td><td>$50.00</td><td></td></tr></tbody></table></div></body></html>"##;
+
+        #[tokio::test]
+        async fn success() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/en/competitions/12/payments"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(PAYMENTS_BODY))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let payments = retrieve_payment_status(&client, &mock_server.uri(), CompetitionId(12))
+                .await
+                .unwrap();
+
+            assert_eq!(
+                vec![
+                    PaymentStatus::new(
+                        MemberId(1),
+                        "$50.00".to_owned(),
+                        "$50.00".to_owned(),
+                        "Paid".to_owned()
+                    ),
+                    PaymentStatus::new(
+                        MemberId(2),
+                        "$50.00".to_owned(),
+                        "$0.00".to_owned(),
+                        "Unpaid".to_owned()
+                    ),
+                ],
+                payments
+            );
+        }
+
+        #[tokio::test]
+        async fn fail_when_lack_of_permissions() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/en/competitions/12/payments"))
+                .respond_with(ResponseTemplate::new(401))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let error = retrieve_payment_status(&client, &mock_server.uri(), CompetitionId(12))
+                .await
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::LackOfPermissions));
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use crate::error::UdaError::ConnectionFailed;
+use crate::error::{log_error_and_return, log_message_and_return, UdaError};
+use crate::locale::Locale;
+use crate::login::get_authenticity_token_from_html;
+use crate::tools::build_url;
+use crate::Result;
+use derive_getters::Getters;
+use log::{debug, error};
+use reqwest::Client;
+use scraper::{Html, Selector};
+
+/// What could be learnt about a UDA instance without logging in.
+#[derive(Debug, Clone, PartialEq, Getters)]
+pub struct InstanceInfo {
+    /// Locale the sign-in page is served in, when UDA supports it.
+    locale: Option<Locale>,
+    /// Whether the sign-in page displays a form to log in with a password.
+    has_login_form: bool,
+    /// Text of the page footer, which usually tells the version of UDA.
+    footer: Option<String>,
+}
+
+/// Check the instance is reachable and is actually UDA, by looking for the sign-in form.
+pub async fn probe_instance(client: &Client, base_url: &str) -> Result<InstanceInfo> {
+    let url = build_url(
+        base_url,
+        &format!("{}/users/sign_in", Locale::default().code()),
+    )?;
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(log_message_and_return(
+            "Can't reach UDA instance",
+            UdaError::connection,
+        ))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        error!("Can't reach UDA instance. Is it up? [status: {status}]");
+        return Err(ConnectionFailed(None));
+    }
+
+    let body = response
+        .text()
+        .await
+        .map_err(log_error_and_return(UdaError::connection))?;
+
+    get_instance_info_from_html(&body)
+}
+
+fn get_instance_info_from_html(body: &str) -> Result<InstanceInfo> {
+    let document = Html::parse_document(body);
+    // Any Rails form has it, but it's required to log in anyway
+    get_authenticity_token_from_html(&document)
+        .map_err(log_message_and_return("Not a UDA instance", |_| {
+            ConnectionFailed(None)
+        }))?;
+
+    let locale = document
+        .root_element()
+        .attr("lang")
+        .and_then(Locale::from_code);
+    let password_selector = Selector::parse(r#"input[name="user[password]"]"#)?;
+    let has_login_form = document.select(&password_selector).next().is_some();
+    let footer_selector = Selector::parse("footer")?;
+    let footer = document
+        .select(&footer_selector)
+        .next()
+        .map(|footer| {
+            footer
+                .text()
+                .map(str::trim)
+                .filter(|text| !text.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|footer| !footer.is_empty());
+
+    debug!("UDA instance found [locale: {locale:?}, footer: {footer:?}]");
+    Ok(InstanceInfo {
+        locale,
+        has_login_form,
+        footer,
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod probe_instance {
+        use crate::error::UdaError;
+        use crate::locale::Locale;
+        use crate::probe::probe_instance;
+        use crate::tools::build_client;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        const SIGN_IN_BODY: &str = r#"<html lang="en"><body><form action="/en/users/sign_in" method="post"><input name="authenticity_token" value="token"><input type="email" name="user[email]"><input type="password" name="user[password]"></form><footer>Powered by Unicycling Registration Software v2.3.0</footer></body></html>"#;
+
+        async fn setup_sign_in_page(mock_server: &MockServer, response: ResponseTemplate) {
+            Mock::given(method("GET"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(response)
+                .mount(mock_server)
+                .await;
+        }
+
+        #[tokio::test]
+        async fn success() {
+            let mock_server = MockServer::start().await;
+            setup_sign_in_page(
+                &mock_server,
+                ResponseTemplate::new(200).set_body_string(SIGN_IN_BODY),
+            )
+            .await;
+
+            let client = build_client().unwrap();
+            let info = probe_instance(&client, &mock_server.uri()).await.unwrap();
+
+            assert_eq!(Some(Locale::En), *info.locale());
+            assert!(info.has_login_form());
+            assert_eq!(
+                Some("Powered by Unicycling Registration Software v2.3.0"),
+                info.footer().as_deref()
+            );
+        }
+
+        #[tokio::test]
+        async fn fail_when_server_error() {
+            let mock_server = MockServer::start().await;
+            setup_sign_in_page(&mock_server, ResponseTemplate::new(500)).await;
+
+            let client = build_client().unwrap();
+            let error = probe_instance(&client, &mock_server.uri())
+                .await
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::ConnectionFailed(None)));
+        }
+
+        #[tokio::test]
+        async fn fail_when_not_uda() {
+            let mock_server = MockServer::start().await;
+            setup_sign_in_page(
+                &mock_server,
+                ResponseTemplate::new(200)
+                    .set_body_string("<html><body><h1>It works!</h1></body></html>"),
+            )
+            .await;
+
+            let client = build_client().unwrap();
+            let error = probe_instance(&client, &mock_server.uri())
+                .await
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::ConnectionFailed(None)));
+        }
+    }
+}
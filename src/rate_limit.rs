@@ -0,0 +1,137 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::{sleep_until, Instant};
+
+/// Token bucket limiting requests sent to UDA to `requests_per_second` each second on average,
+/// up to `requests_per_second` at once after a quiet period.
+/// Clones share the same bucket, so that all the sessions of a batch count against it.
+///
+/// The bucket is refilled lazily, from the time elapsed when a token is taken,
+/// so that it doesn't depend on any task, nor on the runtime it was first used on.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    interval: Duration,
+    /// How far ahead of the time its token is due a request may be sent, i.e. the burst allowed.
+    burst: Duration,
+    /// Time the bucket is refilled up to, once the tokens taken so far are paid for.
+    /// It lags behind the current time while the bucket is full.
+    refilled_until: Arc<Mutex<Option<Instant>>>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: u32) -> Self {
+        let requests_per_second = requests_per_second.max(1);
+        let interval = Duration::from_secs(1) / requests_per_second;
+        Self {
+            interval,
+            burst: interval * (requests_per_second - 1),
+            refilled_until: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Wait until a request may be sent, taking its token.
+    /// Tokens are handed out in the order they are asked for; a wait that is dropped still uses its token.
+    pub async fn acquire(&self) {
+        let send_at = {
+            let mut refilled_until = self
+                .refilled_until
+                .lock()
+                .unwrap_or_else(|error| error.into_inner());
+            let now = Instant::now();
+            let due_at = refilled_until.map_or(now, |refilled_until| refilled_until.max(now));
+            *refilled_until = Some(due_at + self.interval);
+            due_at
+                .checked_sub(self.burst)
+                .map_or(now, |send_at| send_at.max(now))
+        };
+        sleep_until(send_at).await;
+    }
+}
+
+/// Wait for the rate limiter, if any, before sending a request.
+pub(crate) async fn throttle(rate_limiter: Option<&RateLimiter>) {
+    if let Some(rate_limiter) = rate_limiter {
+        rate_limiter.acquire().await;
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod acquire {
+        use crate::rate_limit::RateLimiter;
+        use std::time::Duration;
+        use tokio::runtime::Builder;
+        use tokio::time::{timeout, Instant};
+
+        #[tokio::test(start_paused = true)]
+        async fn let_burst_through() {
+            let rate_limiter = RateLimiter::new(20);
+
+            let start = Instant::now();
+            for _ in 0..20 {
+                rate_limiter.acquire().await;
+            }
+
+            assert_eq!(Duration::ZERO, start.elapsed());
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn space_out_requests() {
+            let rate_limiter = RateLimiter::new(20);
+
+            let start = Instant::now();
+            for _ in 0..25 {
+                rate_limiter.acquire().await;
+            }
+
+            // The first 20 requests empty the bucket, the next 5 wait for a token each
+            assert_eq!(Duration::from_millis(250), start.elapsed());
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn refill_after_quiet_period() {
+            let rate_limiter = RateLimiter::new(20);
+            for _ in 0..20 {
+                rate_limiter.acquire().await;
+            }
+
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            let start = Instant::now();
+            for _ in 0..20 {
+                rate_limiter.acquire().await;
+            }
+
+            assert_eq!(Duration::ZERO, start.elapsed());
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn share_limit_between_clones() {
+            let rate_limiter = RateLimiter::new(20);
+            let cloned_rate_limiter = rate_limiter.clone();
+
+            let start = Instant::now();
+            for _ in 0..13 {
+                rate_limiter.acquire().await;
+                cloned_rate_limiter.acquire().await;
+            }
+
+            assert_eq!(Duration::from_millis(300), start.elapsed());
+        }
+
+        #[test]
+        fn keep_refilling_once_first_runtime_dropped() {
+            let rate_limiter = RateLimiter::new(20);
+            let build_runtime = || Builder::new_current_thread().enable_time().build().unwrap();
+
+            build_runtime().block_on(async {
+                for _ in 0..20 {
+                    rate_limiter.acquire().await;
+                }
+            });
+
+            let acquired = build_runtime()
+                .block_on(async { timeout(Duration::from_secs(1), rate_limiter.acquire()).await });
+            assert!(acquired.is_ok());
+        }
+    }
+}
@@ -1,182 +1,2338 @@
 use crate::error::UdaError::{
-    LackOfPermissions, MalformedXlsFile, OrganizationMembershipsAccessFailed,
+    CantReadPageContent, FeatureNotAvailable, LackOfPermissions, MemberSerializationFailed,
+    OrganizationMembershipsAccessFailed, RateLimited, SessionExpired, UnexpectedContentType,
+    UnexpectedStatus,
 };
-use crate::error::{log_error_and_return, log_message_and_return};
-use crate::imported_uda_member::ImportedUdaMember;
+use crate::error::{log_error_and_return, log_message_and_return, UdaError};
+use crate::ids::MemberId;
+use crate::imported_uda_member::{pick_membership_number, ImportedUdaMember};
+use crate::locale::Locale;
+use crate::membership_number::MembershipNumberFormat;
+use crate::organizations::retrieve_current_organization;
+use crate::rate_limit::RateLimiter;
+use crate::retry::{parse_retry_after, send_throttled_with_retry, RetryPolicy};
+pub use crate::spreadsheet::SkippedRow;
+use crate::spreadsheet::{
+    deserialize_csv, deserialize_sheet, deserialize_sheet_lazily, HeaderAliases,
+};
+use crate::tools::{authorize, build_url, check_cancellation, BasicAuth};
+use crate::uda_member_builder::UdaMemberBuilder;
 use crate::Result;
-use calamine::{
-    open_workbook_from_rs, Data, RangeDeserializer, RangeDeserializerBuilder, Reader, Xls,
+use bytes::Bytes;
+use derive_getters::Getters;
+use futures_util::{stream, Stream, TryStreamExt};
+use log::{debug, error, warn};
+use reqwest::header::{
+    HeaderMap, HeaderName, CONTENT_DISPOSITION, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE,
+    IF_NONE_MATCH, LAST_MODIFIED, RANGE,
 };
-use log::{error, warn};
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use scraper::{ElementRef, Html, Selector};
+use std::collections::{HashMap, HashSet};
 use std::io::Cursor;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 use uda_dto::uda_member::UdaMember;
 #[cfg(any(test, feature = "test"))]
 use wiremock::matchers::{method, path};
 #[cfg(any(test, feature = "test"))]
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
-/// Retrieve members from UDA's organisation membership page.
-pub async fn retrieve_members(client: &Client, base_url: &str) -> Result<Vec<UdaMember>> {
-    let url = format!("{base_url}/en/organization_memberships/export.xls");
+/// Content types UDA serves the XLS(X) export with.
+pub(crate) const SPREADSHEET_CONTENT_TYPES: [&str; 3] = [
+    "application/vnd.ms-excel",
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    "application/octet-stream",
+];
+
+/// Content types UDA serves the CSV export with.
+const CSV_CONTENT_TYPES: [&str; 4] = [
+    "text/csv",
+    "application/csv",
+    "text/plain",
+    "application/octet-stream",
+];
+
+/// IDs over this one relate to non-competitors: they don't require a membership.
+pub const DEFAULT_MAX_COMPETITOR_ID: u16 = 1999;
+
+/// Which members to keep among the ones exported by UDA.
+#[derive(Debug, Clone, PartialEq, Getters)]
+pub struct MemberFilter {
+    /// Members with a greater id are considered non-competitors and are dropped.
+    /// When `None`, every member is considered a competitor.
+    max_competitor_id: Option<u16>,
+    /// Whether non-competitors, e.g. volunteers or officials, are kept rather than dropped.
+    include_non_competitors: bool,
+    /// Whether members whose membership isn't confirmed are dropped.
+    only_valid: bool,
+}
+
+impl MemberFilter {
+    pub fn new(max_competitor_id: Option<u16>) -> Self {
+        Self {
+            max_competitor_id,
+            include_non_competitors: false,
+            only_valid: false,
+        }
+    }
+
+    /// Keep non-competitors, telling them apart with [`MemberFilter::is_competitor`].
+    pub fn with_include_non_competitors(mut self, include_non_competitors: bool) -> Self {
+        self.include_non_competitors = include_non_competitors;
+        self
+    }
+
+    /// Whether the member is a competitor, i.e. its id doesn't exceed the max competitor id.
+    pub fn is_competitor(&self, member: &UdaMember) -> bool {
+        self.is_competitor_id(*member.id())
+    }
+
+    fn is_competitor_id(&self, id: u16) -> bool {
+        self.max_competitor_id
+            .is_none_or(|max_competitor_id| id <= max_competitor_id)
+    }
+
+    /// Keep only members whose membership is confirmed.
+    pub fn with_only_valid(mut self, only_valid: bool) -> Self {
+        self.only_valid = only_valid;
+        self
+    }
+
+    fn accepts(&self, member: &ImportedUdaMember) -> bool {
+        (self.include_non_competitors || self.is_competitor_id(*member.id()))
+            && (!self.only_valid || *member.confirmed())
+    }
+}
+
+impl Default for MemberFilter {
+    fn default() -> Self {
+        Self::new(Some(DEFAULT_MAX_COMPETITOR_ID))
+    }
+}
+
+/// Whether the membership of the member is in good standing, i.e. confirmed on UDA.
+pub fn is_valid(member: &UdaMember) -> bool {
+    *member.confirmed()
+}
+
+/// Whether the member is a competitor, according to the default max competitor id.
+pub fn is_competitor(member: &UdaMember) -> bool {
+    MemberFilter::default().is_competitor(member)
+}
+
+/// Keep only members whose membership is in good standing.
+pub fn filter_valid(members: Vec<UdaMember>) -> Vec<UdaMember> {
+    members.into_iter().filter(is_valid).collect()
+}
+
+/// Drop the members whose id has already been seen, as a malformed export may repeat rows.
+/// The first occurrence is kept, being the one UDA listed first.
+pub fn dedup_by_id(members: Vec<UdaMember>) -> Vec<UdaMember> {
+    let mut seen_ids = HashSet::new();
+    members
+        .into_iter()
+        .filter(|member| {
+            let is_new = seen_ids.insert(*member.id());
+            if !is_new {
+                warn!("Ignoring duplicated member [id: {}]", member.id());
+            }
+            is_new
+        })
+        .collect()
+}
+
+/// Members retrieved from UDA, along with the rows that couldn't be read.
+#[derive(Debug, PartialEq, Getters)]
+pub struct MembersReport {
+    members: Vec<UdaMember>,
+    skipped: Vec<SkippedRow>,
+}
+
+/// Members retrieved from UDA, along with the name of the organization they belong to,
+/// e.g. to label them when gathering the members of several organizations.
+#[derive(Debug, PartialEq, Getters)]
+pub struct MemberExport {
+    /// `None` when UDA gives no clue about it.
+    organization: Option<String>,
+    members: Vec<UdaMember>,
+}
+
+/// Steps of a members retrieval, reported as they happen, e.g. to show a progress bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Progress {
+    DownloadStarted,
+    /// Size of the downloaded export, in bytes.
+    Downloaded(usize),
+    /// Number of rows read so far, rows that can't be read excluded.
+    ParsedRow(usize),
+    /// Number of members retrieved.
+    Done(usize),
+}
+
+/// Validators of the last members export downloaded, for the next download to be skipped
+/// when the export hasn't changed since, e.g. for incremental syncs.
+/// Restore it with [`ExportCache::new`] to keep it between runs.
+#[derive(Debug, Clone, Default, PartialEq, Getters)]
+pub struct ExportCache {
+    /// `ETag` header of the last export, sent back in the `If-None-Match` one.
+    etag: Option<String>,
+    /// `Last-Modified` header of the last export, sent back in the `If-Modified-Since` one.
+    last_modified: Option<String>,
+}
+
+impl ExportCache {
+    pub fn new(etag: Option<String>, last_modified: Option<String>) -> Self {
+        Self {
+            etag,
+            last_modified,
+        }
+    }
+
+    /// Replace the validators with the ones of a freshly downloaded export.
+    fn update(&mut self, headers: &HeaderMap) {
+        let header_value = |name: HeaderName| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned)
+        };
+        self.etag = header_value(ETAG);
+        self.last_modified = header_value(LAST_MODIFIED);
+    }
+}
+
+/// Imported members, along with the rows that couldn't be read.
+struct ImportedMembers {
+    members: Vec<ImportedUdaMember>,
+    skipped: Vec<SkippedRow>,
+}
+
+/// Retrieve members from UDA's organisation membership page.
+/// Cancelling the retrieval, e.g. with a timeout, is safe: the connection of an interrupted download
+/// is closed rather than given back to the pool, so the client can be used again right away.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip(client), fields(endpoint = "organization_memberships/export.xls"))
+)]
+pub async fn retrieve_members(client: &Client, base_url: &str) -> Result<Vec<UdaMember>> {
+    let export = download_members_export(client, base_url).await?;
+    parse_members_export(&export)
+}
+
+/// Retrieve members from UDA's organisation membership page, like [`retrieve_members`],
+/// reporting the progress of the download and of the parsing to the given callback.
+/// Rows are reported one at a time, as they are deserialized from the decoded export.
+pub async fn retrieve_members_with_progress(
+    client: &Client,
+    base_url: &str,
+    progress: impl Fn(Progress),
+) -> Result<Vec<UdaMember>> {
+    progress(Progress::DownloadStarted);
+    let export = fetch_members_export(
+        client,
+        base_url,
+        Locale::default(),
+        &RetryPolicy::default(),
+        None,
+        None,
+    )
+    .await?;
+    progress(Progress::Downloaded(export.len()));
+
+    let filter = MemberFilter::default();
+    let members = deserialize_sheet_lazily::<ImportedUdaMember, _>(Cursor::new(export), None)?
+        .enumerate()
+        .inspect(|(index, _)| progress(Progress::ParsedRow(index + 1)))
+        .map(|(_, member)| member)
+        .filter(|member| filter.accepts(member))
+        .map(UdaMember::from)
+        .collect();
+    let members = dedup_by_id(members);
+    progress(Progress::Done(members.len()));
+
+    Ok(members)
+}
+
+/// Retrieve members from UDA's organisation membership page, unless the export hasn't changed
+/// since the one the cache holds the validators of, in which case `None` is returned.
+/// The cache is updated with the validators of the newly downloaded export.
+pub async fn retrieve_members_conditional(
+    client: &Client,
+    base_url: &str,
+    cache: &mut ExportCache,
+) -> Result<Option<Vec<UdaMember>>> {
+    let url = members_export_url(base_url, Locale::default())?;
+    let export = fetch_export_if_modified(
+        client,
+        &url,
+        &SPREADSHEET_CONTENT_TYPES,
+        &RetryPolicy::default(),
+        None,
+        None,
+        cache,
+    )
+    .await?;
+
+    export
+        .map(|(export, _filename)| parse_members_export(&export))
+        .transpose()
+}
+
+/// Download the raw members export from UDA's organisation membership page,
+/// e.g. to cache it and parse it later with [`parse_members_export`].
+pub async fn download_members_export(client: &Client, base_url: &str) -> Result<Vec<u8>> {
+    fetch_members_export(
+        client,
+        base_url,
+        Locale::default(),
+        &RetryPolicy::default(),
+        None,
+        None,
+    )
+    .await
+    .map(|export| export.to_vec())
+}
+
+/// Check whether the logged-in user may download the members export, e.g. to tell them upfront,
+/// without downloading it: only its headers are requested, or its first byte when UDA rejects HEAD.
+/// Returns `false` when UDA denies access to the export.
+pub async fn has_membership_access(client: &Client, base_url: &str) -> Result<bool> {
+    let url = members_export_url(base_url, Locale::default())?;
+    let mut response = send_access_check(client.head(&url)).await?;
+    if matches!(
+        response.status(),
+        StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+    ) {
+        debug!(
+            "HEAD rejected, requesting the first byte of the export instead [status: {}]",
+            response.status()
+        );
+        response = send_access_check(client.get(&url).header(RANGE, "bytes=0-0")).await?;
+    }
+
+    if response.url().path().ends_with("/users/sign_in") {
+        error!("Redirected to sign in page instead of export. Session expired?");
+        return Err(SessionExpired);
+    }
+    let status = response.status();
+    match status {
+        status if status.is_success() => Ok(true),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            debug!("Members export is inaccessible [status: {status}]");
+            Ok(false)
+        }
+        _ => {
+            error!("Can't check access to members export [status: {status}]");
+            Err(UnexpectedStatus(status))
+        }
+    }
+}
+
+async fn send_access_check(request: RequestBuilder) -> Result<Response> {
+    request.send().await.map_err(log_message_and_return(
+        "Can't reach members export",
+        UdaError::connection,
+    ))
+}
+
+/// Read members from a raw export, as downloaded by [`download_members_export`].
+pub fn parse_members_export(export: &[u8]) -> Result<Vec<UdaMember>> {
+    retrieve_imported_members_from_xls(Cursor::new(export), &MemberFilter::default(), None)
+        .map(|imported_members| into_members_report(imported_members).members)
+}
+
+/// Read members from a raw export with every field UDA exports, e.g. their address,
+/// rather than only the ones [`UdaMember`] holds.
+pub fn parse_imported_members(export: &[u8]) -> Result<Vec<ImportedUdaMember>> {
+    retrieve_imported_members_from_xls(Cursor::new(export), &MemberFilter::default(), None)
+        .map(|imported_members| imported_members.members)
+}
+
+/// Retrieve members from the CSV export of UDA's organisation membership page,
+/// lighter than the XLS one but only available on some versions of UDA.
+pub async fn retrieve_members_csv(client: &Client, base_url: &str) -> Result<Vec<UdaMember>> {
+    let url = build_url(
+        base_url,
+        &format!(
+            "{}/organization_memberships/export.csv",
+            Locale::default().code()
+        ),
+    )?;
+    let export = fetch_export(
+        client,
+        &url,
+        &CSV_CONTENT_TYPES,
+        &RetryPolicy::default(),
+        None,
+        None,
+    )
+    .await?;
+    parse_members_csv(&export)
+}
+
+/// Read members from a raw CSV export, whose columns are the same as the XLS export's.
+pub fn parse_members_csv(export: &[u8]) -> Result<Vec<UdaMember>> {
+    let imported_members = deserialize_csv::<ImportedUdaMember>(export)?;
+    let filter = MemberFilter::default();
+    let members = imported_members
+        .rows
+        .into_iter()
+        .filter(|member| filter.accepts(member))
+        .collect();
+
+    Ok(into_members_report(ImportedMembers {
+        members,
+        skipped: imported_members.skipped,
+    })
+    .members)
+}
+
+/// Retrieve members from UDA's organisation membership page, served in the given locale.
+pub async fn retrieve_members_with_locale(
+    client: &Client,
+    base_url: &str,
+    locale: Locale,
+) -> Result<Vec<UdaMember>> {
+    fetch_members(
+        client,
+        base_url,
+        locale,
+        &RetryPolicy::default(),
+        None,
+        None,
+        &MemberFilter::default(),
+    )
+    .await
+}
+
+/// Retrieve members from UDA's organisation membership page,
+/// retrying the download when it fails transiently according to the given policy.
+pub async fn retrieve_members_with_retry(
+    client: &Client,
+    base_url: &str,
+    retry_policy: &RetryPolicy,
+) -> Result<Vec<UdaMember>> {
+    fetch_members(
+        client,
+        base_url,
+        Locale::default(),
+        retry_policy,
+        None,
+        None,
+        &MemberFilter::default(),
+    )
+    .await
+}
+
+/// Retrieve members from UDA's organisation membership page, keeping only the ones accepted by the filter.
+pub async fn retrieve_members_filtered(
+    client: &Client,
+    base_url: &str,
+    filter: &MemberFilter,
+) -> Result<Vec<UdaMember>> {
+    fetch_members(
+        client,
+        base_url,
+        Locale::default(),
+        &RetryPolicy::default(),
+        None,
+        None,
+        filter,
+    )
+    .await
+}
+
+/// Retrieve members from UDA's organisation membership page,
+/// reporting the rows that couldn't be read instead of silently ignoring them.
+pub async fn retrieve_members_with_report(
+    client: &Client,
+    base_url: &str,
+) -> Result<MembersReport> {
+    fetch_members_with_report(
+        client,
+        base_url,
+        Locale::default(),
+        &RetryPolicy::default(),
+        None,
+        None,
+        &MemberFilter::default(),
+        None,
+    )
+    .await
+}
+
+/// Retrieve members from UDA's organisation membership page,
+/// reading them from the worksheet named `sheet_name` rather than from the first one.
+pub async fn retrieve_members_from_sheet(
+    client: &Client,
+    base_url: &str,
+    sheet_name: &str,
+) -> Result<Vec<UdaMember>> {
+    fetch_members_with_report(
+        client,
+        base_url,
+        Locale::default(),
+        &RetryPolicy::default(),
+        None,
+        None,
+        &MemberFilter::default(),
+        Some(sheet_name),
+    )
+    .await
+    .map(|report| report.members)
+}
+
+/// Retrieve members from UDA's organisation membership page, yielding them one at a time.
+/// The whole export is still downloaded and decoded before the first member is yielded,
+/// as the spreadsheet can't be read partially: only the members themselves are built lazily,
+/// so that callers processing and dropping them don't hold them all at once.
+pub fn retrieve_members_stream<'a>(
+    client: &'a Client,
+    base_url: &'a str,
+) -> impl Stream<Item = Result<UdaMember>> + 'a {
+    members_stream(client, base_url, None)
+}
+
+/// Retrieve members from UDA's organisation membership page, yielding them one at a time
+/// like [`retrieve_members_stream`], until the token is cancelled, e.g. on shutdown.
+/// The token is checked before downloading the export and before each member,
+/// the stream then ending with a [`UdaError::Cancelled`] error.
+pub fn retrieve_members_stream_with_cancellation<'a>(
+    client: &'a Client,
+    base_url: &'a str,
+    cancellation_token: &'a CancellationToken,
+) -> impl Stream<Item = Result<UdaMember>> + 'a {
+    members_stream(client, base_url, Some(cancellation_token))
+}
+
+fn members_stream<'a>(
+    client: &'a Client,
+    base_url: &'a str,
+    cancellation_token: Option<&'a CancellationToken>,
+) -> impl Stream<Item = Result<UdaMember>> + 'a {
+    stream::once(async move {
+        check_cancellation(cancellation_token)?;
+        let body = fetch_members_export(
+            client,
+            base_url,
+            Locale::default(),
+            &RetryPolicy::default(),
+            None,
+            None,
+        )
+        .await?;
+        let filter = MemberFilter::default();
+        let mut cancelled = false;
+        let members = deserialize_sheet_lazily::<ImportedUdaMember, _>(Cursor::new(body), None)?
+            .filter(move |member| filter.accepts(member))
+            .map_while(move |imported_member| {
+                // Cancelling is reported once, then the stream ends
+                if cancelled {
+                    return None;
+                }
+                if let Err(error) = check_cancellation(cancellation_token) {
+                    cancelled = true;
+                    return Some(Err(error));
+                }
+                Some(Ok(UdaMember::from(imported_member)))
+            });
+        Ok::<_, UdaError>(stream::iter(members))
+    })
+    .try_flatten()
+}
+
+/// Retrieve a single member, e.g. to check an update after a full import, from their registrant page.
+/// When the page doesn't show the member details, e.g. on an instance laid out differently,
+/// the member is looked for in the members export instead.
+/// Returns `None` when UDA doesn't know the member.
+pub async fn retrieve_member_by_id(
+    client: &Client,
+    base_url: &str,
+    id: MemberId,
+) -> Result<Option<UdaMember>> {
+    fetch_member_by_id(
+        client,
+        base_url,
+        Locale::default(),
+        id,
+        &RetryPolicy::default(),
+        None,
+        None,
+    )
+    .await
+}
+
+pub(crate) async fn fetch_member_by_id(
+    client: &Client,
+    base_url: &str,
+    locale: Locale,
+    id: MemberId,
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+) -> Result<Option<UdaMember>> {
+    let Some(page) = fetch_registrant_page(
+        client,
+        base_url,
+        locale,
+        id,
+        retry_policy,
+        rate_limiter,
+        basic_auth,
+    )
+    .await?
+    else {
+        return Ok(None);
+    };
+    if let Some(member) = read_member_from_registrant_page(id.0, &page)? {
+        return Ok(Some(member));
+    }
+
+    debug!(
+        "Registrant page doesn't show member details. Looking for them in the export. [id: {id}]"
+    );
+    let export = fetch_members_export(
+        client,
+        base_url,
+        locale,
+        retry_policy,
+        rate_limiter,
+        basic_auth,
+    )
+    .await?;
+    // Non-competitors are looked for too, the id being explicitly asked for
+    let imported_members =
+        retrieve_imported_members_from_xls(Cursor::new(export), &MemberFilter::new(None), None)?;
+    let member = imported_members
+        .members
+        .into_iter()
+        .find(|member| *member.id() == id.0)
+        .map(UdaMember::from);
+    if member.is_none() {
+        warn!("Registrant exists but isn't in the members export [id: {id}]");
+    }
+    Ok(member)
+}
+
+/// Content of the registrant page of the member, or `None` when UDA doesn't know the member.
+async fn fetch_registrant_page(
+    client: &Client,
+    base_url: &str,
+    locale: Locale,
+    id: MemberId,
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+) -> Result<Option<String>> {
+    let url = registrant_page_url(base_url, locale, id)?;
+    let response = send_throttled_with_retry(retry_policy, rate_limiter, || {
+        authorize(client.get(&url), &url, basic_auth)
+    })
+    .await
+    .map_err(log_message_and_return(
+        "Can't reach registrant page",
+        UdaError::connection,
+    ))?;
+
+    let status = response.status();
+    match status {
+        status if status.is_success() => {}
+        StatusCode::NOT_FOUND => {
+            debug!("Registrant not found [id: {id}]");
+            return Ok(None);
+        }
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            error!(
+                "Can't access registrant page. Lack of permissions? [id: {id}, status: {status}]"
+            );
+            return Err(LackOfPermissions);
+        }
+        _ => {
+            error!("Can't reach registrant page [id: {id}, status: {status}]");
+            return Err(UnexpectedStatus(status));
+        }
+    }
+
+    let body = response.text().await.map_err(log_message_and_return(
+        "Can't read registrant page",
+        |error| CantReadPageContent(Some(Arc::new(error))),
+    ))?;
+    if is_sign_in_page(body.as_bytes()) {
+        error!("Redirected to sign in page instead of registrant page. Session expired?");
+        return Err(SessionExpired);
+    }
+    Ok(Some(body))
+}
+
+pub(crate) fn registrant_page_url(base_url: &str, locale: Locale, id: MemberId) -> Result<String> {
+    build_url(base_url, &format!("{}/registrants/{id}", locale.code()))
+}
+
+/// Member shown on their registrant page, whose details are listed as label and value pairs,
+/// either in a table or a description list, under the headers of the members export.
+/// Returns `None` when the page doesn't show the name, the email and the confirmation of the member.
+fn read_member_from_registrant_page(id: u16, body: &str) -> Result<Option<UdaMember>> {
+    let row_selector = Selector::parse("tr").map_err(UdaError::from)?;
+    let label_selector = Selector::parse("th").map_err(UdaError::from)?;
+    let value_selector = Selector::parse("td").map_err(UdaError::from)?;
+    let term_selector = Selector::parse("dt").map_err(UdaError::from)?;
+    let document = Html::parse_document(body);
+
+    let rows = document.select(&row_selector).filter_map(|row| {
+        let label = row.select(&label_selector).next()?;
+        let value = row.select(&value_selector).next()?;
+        Some((label, value))
+    });
+    let terms = document.select(&term_selector).filter_map(|term| {
+        let definition = term
+            .next_siblings()
+            .filter_map(ElementRef::wrap)
+            .next()
+            .filter(|sibling| sibling.value().name() == "dd")?;
+        Some((term, definition))
+    });
+    let details: HashMap<&str, String> = rows
+        .chain(terms)
+        .filter_map(|(label, value)| {
+            let label = element_text(label);
+            let field = ImportedUdaMember::canonical_header(label.trim_end_matches(':'))?;
+            Some((field, element_text(value)))
+        })
+        .collect();
+
+    let detail = |field: &str| {
+        details
+            .get(field)
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+    };
+    let (Some(first_name), Some(last_name), Some(email), Some(confirmed)) = (
+        detail("First Name"),
+        detail("Last Name"),
+        detail("Email"),
+        detail("Confirmed already a member"),
+    ) else {
+        return Ok(None);
+    };
+
+    let mut builder = UdaMemberBuilder::new()
+        .id(id)
+        .first_name(first_name)
+        .last_name(last_name)
+        .email(email)
+        .confirmed(matches!(
+            confirmed.to_lowercase().as_str(),
+            "yes" | "true" | "✓" | "1"
+        ));
+    if let Some(club) = detail("Club") {
+        builder = builder.club(club);
+    }
+    if let Some(membership_number) = pick_membership_number(
+        id,
+        detail("Manual Organization Membership#"),
+        detail("System Organization Membership#"),
+        &MembershipNumberFormat::default(),
+    ) {
+        builder = builder.membership_number(membership_number);
+    }
+    builder.build().map(Some)
+}
+
+/// Text of the element, with its whitespaces collapsed.
+fn element_text(element: ElementRef) -> String {
+    element
+        .text()
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Retrieve members from UDA's organisation membership page, along with the name of their organization.
+/// The name is read from the name UDA suggests for the export, e.g. `MyClub_members.xls`,
+/// or else from the dashboard.
+pub async fn retrieve_members_with_context(
+    client: &Client,
+    base_url: &str,
+) -> Result<MemberExport> {
+    let url = members_export_url(base_url, Locale::default())?;
+    let (export, filename) = fetch_export_with_filename(
+        client,
+        &url,
+        &SPREADSHEET_CONTENT_TYPES,
+        &RetryPolicy::default(),
+        None,
+        None,
+    )
+    .await?;
+    let members = parse_members_export(&export)?;
+
+    let organization = match filename.as_deref().and_then(organization_from_filename) {
+        Some(organization) => Some(organization),
+        None => retrieve_current_organization(client, base_url)
+            .await
+            .inspect_err(|error| warn!("Can't find organization of the members [error: {error}]"))
+            .ok()
+            .map(|organization| organization.name().to_owned()),
+    };
+
+    Ok(MemberExport {
+        organization,
+        members,
+    })
+}
+
+/// Name of the organization an export is named after, e.g. `MyClub` for `MyClub_members.xls`.
+fn organization_from_filename(filename: &str) -> Option<String> {
+    let stem = filename
+        .rsplit_once('.')
+        .map_or(filename, |(stem, _extension)| stem);
+    let organization = stem.strip_suffix("_members")?.trim();
+    (!organization.is_empty()).then(|| organization.to_owned())
+}
+
+/// Name suggested for the file in a `Content-Disposition` header,
+/// e.g. `MyClub_members.xls` for `attachment; filename="MyClub_members.xls"`.
+fn filename_from_content_disposition(content_disposition: &str) -> Option<String> {
+    content_disposition
+        .split(';')
+        .filter_map(|parameter| parameter.trim().split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("filename"))
+        .map(|(_, value)| value.trim().trim_matches('"').to_owned())
+        .filter(|filename| !filename.is_empty())
+}
+
+/// Retrieve members from UDA's organisation membership page, serialized as a JSON array.
+/// Missing optional fields are serialized as `null`, so that the schema stays stable.
+pub async fn retrieve_members_as_json(client: &Client, base_url: &str) -> Result<String> {
+    let members = retrieve_members(client, base_url).await?;
+    serde_json::to_string(&members).map_err(log_error_and_return(|error| {
+        MemberSerializationFailed(Arc::new(error))
+    }))
+}
+
+pub(crate) async fn fetch_members(
+    client: &Client,
+    base_url: &str,
+    locale: Locale,
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+    filter: &MemberFilter,
+) -> Result<Vec<UdaMember>> {
+    fetch_members_with_report(
+        client,
+        base_url,
+        locale,
+        retry_policy,
+        rate_limiter,
+        basic_auth,
+        filter,
+        None,
+    )
+    .await
+    .map(|report| report.members)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn fetch_members_with_report(
+    client: &Client,
+    base_url: &str,
+    locale: Locale,
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+    filter: &MemberFilter,
+    sheet_name: Option<&str>,
+) -> Result<MembersReport> {
+    let body = fetch_members_export(
+        client,
+        base_url,
+        locale,
+        retry_policy,
+        rate_limiter,
+        basic_auth,
+    )
+    .await?;
+    retrieve_imported_members_from_xls(Cursor::new(body), filter, sheet_name)
+        .map(into_members_report)
+}
+
+fn into_members_report(imported_members: ImportedMembers) -> MembersReport {
+    MembersReport {
+        members: dedup_by_id(
+            imported_members
+                .members
+                .into_iter()
+                .map(UdaMember::from)
+                .collect(),
+        ),
+        skipped: imported_members.skipped,
+    }
+}
+
+async fn fetch_members_export(
+    client: &Client,
+    base_url: &str,
+    locale: Locale,
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+) -> Result<Bytes> {
+    let url = members_export_url(base_url, locale)?;
+    fetch_export(
+        client,
+        &url,
+        &SPREADSHEET_CONTENT_TYPES,
+        retry_policy,
+        rate_limiter,
+        basic_auth,
+    )
+    .await
+}
+
+pub(crate) fn members_export_url(base_url: &str, locale: Locale) -> Result<String> {
+    build_url(
+        base_url,
+        &format!("{}/organization_memberships/export.xls", locale.code()),
+    )
+}
+
+async fn fetch_export(
+    client: &Client,
+    url: &str,
+    content_types: &[&str],
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+) -> Result<Bytes> {
+    fetch_export_with_filename(
+        client,
+        url,
+        content_types,
+        retry_policy,
+        rate_limiter,
+        basic_auth,
+    )
+    .await
+    .map(|(export, _filename)| export)
+}
+
+/// Download an export, along with the name UDA suggests for the file, if any.
+/// The export is rejected when served with another content type than the given ones,
+/// e.g. when a misconfigured instance answers with an HTML error page.
+/// Without content type, the export is accepted, its format being told by its content.
+async fn fetch_export_with_filename(
+    client: &Client,
+    url: &str,
+    content_types: &[&str],
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+) -> Result<(Bytes, Option<String>)> {
+    fetch_export_if_modified(
+        client,
+        url,
+        content_types,
+        retry_policy,
+        rate_limiter,
+        basic_auth,
+        &mut ExportCache::default(),
+    )
+    .await?
+    .ok_or_else(|| {
+        // Without validators, the request isn't conditional
+        error!("Export not modified although not asked for conditionally [url: {url}]");
+        UnexpectedStatus(StatusCode::NOT_MODIFIED)
+    })
+}
+
+/// Like [`fetch_export_with_filename`], sending the validators of the cache if any,
+/// `None` being returned when UDA answers the export hasn't been modified since.
+async fn fetch_export_if_modified(
+    client: &Client,
+    url: &str,
+    content_types: &[&str],
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+    cache: &mut ExportCache,
+) -> Result<Option<(Bytes, Option<String>)>> {
+    let response = send_throttled_with_retry(retry_policy, rate_limiter, || {
+        let mut request = authorize(client.get(url), url, basic_auth);
+        if let Some(etag) = &cache.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &cache.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+        request
+    })
+    .await
+    .map_err(log_error_and_return(|error| {
+        OrganizationMembershipsAccessFailed(Arc::new(error))
+    }))?;
+
+    let status = response.status();
+    #[cfg(feature = "tracing")]
+    tracing::debug!(%status, "Members export answered");
+    if status == StatusCode::NOT_MODIFIED {
+        debug!("Export not modified since last download [url: {url}]");
+        Ok(None)
+    } else if status.is_success() {
+        let headers = response.headers().clone();
+        let body = response.bytes().await.map_err(log_message_and_return(
+            "Can't read organization_memberships content",
+            |error| OrganizationMembershipsAccessFailed(Arc::new(error)),
+        ))?;
+        check_export_content(url, &headers, &body, content_types)?;
+        let filename = headers
+            .get(CONTENT_DISPOSITION)
+            .and_then(|content_disposition| content_disposition.to_str().ok())
+            .and_then(filename_from_content_disposition);
+
+        cache.update(&headers);
+        Ok(Some((body, filename)))
+    } else {
+        Err(classify_export_failure(url, status, response.headers()))
+    }
+}
+
+/// Check a successfully downloaded export is one indeed, whatever the client it was downloaded with.
+pub(crate) fn check_export_content(
+    url: &str,
+    headers: &HeaderMap,
+    body: &[u8],
+    content_types: &[&str],
+) -> Result<()> {
+    if is_sign_in_page(body) {
+        error!("Redirected to sign in page instead of export. Session expired?");
+        return Err(SessionExpired);
+    }
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .map(|content_type| String::from_utf8_lossy(content_type.as_bytes()).into_owned());
+    if let Some(content_type) =
+        content_type.filter(|content_type| !is_accepted_content_type(content_type, content_types))
+    {
+        error!(
+            "Unexpected content type instead of export [url: {url}, content_type: {content_type}]"
+        );
+        return Err(UnexpectedContentType(content_type));
+    }
+    Ok(())
+}
+
+/// Error telling why UDA didn't serve an export, according to the status it answered with.
+pub(crate) fn classify_export_failure(
+    url: &str,
+    status: StatusCode,
+    headers: &HeaderMap,
+) -> UdaError {
+    if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+        error!(
+            "Can't access organization_memberships page. Lack of permissions? [status: {status}]"
+        );
+        LackOfPermissions
+    } else if status == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = parse_retry_after(headers);
+        error!("Rate limited when exporting members [retry_after: {retry_after:?}]");
+        RateLimited { retry_after }
+    } else if status == StatusCode::NOT_FOUND {
+        error!("Can't find organization_memberships export. Is the UDA instance running an unsupported version? [url: {url}]");
+        FeatureNotAvailable("organization_memberships".to_owned())
+    } else {
+        error!("Can't reach organization_memberships page: {status:?}");
+        UnexpectedStatus(status)
+    }
+}
+
+/// Whether the media type of the content type is one of the given ones, whatever its parameters,
+/// e.g. `text/csv; charset=utf-8` is a `text/csv` one.
+fn is_accepted_content_type(content_type: &str, content_types: &[&str]) -> bool {
+    let media_type = content_type.split(';').next().unwrap_or_default().trim();
+    content_types
+        .iter()
+        .any(|accepted| media_type.eq_ignore_ascii_case(accepted))
+}
+
+/// When the session has expired, UDA redirects to the sign-in form instead of serving the file.
+pub(crate) fn is_sign_in_page(body: &[u8]) -> bool {
+    const SIGN_IN_FORM_MARKER: &[u8] = b"user[password]";
+    body.windows(SIGN_IN_FORM_MARKER.len())
+        .any(|window| window == SIGN_IN_FORM_MARKER)
+}
+
+fn retrieve_imported_members_from_xls<T: AsRef<[u8]>>(
+    cursor: Cursor<T>,
+    filter: &MemberFilter,
+    sheet_name: Option<&str>,
+) -> Result<ImportedMembers> {
+    let imported_members = deserialize_sheet::<ImportedUdaMember, _>(cursor, sheet_name)?;
+    let members = imported_members
+        .rows
+        .into_iter()
+        .filter(|member| filter.accepts(member))
+        .collect();
+
+    Ok(ImportedMembers {
+        members,
+        skipped: imported_members.skipped,
+    })
+}
+
+#[cfg(any(test, feature = "test"))]
+pub fn get_test_file_content() -> Vec<u8> {
+    std::fs::read("test/resources/uda_members.xls").unwrap()
+}
+
+#[cfg(test)]
+fn get_xlsx_test_file_content() -> Vec<u8> {
+    std::fs::read("test/resources/uda_members.xlsx").unwrap()
+}
+
+#[cfg(any(test, feature = "test"))]
+pub fn get_expected_member() -> Vec<UdaMember> {
+    vec![
+        UdaMember::new(
+            1,
+            Some("123456".to_owned()),
+            "Jon".to_owned(),
+            "Doe".to_owned(),
+            "jon.doe@email.com".to_owned(),
+            Some("Le club de test".to_owned()),
+            true,
+        ),
+        UdaMember::new(
+            2,
+            Some("654321".to_owned()),
+            "Jonette".to_owned(),
+            "Snow".to_owned(),
+            "jonette.snow@email.com".to_owned(),
+            None,
+            false,
+        ),
+        UdaMember::new(
+            1999,
+            Some("456789".to_owned()),
+            "Kris".to_owned(),
+            "Holm".to_owned(),
+            "kris.holm@email.com".to_owned(),
+            Some("KH Team".to_owned()),
+            true,
+        ),
+    ]
+}
+
+#[cfg(any(test, feature = "test"))]
+pub async fn setup_member_retrieval(mock_server: &MockServer) -> Vec<UdaMember> {
+    let body = get_test_file_content();
+
+    Mock::given(method("GET"))
+        .and(path("/en/organization_memberships/export.xls"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(body))
+        .mount(mock_server)
+        .await;
+
+    get_expected_member()
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod retrieve_members {
+        use crate::error::UdaError::{
+            FeatureNotAvailable, LackOfPermissions, RateLimited, SessionExpired,
+            UnexpectedContentType, UnexpectedStatus,
+        };
+        use crate::locale::Locale;
+        use crate::retrieve_members::{
+            get_expected_member, get_test_file_content, get_xlsx_test_file_content,
+            retrieve_members, retrieve_members_as_json, retrieve_members_filtered,
+            retrieve_members_with_locale, retrieve_members_with_report,
+            retrieve_members_with_retry, setup_member_retrieval, MemberFilter,
+        };
+        use crate::retry::RetryPolicy;
+        use crate::tools::build_client;
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+        use std::time::Duration;
+        use uda_dto::uda_member::UdaMember;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn success() {
+            let mock_server = MockServer::start().await;
+            let expected_result = setup_member_retrieval(&mock_server).await;
+            let client = build_client().unwrap();
+            let result = retrieve_members(&client, &mock_server.uri()).await.unwrap();
+            assert_eq!(expected_result, result);
+        }
+
+        #[tokio::test]
+        async fn success_as_json() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+            let expected_members = setup_member_retrieval(&mock_server).await;
+
+            let json = retrieve_members_as_json(&client, &mock_server.uri())
+                .await
+                .unwrap();
+
+            let members: Vec<UdaMember> = serde_json::from_str(&json).unwrap();
+            assert_eq!(expected_members, members);
+            let values: serde_json::Value = serde_json::from_str(&json).unwrap();
+            assert!(values[1].get("club").unwrap().is_null());
+        }
+
+        #[tokio::test]
+        async fn success_when_no_member() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+            let content = std::fs::read("test/resources/uda_members_empty.xls").unwrap();
+            Mock::given(method("GET"))
+                .and(path("/en/organization_memberships/export.xls"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(content))
+                .mount(&mock_server)
+                .await;
+
+            let members = retrieve_members(&client, &mock_server.uri()).await.unwrap();
+
+            assert_eq!(Vec::<UdaMember>::new(), members);
+        }
+
+        #[tokio::test]
+        async fn success_with_report() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+            let content = std::fs::read("test/resources/uda_members_1_invalid.xls").unwrap();
+            Mock::given(method("GET"))
+                .and(path("/en/organization_memberships/export.xls"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(content))
+                .mount(&mock_server)
+                .await;
+
+            let report = retrieve_members_with_report(&client, &mock_server.uri())
+                .await
+                .unwrap();
+
+            assert_eq!(1, report.members().len());
+            assert_eq!(1, *report.members()[0].id());
+            assert_eq!(1, report.skipped().len());
+            let skipped_row = &report.skipped()[0];
+            assert_eq!(2, *skipped_row.row_index());
+            assert!(!skipped_row.error().is_empty());
+        }
+
+        #[tokio::test]
+        async fn success_with_xlsx() {
+            let mock_server = MockServer::start().await;
+            let expected_result = setup_member_retrieval(&mock_server).await;
+            let client = build_client().unwrap();
+            Mock::given(method("GET"))
+                .and(path("/en/organization_memberships/export.xls"))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_bytes(get_xlsx_test_file_content()),
+                )
+                .with_priority(1)
+                .mount(&mock_server)
+                .await;
+
+            let result = retrieve_members(&client, &mock_server.uri()).await.unwrap();
+            assert_eq!(expected_result, result);
+        }
+
+        #[tokio::test]
+        async fn success_with_gzipped_export() {
+            let mock_server = MockServer::start().await;
+            let mut encoder = GzEncoder::new(vec![], Compression::default());
+            encoder.write_all(&get_test_file_content()).unwrap();
+            let body = encoder.finish().unwrap();
+            Mock::given(method("GET"))
+                .and(path("/en/organization_memberships/export.xls"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .insert_header("Content-Encoding", "gzip")
+                        .set_body_bytes(body),
+                )
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let result = retrieve_members(&client, &mock_server.uri()).await.unwrap();
+            assert_eq!(get_expected_member(), result);
+        }
+
+        #[tokio::test]
+        async fn success_with_locale() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/fr/organization_memberships/export.xls"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(get_test_file_content()))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let result = retrieve_members_with_locale(&client, &mock_server.uri(), Locale::Fr)
+                .await
+                .unwrap();
+            assert_eq!(3, result.len());
+        }
+
+        #[tokio::test]
+        async fn success_with_filter() {
+            let mock_server = MockServer::start().await;
+            setup_member_retrieval(&mock_server).await;
+
+            let client = build_client().unwrap();
+            let result =
+                retrieve_members_filtered(&client, &mock_server.uri(), &MemberFilter::new(Some(1)))
+                    .await
+                    .unwrap();
+
+            assert_eq!(1, result.len());
+            assert_eq!(1, *result[0].id());
+        }
+
+        #[tokio::test]
+        async fn success_with_only_valid_filter() {
+            let mock_server = MockServer::start().await;
+            setup_member_retrieval(&mock_server).await;
+
+            let client = build_client().unwrap();
+            let filter = MemberFilter::default().with_only_valid(true);
+            let result = retrieve_members_filtered(&client, &mock_server.uri(), &filter)
+                .await
+                .unwrap();
+
+            let ids = result.iter().map(|member| *member.id()).collect::<Vec<_>>();
+            assert_eq!(vec![1, 1999], ids);
+        }
+
+        #[tokio::test]
+        async fn success_after_transient_failures() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("en/organization_memberships/export.xls"))
+                .respond_with(ResponseTemplate::new(502))
+                .up_to_n_times(2)
+                .with_priority(1)
+                .mount(&mock_server)
+                .await;
+            let expected_result = setup_member_retrieval(&mock_server).await;
+
+            let client = build_client().unwrap();
+            let retry_policy = RetryPolicy::new(
+                3,
+                Duration::from_millis(10),
+                Duration::from_millis(50),
+                false,
+            );
+            let result = retrieve_members_with_retry(&client, &mock_server.uri(), &retry_policy)
+                .await
+                .unwrap();
+
+            assert_eq!(expected_result, result);
+            assert_eq!(3, mock_server.received_requests().await.unwrap().len());
+        }
+
+        #[tokio::test]
+        async fn fail_when_unreachable() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+            Mock::given(method("GET"))
+                .and(path("en/organization_memberships/export.xls"))
+                .respond_with(ResponseTemplate::new(500))
+                .mount(&mock_server)
+                .await;
+
+            let error = retrieve_members(&client, &mock_server.uri())
+                .await
+                .unwrap_err();
+            assert!(matches!(error, UnexpectedStatus(status) if status == 500));
+        }
+
+        #[tokio::test]
+        async fn fail_when_session_expired() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+            let body = r#"<html><body><form action="/en/users/sign_in" method="post"><input type="email" name="user[email]"><input type="password" name="user[password]"></form></body></html>"#;
+            Mock::given(method("GET"))
+                .and(path("en/organization_memberships/export.xls"))
+                .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/html"))
+                .mount(&mock_server)
+                .await;
+
+            let error = retrieve_members(&client, &mock_server.uri())
+                .await
+                .unwrap_err();
+            assert!(matches!(error, SessionExpired));
+        }
+
+        #[tokio::test]
+        async fn fail_when_html_error_page() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+            Mock::given(method("GET"))
+                .and(path("en/organization_memberships/export.xls"))
+                .respond_with(ResponseTemplate::new(200).set_body_raw(
+                    "<html><body>Something went wrong</body></html>",
+                    "text/html; charset=utf-8",
+                ))
+                .mount(&mock_server)
+                .await;
+
+            let error = retrieve_members(&client, &mock_server.uri())
+                .await
+                .unwrap_err();
+            assert!(
+                matches!(error, UnexpectedContentType(ref content_type) if content_type == "text/html; charset=utf-8")
+            );
+        }
+
+        #[tokio::test]
+        async fn success_with_spreadsheet_content_type() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+            Mock::given(method("GET"))
+                .and(path("en/organization_memberships/export.xls"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_raw(get_test_file_content(), "application/vnd.ms-excel"),
+                )
+                .mount(&mock_server)
+                .await;
+
+            let members = retrieve_members(&client, &mock_server.uri()).await.unwrap();
+            assert_eq!(get_expected_member(), members);
+        }
+
+        #[tokio::test]
+        async fn fail_when_session_expired_without_content_type() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+            let body = r#"<html><body><form action="/en/users/sign_in" method="post"><input type="email" name="user[email]"><input type="password" name="user[password]"></form></body></html>"#;
+            Mock::given(method("GET"))
+                .and(path("en/organization_memberships/export.xls"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(body))
+                .mount(&mock_server)
+                .await;
+
+            let error = retrieve_members(&client, &mock_server.uri())
+                .await
+                .unwrap_err();
+            assert!(matches!(error, SessionExpired));
+        }
+
+        #[tokio::test]
+        async fn fail_when_lack_of_permissions() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+            Mock::given(method("GET"))
+                .and(path("en/organization_memberships/export.xls"))
+                .respond_with(ResponseTemplate::new(401))
+                .mount(&mock_server)
+                .await;
+
+            let error = retrieve_members(&client, &mock_server.uri())
+                .await
+                .unwrap_err();
+            assert!(matches!(error, LackOfPermissions));
+        }
+
+        #[tokio::test]
+        async fn fail_when_rate_limited() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+            Mock::given(method("GET"))
+                .and(path("en/organization_memberships/export.xls"))
+                .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "120"))
+                .mount(&mock_server)
+                .await;
+
+            let error = retrieve_members(&client, &mock_server.uri())
+                .await
+                .unwrap_err();
+            assert!(matches!(
+                error,
+                RateLimited { retry_after: Some(retry_after) } if retry_after == Duration::from_secs(120)
+            ));
+        }
+
+        #[tokio::test]
+        async fn fail_when_forbidden() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+            Mock::given(method("GET"))
+                .and(path("en/organization_memberships/export.xls"))
+                .respond_with(ResponseTemplate::new(403))
+                .mount(&mock_server)
+                .await;
+
+            let error = retrieve_members(&client, &mock_server.uri())
+                .await
+                .unwrap_err();
+            assert!(matches!(error, LackOfPermissions));
+        }
+
+        #[tokio::test]
+        async fn fail_when_export_not_found() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+            Mock::given(method("GET"))
+                .and(path("en/organization_memberships/export.xls"))
+                .respond_with(ResponseTemplate::new(404))
+                .mount(&mock_server)
+                .await;
+
+            let error = retrieve_members(&client, &mock_server.uri())
+                .await
+                .unwrap_err();
+            assert!(
+                matches!(error, FeatureNotAvailable(feature) if feature == "organization_memberships")
+            );
+        }
+    }
+
+    mod filter_valid {
+        use crate::retrieve_members::{filter_valid, get_expected_member};
+
+        #[test]
+        fn exclude_invalid_members() {
+            let members = filter_valid(get_expected_member());
+
+            let names = members
+                .iter()
+                .map(|member| member.first_name().as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(vec!["Jon", "Kris"], names);
+        }
+    }
+
+    mod is_competitor {
+        use crate::retrieve_members::is_competitor;
+        use uda_dto::uda_member::UdaMember;
+
+        fn member(id: u16) -> UdaMember {
+            UdaMember::new(
+                id,
+                None,
+                "Jon".to_owned(),
+                "Doe".to_owned(),
+                "jon.doe@email.com".to_owned(),
+                None,
+                true,
+            )
+        }
+
+        #[test]
+        fn tell_apart_with_default_max_competitor_id() {
+            assert!(is_competitor(&member(1999)));
+            assert!(!is_competitor(&member(2000)));
+            assert!(!is_competitor(&member(2500)));
+        }
+    }
+
+    mod dedup_by_id {
+        use crate::retrieve_members::{dedup_by_id, get_expected_member};
+        use uda_dto::uda_member::UdaMember;
+
+        #[test]
+        fn keep_first_occurrence() {
+            let mut members = get_expected_member();
+            members.push(UdaMember::new(
+                1,
+                None,
+                "Jane".to_owned(),
+                "Doe".to_owned(),
+                "jane.doe@email.com".to_owned(),
+                None,
+                false,
+            ));
+
+            let members = dedup_by_id(members);
+
+            assert_eq!(get_expected_member(), members);
+        }
+    }
+
+    mod cancellation {
+        use crate::retrieve_members::{
+            get_test_file_content, retrieve_members, setup_member_retrieval,
+        };
+        use crate::tools::build_client;
+        use std::time::Duration;
+        use tokio::time::timeout;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn succeed_after_cancelled_retrieval() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/en/organization_memberships/export.xls"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_bytes(get_test_file_content())
+                        .set_delay(Duration::from_secs(5)),
+                )
+                .up_to_n_times(1)
+                .with_priority(1)
+                .mount(&mock_server)
+                .await;
+            let expected_members = setup_member_retrieval(&mock_server).await;
+
+            let client = build_client().unwrap();
+            let cancelled = timeout(
+                Duration::from_millis(100),
+                retrieve_members(&client, &mock_server.uri()),
+            )
+            .await;
+            assert!(cancelled.is_err());
+
+            let members = retrieve_members(&client, &mock_server.uri()).await.unwrap();
+            assert_eq!(expected_members, members);
+        }
+    }
+
+    mod retrieve_members_with_progress {
+        use crate::error::UdaError;
+        use crate::retrieve_members::{
+            get_expected_member, get_test_file_content, retrieve_members_with_progress,
+            setup_member_retrieval, Progress,
+        };
+        use crate::tools::build_client;
+        use std::sync::Mutex;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn success() {
+            let mock_server = MockServer::start().await;
+            setup_member_retrieval(&mock_server).await;
+
+            let client = build_client().unwrap();
+            let events = Mutex::new(vec![]);
+            let members = retrieve_members_with_progress(&client, &mock_server.uri(), |event| {
+                events.lock().unwrap().push(event)
+            })
+            .await
+            .unwrap();
+
+            assert_eq!(get_expected_member(), members);
+            let events = events.into_inner().unwrap();
+            assert_eq!(
+                vec![
+                    Progress::DownloadStarted,
+                    Progress::Downloaded(get_test_file_content().len()),
+                    Progress::ParsedRow(1),
+                    Progress::ParsedRow(2),
+                    Progress::ParsedRow(3),
+                    Progress::ParsedRow(4),
+                    Progress::Done(3),
+                ],
+                events
+            );
+        }
+
+        #[tokio::test]
+        async fn stop_reporting_when_download_fails() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/en/organization_memberships/export.xls"))
+                .respond_with(ResponseTemplate::new(403))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let events = Mutex::new(vec![]);
+            let error = retrieve_members_with_progress(&client, &mock_server.uri(), |event| {
+                events.lock().unwrap().push(event)
+            })
+            .await
+            .unwrap_err();
+
+            assert!(matches!(error, UdaError::LackOfPermissions));
+            assert_eq!(
+                vec![Progress::DownloadStarted],
+                events.into_inner().unwrap()
+            );
+        }
+    }
+
+    mod retrieve_members_conditional {
+        use crate::retrieve_members::{
+            get_expected_member, get_test_file_content, retrieve_members_conditional, ExportCache,
+        };
+        use crate::tools::build_client;
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        const ETAG: &str = r#""b1946ac92492d2347c6235b4d2611184""#;
+        const LAST_MODIFIED: &str = "Sat, 19 Jul 2025 10:00:00 GMT";
+
+        #[tokio::test]
+        async fn none_when_not_modified() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/en/organization_memberships/export.xls"))
+                .and(header("If-None-Match", ETAG))
+                .respond_with(ResponseTemplate::new(304))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let mut cache = ExportCache::new(Some(ETAG.to_owned()), Some(LAST_MODIFIED.to_owned()));
+            let members = retrieve_members_conditional(&client, &mock_server.uri(), &mut cache)
+                .await
+                .unwrap();
+
+            assert_eq!(None, members);
+            // Not matched by the mock, as the date holds a comma splitting it into several values
+            let requests = mock_server.received_requests().await.unwrap();
+            assert_eq!(LAST_MODIFIED, requests[0].headers["If-Modified-Since"]);
+            assert_eq!(
+                ExportCache::new(Some(ETAG.to_owned()), Some(LAST_MODIFIED.to_owned())),
+                cache
+            );
+        }
+
+        #[tokio::test]
+        async fn update_cache_when_modified() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/en/organization_memberships/export.xls"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .insert_header("ETag", ETAG)
+                        .insert_header("Last-Modified", LAST_MODIFIED)
+                        .set_body_bytes(get_test_file_content()),
+                )
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let mut cache = ExportCache::default();
+            let members = retrieve_members_conditional(&client, &mock_server.uri(), &mut cache)
+                .await
+                .unwrap();
+
+            assert_eq!(Some(get_expected_member()), members);
+            assert_eq!(&Some(ETAG.to_owned()), cache.etag());
+            assert_eq!(&Some(LAST_MODIFIED.to_owned()), cache.last_modified());
+        }
+
+        #[tokio::test]
+        async fn skip_validators_when_cache_empty() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/en/organization_memberships/export.xls"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(get_test_file_content()))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let mut cache = ExportCache::default();
+            retrieve_members_conditional(&client, &mock_server.uri(), &mut cache)
+                .await
+                .unwrap();
+
+            let requests = mock_server.received_requests().await.unwrap();
+            assert!(!requests[0].headers.contains_key("If-None-Match"));
+            assert!(!requests[0].headers.contains_key("If-Modified-Since"));
+            assert_eq!(ExportCache::default(), cache);
+        }
+    }
+
+    mod download_members_export {
+        use crate::error::UdaError::LackOfPermissions;
+        use crate::retrieve_members::{
+            download_members_export, get_test_file_content, setup_member_retrieval,
+        };
+        use crate::tools::build_client;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn success() {
+            let mock_server = MockServer::start().await;
+            setup_member_retrieval(&mock_server).await;
+
+            let client = build_client().unwrap();
+            let export = download_members_export(&client, &mock_server.uri())
+                .await
+                .unwrap();
+
+            assert_eq!(get_test_file_content(), export);
+        }
+
+        #[tokio::test]
+        async fn fail_when_lack_of_permissions() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("en/organization_memberships/export.xls"))
+                .respond_with(ResponseTemplate::new(403))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let error = download_members_export(&client, &mock_server.uri())
+                .await
+                .unwrap_err();
+
+            assert!(matches!(error, LackOfPermissions));
+        }
+    }
+
+    mod retrieve_member_by_id {
+        use crate::error::UdaError;
+        use crate::ids::MemberId;
+        use crate::retrieve_members::{
+            get_expected_member, retrieve_member_by_id, setup_member_retrieval,
+        };
+        use crate::tools::build_client;
+        use crate::uda_member_builder::UdaMemberBuilder;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        const REGISTRANT_PAGE: &str = r#"<html><body><h1>Registrant #2</h1><table>
+            <tr><th>First Name</th><td>Jonette</td></tr>
+            <tr><th>Last Name</th><td>Snow</td></tr>
+            <tr><th>Email</th><td>jonette.snow@email.com</td></tr>
+            <tr><th>Club</th><td>Le club de test</td></tr>
+            <tr><th>Manual Organization Membership#</th><td>ID #654321</td></tr>
+            <tr><th>System Organization Membership#</th><td>654321</td></tr>
+            <tr><th>Confirmed already a member</th><td>Yes</td></tr>
+        </table></body></html>"#;
+
+        async fn setup_registrant_page(mock_server: &MockServer, id: u16, status: u16) {
+            setup_registrant_page_with_body(mock_server, id, ResponseTemplate::new(status)).await;
+        }
+
+        async fn setup_registrant_page_with_body(
+            mock_server: &MockServer,
+            id: u16,
+            response: ResponseTemplate,
+        ) {
+            Mock::given(method("GET"))
+                .and(path(format!("/en/registrants/{id}")))
+                .respond_with(response)
+                .mount(mock_server)
+                .await;
+        }
+
+        async fn setup_unused_export(mock_server: &MockServer) {
+            Mock::given(method("GET"))
+                .and(path("/en/organization_memberships/export.xls"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(0)
+                .mount(mock_server)
+                .await;
+        }
+
+        #[tokio::test]
+        async fn success() {
+            let mock_server = MockServer::start().await;
+            setup_registrant_page_with_body(
+                &mock_server,
+                2,
+                ResponseTemplate::new(200).set_body_string(REGISTRANT_PAGE),
+            )
+            .await;
+            setup_unused_export(&mock_server).await;
+
+            let client = build_client().unwrap();
+            let member = retrieve_member_by_id(&client, &mock_server.uri(), MemberId(2))
+                .await
+                .unwrap();
+
+            let expected_member = UdaMemberBuilder::new()
+                .id(2)
+                .membership_number("654321")
+                .first_name("Jonette")
+                .last_name("Snow")
+                .email("jonette.snow@email.com")
+                .club("Le club de test")
+                .confirmed(true)
+                .build()
+                .unwrap();
+            assert_eq!(Some(expected_member), member);
+        }
+
+        #[tokio::test]
+        async fn read_description_list() {
+            let mock_server = MockServer::start().await;
+            setup_registrant_page_with_body(
+                &mock_server,
+                2,
+                ResponseTemplate::new(200).set_body_string(
+                    "<dl><dt>First Name:</dt><dd>Jonette</dd><dt>Last Name:</dt><dd>Snow</dd>\
+                    <dt>Email:</dt><dd>jonette.snow@email.com</dd><dt>Confirmed:</dt><dd>No</dd></dl>",
+                ),
+            )
+            .await;
+            setup_unused_export(&mock_server).await;
+
+            let client = build_client().unwrap();
+            let member = retrieve_member_by_id(&client, &mock_server.uri(), MemberId(2))
+                .await
+                .unwrap()
+                .unwrap();
+
+            assert_eq!("Jonette", member.first_name());
+            assert_eq!("jonette.snow@email.com", member.email());
+            assert_eq!(&None, member.club());
+            assert!(!member.confirmed());
+        }
+
+        #[tokio::test]
+        async fn fall_back_to_export_when_page_shows_no_details() {
+            let mock_server = MockServer::start().await;
+            setup_member_retrieval(&mock_server).await;
+            setup_registrant_page_with_body(
+                &mock_server,
+                2,
+                ResponseTemplate::new(200).set_body_string("<h1>Registrant #2</h1>"),
+            )
+            .await;
+
+            let client = build_client().unwrap();
+            let member = retrieve_member_by_id(&client, &mock_server.uri(), MemberId(2))
+                .await
+                .unwrap();
+
+            assert_eq!(Some(get_expected_member()[1].clone()), member);
+        }
+
+        #[tokio::test]
+        async fn none_when_not_found() {
+            let mock_server = MockServer::start().await;
+            setup_registrant_page(&mock_server, 42, 404).await;
+            setup_unused_export(&mock_server).await;
+
+            let client = build_client().unwrap();
+            let member = retrieve_member_by_id(&client, &mock_server.uri(), MemberId(42))
+                .await
+                .unwrap();
+
+            assert_eq!(None, member);
+        }
+
+        #[tokio::test]
+        async fn fail_when_lack_of_permissions() {
+            let mock_server = MockServer::start().await;
+            setup_registrant_page(&mock_server, 2, 403).await;
+
+            let client = build_client().unwrap();
+            let error = retrieve_member_by_id(&client, &mock_server.uri(), MemberId(2))
+                .await
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::LackOfPermissions));
+        }
+
+        #[tokio::test]
+        async fn fail_when_session_expired() {
+            let mock_server = MockServer::start().await;
+            setup_registrant_page_with_body(
+                &mock_server,
+                2,
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"<form><input name="user[password]"></form>"#),
+            )
+            .await;
+            setup_unused_export(&mock_server).await;
+
+            let client = build_client().unwrap();
+            let error = retrieve_member_by_id(&client, &mock_server.uri(), MemberId(2))
+                .await
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::SessionExpired));
+        }
+    }
+
+    mod retrieve_members_with_context {
+        use crate::retrieve_members::{
+            get_expected_member, get_test_file_content, retrieve_members_with_context,
+        };
+        use crate::tools::build_client;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        async fn setup_export(mock_server: &MockServer, response: ResponseTemplate) {
+            Mock::given(method("GET"))
+                .and(path("/en/organization_memberships/export.xls"))
+                .respond_with(response.set_body_bytes(get_test_file_content()))
+                .mount(mock_server)
+                .await;
+        }
+
+        #[tokio::test]
+        async fn read_organization_from_filename() {
+            let mock_server = MockServer::start().await;
+            setup_export(
+                &mock_server,
+                ResponseTemplate::new(200).insert_header(
+                    "Content-Disposition",
+                    r#"attachment; filename="MyClub_members.xls""#,
+                ),
+            )
+            .await;
+
+            let client = build_client().unwrap();
+            let export = retrieve_members_with_context(&client, &mock_server.uri())
+                .await
+                .unwrap();
+
+            assert_eq!(Some("MyClub"), export.organization().as_deref());
+            assert_eq!(&get_expected_member(), export.members());
+        }
+
+        #[tokio::test]
+        async fn read_organization_from_dashboard() {
+            let mock_server = MockServer::start().await;
+            setup_export(&mock_server, ResponseTemplate::new(200)).await;
+            Mock::given(method("GET"))
+                .and(path("/en/dashboard"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(
+                    r#"<html><body><div id="current_organization"><a href="/en/organizations/7">MyClub</a></div></body></html>"#,
+                ))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let export = retrieve_members_with_context(&client, &mock_server.uri())
+                .await
+                .unwrap();
+
+            assert_eq!(Some("MyClub"), export.organization().as_deref());
+        }
+
+        #[tokio::test]
+        async fn no_organization_when_unknown() {
+            let mock_server = MockServer::start().await;
+            setup_export(&mock_server, ResponseTemplate::new(200)).await;
+
+            let client = build_client().unwrap();
+            let export = retrieve_members_with_context(&client, &mock_server.uri())
+                .await
+                .unwrap();
+
+            assert_eq!(None, *export.organization());
+            assert_eq!(&get_expected_member(), export.members());
+        }
+    }
+
+    mod has_membership_access {
+        use crate::error::UdaError;
+        use crate::retrieve_members::has_membership_access;
+        use crate::tools::build_client;
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        const EXPORT_PATH: &str = "/en/organization_memberships/export.xls";
+
+        async fn setup_head(mock_server: &MockServer, status: u16) {
+            Mock::given(method("HEAD"))
+                .and(path(EXPORT_PATH))
+                .respond_with(ResponseTemplate::new(status))
+                .mount(mock_server)
+                .await;
+        }
+
+        #[tokio::test]
+        async fn success() {
+            let mock_server = MockServer::start().await;
+            setup_head(&mock_server, 200).await;
+
+            let client = build_client().unwrap();
+            let has_access = has_membership_access(&client, &mock_server.uri())
+                .await
+                .unwrap();
+
+            assert!(has_access);
+            // The export isn't downloaded
+            let requests = mock_server.received_requests().await.unwrap();
+            assert_eq!(1, requests.len());
+            assert_eq!("HEAD", requests[0].method.as_str());
+        }
+
+        #[tokio::test]
+        async fn no_access_when_lack_of_permissions() {
+            for status in [401, 403] {
+                let mock_server = MockServer::start().await;
+                setup_head(&mock_server, status).await;
+
+                let client = build_client().unwrap();
+                let has_access = has_membership_access(&client, &mock_server.uri())
+                    .await
+                    .unwrap();
+
+                assert!(!has_access, "{status}");
+            }
+        }
+
+        #[tokio::test]
+        async fn fail_when_unexpected_status() {
+            let mock_server = MockServer::start().await;
+            setup_head(&mock_server, 500).await;
+
+            let client = build_client().unwrap();
+            let error = has_membership_access(&client, &mock_server.uri())
+                .await
+                .unwrap_err();
+
+            assert!(matches!(
+                error,
+                UdaError::UnexpectedStatus(status) if status.as_u16() == 500
+            ));
+        }
+
+        #[tokio::test]
+        async fn fall_back_to_get_when_head_rejected() {
+            let mock_server = MockServer::start().await;
+            setup_head(&mock_server, 405).await;
+            Mock::given(method("GET"))
+                .and(path(EXPORT_PATH))
+                .and(header("range", "bytes=0-0"))
+                .respond_with(ResponseTemplate::new(206).set_body_bytes(vec![0xd0]))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let has_access = has_membership_access(&client, &mock_server.uri())
+                .await
+                .unwrap();
+
+            assert!(has_access);
+        }
+
+        #[tokio::test]
+        async fn no_access_when_get_fallback_rejected() {
+            let mock_server = MockServer::start().await;
+            setup_head(&mock_server, 405).await;
+            Mock::given(method("GET"))
+                .and(path(EXPORT_PATH))
+                .respond_with(ResponseTemplate::new(403))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let has_access = has_membership_access(&client, &mock_server.uri())
+                .await
+                .unwrap();
 
-    let response = client
-        .get(url)
-        .send()
-        .await
-        .map_err(log_error_and_return(OrganizationMembershipsAccessFailed))?;
+            assert!(!has_access);
+        }
 
-    let status = response.status();
-    if status.is_success() {
-        let body = response.bytes().await.map_err(log_message_and_return(
-            "Can't read organization_memberships content",
-            OrganizationMembershipsAccessFailed,
-        ))?;
+        #[tokio::test]
+        async fn fail_when_redirected_to_sign_in_page() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("HEAD"))
+                .and(path(EXPORT_PATH))
+                .respond_with(
+                    ResponseTemplate::new(302).insert_header("location", "/en/users/sign_in"),
+                )
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("HEAD"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(ResponseTemplate::new(200))
+                .mount(&mock_server)
+                .await;
 
-        retrieve_imported_members_from_xls(Cursor::new(body)).map(|imported_members| {
-            imported_members
-                .into_iter()
-                .map(|imported_member| imported_member.into())
-                .collect()
-        })
-    } else if status.as_u16() == 401 {
-        error!("Can't access organization_memberships page. Lack of permissions?");
-        Err(LackOfPermissions)
-    } else {
-        error!(
-            "Can't reach organization_memberships page: {:?}",
-            response.status()
-        );
-        Err(OrganizationMembershipsAccessFailed)?
+            let client = build_client().unwrap();
+            let error = has_membership_access(&client, &mock_server.uri())
+                .await
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::SessionExpired));
+        }
+
+        #[tokio::test]
+        async fn fail_when_unreachable() {
+            let client = build_client().unwrap();
+            let error = has_membership_access(&client, "http://127.0.0.1:1")
+                .await
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::ConnectionFailed(_)));
+        }
     }
-}
 
-fn retrieve_imported_members_from_xls<T: AsRef<[u8]>>(
-    cursor: Cursor<T>,
-) -> Result<Vec<ImportedUdaMember>> {
-    let mut workbook: Xls<_> =
-        open_workbook_from_rs(cursor).map_err(log_error_and_return(MalformedXlsFile))?;
-    let sheets = workbook.sheet_names();
-    let first_sheet = sheets.first();
-    let worksheet_name = first_sheet.ok_or(MalformedXlsFile)?;
-    let range = workbook
-        .worksheet_range(worksheet_name)
-        .map_err(log_message_and_return(
-            "Can't read organization_memberships content",
-            MalformedXlsFile,
-        ))?;
-    let deserializer: RangeDeserializer<'_, Data, ImportedUdaMember> =
-        RangeDeserializerBuilder::new()
-            .has_headers(true)
-            .from_range(&range)
-            .map_err(log_message_and_return(
-                "Can't read organization_memberships content",
-                MalformedXlsFile,
-            ))?;
-
-    let members = deserializer
-        .flat_map(|result| match result {
-            Ok(member) => {
-                match member.id() {
-                    0..2000 => Some(member),
-                    _ => None, // IDs over 2000 relate to non-competitors: they don't require a membership.
-                }
-            }
-            Err(error) => {
-                warn!("Can't deserialize UDA member. Ignoring. {:?}", error);
-                None
-            }
-        })
-        .collect();
+    mod filename_from_content_disposition {
+        use crate::retrieve_members::{
+            filename_from_content_disposition, organization_from_filename,
+        };
 
-    Ok(members)
-}
+        #[test]
+        fn parse_filename() {
+            assert_eq!(
+                Some("MyClub_members.xls".to_owned()),
+                filename_from_content_disposition(r#"attachment; filename="MyClub_members.xls""#)
+            );
+            assert_eq!(
+                Some("export.xls".to_owned()),
+                filename_from_content_disposition("attachment;filename=export.xls")
+            );
+            assert_eq!(None, filename_from_content_disposition("attachment"));
+        }
 
-#[cfg(any(test, feature = "test"))]
-fn get_test_file_content() -> Vec<u8> {
-    std::fs::read("test/resources/uda_members.xls").unwrap()
-}
+        #[test]
+        fn parse_organization() {
+            assert_eq!(
+                Some("MyClub".to_owned()),
+                organization_from_filename("MyClub_members.xls")
+            );
+            assert_eq!(None, organization_from_filename("export.xls"));
+            assert_eq!(None, organization_from_filename("_members.xls"));
+        }
+    }
 
-#[cfg(any(test, feature = "test"))]
-fn get_expected_member() -> Vec<UdaMember> {
-    vec![
-        UdaMember::new(
-            1,
-            Some("123456".to_owned()),
-            "Jon".to_owned(),
-            "Doe".to_owned(),
-            "jon.doe@email.com".to_owned(),
-            Some("Le club de test".to_owned()),
-            true,
-        ),
-        UdaMember::new(
-            2,
-            Some("654321".to_owned()),
-            "Jonette".to_owned(),
-            "Snow".to_owned(),
-            "jonette.snow@email.com".to_owned(),
-            None,
-            false,
-        ),
-        UdaMember::new(
-            1999,
-            Some("456789".to_owned()),
-            "Kris".to_owned(),
-            "Holm".to_owned(),
-            "kris.holm@email.com".to_owned(),
-            Some("KH Team".to_owned()),
-            true,
-        ),
-    ]
-}
+    mod parse_members_export {
+        use crate::error::UdaError::MalformedXlsFile;
+        use crate::retrieve_members::{
+            get_expected_member, get_test_file_content, parse_members_export,
+        };
 
-#[cfg(any(test, feature = "test"))]
-pub async fn setup_member_retrieval(mock_server: &MockServer) -> Vec<UdaMember> {
-    let body = get_test_file_content();
+        #[test]
+        fn success() {
+            let members = parse_members_export(&get_test_file_content()).unwrap();
 
-    Mock::given(method("GET"))
-        .and(path("/en/organization_memberships/export.xls"))
-        .respond_with(ResponseTemplate::new(200).set_body_bytes(body))
-        .mount(mock_server)
-        .await;
+            assert_eq!(get_expected_member(), members);
+        }
 
-    get_expected_member()
-}
+        #[test]
+        fn fail_when_not_a_spreadsheet() {
+            let error = parse_members_export(b"not a spreadsheet").unwrap_err();
 
-#[cfg(test)]
-pub mod tests {
-    mod retrieve_members {
-        use crate::error::UdaError;
-        use crate::error::UdaError::LackOfPermissions;
-        use crate::retrieve_members::{retrieve_members, setup_member_retrieval};
-        use crate::tools::tests::build_client;
+            assert!(matches!(error, MalformedXlsFile(_)));
+        }
+    }
+
+    mod retrieve_members_csv {
+        use crate::error::UdaError::FeatureNotAvailable;
+        use crate::retrieve_members::{get_expected_member, retrieve_members_csv};
+        use crate::tools::build_client;
         use wiremock::matchers::{method, path};
         use wiremock::{Mock, MockServer, ResponseTemplate};
-        use UdaError::OrganizationMembershipsAccessFailed;
 
         #[tokio::test]
         async fn success() {
             let mock_server = MockServer::start().await;
-            let expected_result = setup_member_retrieval(&mock_server).await;
+            let content = std::fs::read("test/resources/uda_members.csv").unwrap();
+            Mock::given(method("GET"))
+                .and(path("/en/organization_memberships/export.csv"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(content))
+                .mount(&mock_server)
+                .await;
+
             let client = build_client().unwrap();
-            let result = retrieve_members(&client, &mock_server.uri()).await.unwrap();
-            assert_eq!(expected_result, result);
+            let members = retrieve_members_csv(&client, &mock_server.uri())
+                .await
+                .unwrap();
+
+            assert_eq!(get_expected_member(), members);
         }
 
         #[tokio::test]
-        async fn fail_when_unreachable() {
+        async fn fail_when_csv_export_not_available() {
             let mock_server = MockServer::start().await;
-            let client = build_client().unwrap();
             Mock::given(method("GET"))
-                .and(path("en/organization_memberships/export.xls"))
-                .respond_with(ResponseTemplate::new(500))
+                .and(path("/en/organization_memberships/export.csv"))
+                .respond_with(ResponseTemplate::new(404))
                 .mount(&mock_server)
                 .await;
 
-            let error = retrieve_members(&client, &mock_server.uri())
+            let client = build_client().unwrap();
+            let error = retrieve_members_csv(&client, &mock_server.uri())
                 .await
                 .unwrap_err();
-            assert!(matches!(error, OrganizationMembershipsAccessFailed));
+
+            assert!(matches!(error, FeatureNotAvailable(_)));
+        }
+    }
+
+    mod parse_members_csv {
+        use crate::retrieve_members::{
+            get_expected_member, get_test_file_content, parse_members_csv, parse_members_export,
+        };
+
+        #[test]
+        fn same_members_as_xls_export() {
+            let content = std::fs::read("test/resources/uda_members.csv").unwrap();
+
+            let members = parse_members_csv(&content).unwrap();
+
+            assert_eq!(
+                parse_members_export(&get_test_file_content()).unwrap(),
+                members
+            );
+        }
+
+        #[test]
+        fn success_without_bom() {
+            let content = std::fs::read("test/resources/uda_members.csv").unwrap();
+
+            let members = parse_members_csv(&content[3..]).unwrap();
+
+            assert_eq!(get_expected_member(), members);
+        }
+
+        #[test]
+        fn success_with_renamed_headers() {
+            let content = std::fs::read_to_string("test/resources/uda_members.csv")
+                .unwrap()
+                .replacen("Birthday", "Date of birth", 1)
+                .replacen("Email", "E-mail", 1);
+
+            let members = parse_members_csv(content.as_bytes()).unwrap();
+
+            assert_eq!(get_expected_member(), members);
+        }
+
+        #[test]
+        fn skip_malformed_record() {
+            let content = "Id,First Name\nnot a number,Jon\n";
+
+            let members = parse_members_csv(content.as_bytes()).unwrap();
+
+            assert!(members.is_empty());
+        }
+    }
+
+    mod retrieve_members_stream {
+        use crate::error::UdaError;
+        use crate::retrieve_members::{
+            retrieve_members_stream, retrieve_members_stream_with_cancellation,
+            setup_member_retrieval,
+        };
+        use crate::tools::build_client;
+        use futures_util::{StreamExt, TryStreamExt};
+        use std::pin::pin;
+        use tokio_util::sync::CancellationToken;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn success() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+            let expected_members = setup_member_retrieval(&mock_server).await;
+
+            let members: Vec<_> = retrieve_members_stream(&client, &mock_server.uri())
+                .try_collect()
+                .await
+                .unwrap();
+
+            assert_eq!(expected_members, members);
         }
 
         #[tokio::test]
@@ -184,23 +2340,73 @@ pub mod tests {
             let mock_server = MockServer::start().await;
             let client = build_client().unwrap();
             Mock::given(method("GET"))
-                .and(path("en/organization_memberships/export.xls"))
-                .respond_with(ResponseTemplate::new(401))
+                .and(path("/en/organization_memberships/export.xls"))
+                .respond_with(ResponseTemplate::new(403))
                 .mount(&mock_server)
                 .await;
 
-            let error = retrieve_members(&client, &mock_server.uri())
+            let error = retrieve_members_stream(&client, &mock_server.uri())
+                .try_collect::<Vec<_>>()
                 .await
                 .unwrap_err();
-            assert!(matches!(error, LackOfPermissions));
+
+            assert!(matches!(error, UdaError::LackOfPermissions));
+        }
+
+        #[tokio::test]
+        async fn stop_when_cancelled() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+            let expected_members = setup_member_retrieval(&mock_server).await;
+            let base_url = mock_server.uri();
+            let cancellation_token = CancellationToken::new();
+
+            let mut members = pin!(retrieve_members_stream_with_cancellation(
+                &client,
+                &base_url,
+                &cancellation_token
+            ));
+            let first_member = members.next().await.unwrap().unwrap();
+            cancellation_token.cancel();
+
+            assert_eq!(expected_members[0], first_member);
+            assert!(matches!(
+                members.next().await,
+                Some(Err(UdaError::Cancelled))
+            ));
+            assert!(members.next().await.is_none());
+        }
+
+        #[tokio::test]
+        async fn send_no_request_when_cancelled() {
+            let mock_server = MockServer::start().await;
+            let client = build_client().unwrap();
+            let cancellation_token = CancellationToken::new();
+            cancellation_token.cancel();
+
+            let error = retrieve_members_stream_with_cancellation(
+                &client,
+                &mock_server.uri(),
+                &cancellation_token,
+            )
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap_err();
+
+            assert!(matches!(error, UdaError::Cancelled));
+            assert!(mock_server.received_requests().await.unwrap().is_empty());
         }
     }
 
     mod retrieve_imported_members_from_xls {
         use crate::error::UdaError;
         use crate::imported_uda_member::ImportedUdaMember;
-        use crate::retrieve_members::{get_test_file_content, retrieve_imported_members_from_xls};
+        use crate::retrieve_members::{
+            get_test_file_content, get_xlsx_test_file_content, retrieve_imported_members_from_xls,
+            MemberFilter,
+        };
         use std::io::Cursor;
+        use uda_dto::uda_member::UdaMember;
         use UdaError::MalformedXlsFile;
 
         fn get_expected_imported_members() -> Vec<ImportedUdaMember> {
@@ -262,15 +2468,129 @@ pub mod tests {
         #[test]
         fn success() {
             let content = get_test_file_content();
-            let members = retrieve_imported_members_from_xls(Cursor::new(content)).unwrap();
+            let members = retrieve_imported_members_from_xls(
+                Cursor::new(content),
+                &MemberFilter::default(),
+                None,
+            )
+            .unwrap()
+            .members;
+            assert_eq!(get_expected_imported_members(), members)
+        }
+
+        #[test]
+        fn success_with_xlsx() {
+            let content = get_xlsx_test_file_content();
+            let members = retrieve_imported_members_from_xls(
+                Cursor::new(content),
+                &MemberFilter::default(),
+                None,
+            )
+            .unwrap()
+            .members;
+            assert_eq!(get_expected_imported_members(), members)
+        }
+
+        #[test]
+        fn success_with_renamed_headers() {
+            let content =
+                std::fs::read("test/resources/uda_members_with_renamed_headers.xlsx").unwrap();
+            let members = retrieve_imported_members_from_xls(
+                Cursor::new(content),
+                &MemberFilter::default(),
+                None,
+            )
+            .unwrap()
+            .members;
             assert_eq!(get_expected_imported_members(), members)
         }
 
+        fn retrieve_ids(filter: &MemberFilter) -> Vec<u16> {
+            let content = get_test_file_content();
+            retrieve_imported_members_from_xls(Cursor::new(content), filter, None)
+                .unwrap()
+                .members
+                .iter()
+                .map(|member| *member.id())
+                .collect()
+        }
+
+        #[test]
+        fn filter_non_competitors_by_default() {
+            assert_eq!(vec![1, 2, 1999], retrieve_ids(&MemberFilter::default()));
+        }
+
+        #[test]
+        fn filter_with_custom_max_competitor_id() {
+            assert_eq!(vec![1, 2], retrieve_ids(&MemberFilter::new(Some(100))));
+        }
+
+        #[test]
+        fn keep_member_with_max_competitor_id() {
+            assert_eq!(
+                vec![1, 2, 1999],
+                retrieve_ids(&MemberFilter::new(Some(1999)))
+            );
+            assert_eq!(vec![1, 2], retrieve_ids(&MemberFilter::new(Some(1998))));
+        }
+
+        #[test]
+        fn keep_non_competitors_when_included() {
+            let content =
+                std::fs::read("test/resources/uda_members_with_non_competitor.xlsx").unwrap();
+            let filter = MemberFilter::default().with_include_non_competitors(true);
+            let members = retrieve_imported_members_from_xls(Cursor::new(content), &filter, None)
+                .unwrap()
+                .members
+                .into_iter()
+                .map(UdaMember::from)
+                .collect::<Vec<_>>();
+
+            let flags = members
+                .iter()
+                .map(|member| (*member.id(), filter.is_competitor(member)))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                vec![(1, true), (2, true), (1999, true), (2500, false)],
+                flags
+            );
+        }
+
+        #[test]
+        fn drop_non_competitors_when_not_included() {
+            let content =
+                std::fs::read("test/resources/uda_members_with_non_competitor.xlsx").unwrap();
+            let members = retrieve_imported_members_from_xls(
+                Cursor::new(content),
+                &MemberFilter::default().with_include_non_competitors(false),
+                None,
+            )
+            .unwrap()
+            .members;
+
+            let ids = members
+                .iter()
+                .map(|member| *member.id())
+                .collect::<Vec<_>>();
+            assert_eq!(vec![1, 2, 1999], ids);
+        }
+
+        #[test]
+        fn keep_every_member_without_max_competitor_id() {
+            assert_eq!(
+                vec![1, 2, 1999, 2000],
+                retrieve_ids(&MemberFilter::new(None))
+            );
+        }
+
         #[test]
         fn ignore_member_when_missing_field() {
             let content = std::fs::read("test/resources/uda_members_1_invalid.xls").unwrap();
             let cursor = Cursor::new(content);
-            let members = retrieve_imported_members_from_xls(cursor).unwrap();
+            let members =
+                retrieve_imported_members_from_xls(cursor, &MemberFilter::default(), None)
+                    .unwrap()
+                    .members;
             assert_eq!(
                 vec![ImportedUdaMember::new(
                     1,
@@ -293,12 +2613,91 @@ pub mod tests {
             );
         }
 
+        #[test]
+        fn report_member_when_missing_field() {
+            let content = std::fs::read("test/resources/uda_members_1_invalid.xls").unwrap();
+            let cursor = Cursor::new(content);
+            let skipped =
+                retrieve_imported_members_from_xls(cursor, &MemberFilter::default(), None)
+                    .unwrap()
+                    .skipped;
+            assert_eq!(1, skipped.len());
+            assert_eq!(2, *skipped[0].row_index());
+            assert!(!skipped[0].error().is_empty());
+        }
+
+        #[test]
+        fn success_with_sheet_name() {
+            let content = std::fs::read("test/resources/uda_members_with_summary.xlsx").unwrap();
+            let members = retrieve_imported_members_from_xls(
+                Cursor::new(content),
+                &MemberFilter::default(),
+                Some("Members"),
+            )
+            .unwrap()
+            .members;
+            assert_eq!(get_expected_imported_members(), members)
+        }
+
+        #[test]
+        fn fail_when_sheet_name_not_found() {
+            let content = get_test_file_content();
+            let error = retrieve_imported_members_from_xls(
+                Cursor::new(content),
+                &MemberFilter::default(),
+                Some("Members"),
+            )
+            .err()
+            .unwrap();
+            assert!(matches!(error, UdaError::WorksheetNotFound(name) if name == "Members"));
+        }
+
+        #[test]
+        fn read_first_sheet_when_no_sheet_name() {
+            let content = std::fs::read("test/resources/uda_members_with_summary.xlsx").unwrap();
+            let members = retrieve_imported_members_from_xls(
+                Cursor::new(content),
+                &MemberFilter::default(),
+                None,
+            )
+            .unwrap()
+            .members;
+            assert!(members.is_empty());
+        }
+
+        #[test]
+        fn success_when_no_member() {
+            let content = std::fs::read("test/resources/uda_members_empty.xls").unwrap();
+            let imported_members = retrieve_imported_members_from_xls(
+                Cursor::new(content),
+                &MemberFilter::default(),
+                None,
+            )
+            .unwrap();
+            assert!(imported_members.members.is_empty());
+            assert!(imported_members.skipped.is_empty());
+        }
+
         #[test]
         fn fail_when_malformed_xls() {
-            let error = retrieve_imported_members_from_xls(Cursor::new(""))
-                .err()
-                .unwrap();
-            assert!(matches!(error, MalformedXlsFile));
+            let error =
+                retrieve_imported_members_from_xls(Cursor::new(""), &MemberFilter::default(), None)
+                    .err()
+                    .unwrap();
+            assert!(matches!(error, MalformedXlsFile(_)));
+        }
+
+        #[test]
+        fn fail_when_password_protected() {
+            let content = std::fs::read("test/resources/uda_members_protected.xlsx").unwrap();
+            let error = retrieve_imported_members_from_xls(
+                Cursor::new(content),
+                &MemberFilter::default(),
+                None,
+            )
+            .err()
+            .unwrap();
+            assert!(matches!(error, UdaError::ProtectedWorkbook));
         }
     }
 }
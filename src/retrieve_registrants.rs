@@ -0,0 +1,655 @@
+use crate::error::log_message_and_return;
+use crate::error::UdaError;
+use crate::error::UdaError::{
+    FeatureNotAvailable, LackOfPermissions, SessionExpired, UnexpectedStatus,
+};
+use crate::gender::Gender;
+use crate::ids::{CompetitionId, EventId};
+use crate::locale::Locale;
+use crate::rate_limit::RateLimiter;
+use crate::retrieve_members::is_sign_in_page;
+use crate::retry::{send_cancellable_with_retry, RetryPolicy};
+use crate::spreadsheet::deserialize_first_sheet;
+use crate::tools::{authorize, build_url, BasicAuth};
+use crate::uda_registrant::{split_events, UdaRegistrant};
+use crate::Result;
+use log::{error, warn};
+use reqwest::{Client, StatusCode};
+use scraper::{ElementRef, Html, Selector};
+use std::io::Cursor;
+use tokio_util::sync::CancellationToken;
+
+/// Retrieve the registrants of a competition from UDA.
+pub async fn retrieve_registrants(
+    client: &Client,
+    base_url: &str,
+    competition_id: CompetitionId,
+) -> Result<Vec<UdaRegistrant>> {
+    fetch_registrants(
+        client,
+        base_url,
+        Locale::default(),
+        competition_id,
+        &RetryPolicy::default(),
+        None,
+        None,
+        None,
+    )
+    .await
+}
+
+/// Retrieve the registrants of a competition, waiting for the rate limiter, if any, before each attempt.
+/// The token, if any, is checked once the rate limiter lets each attempt through.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn fetch_registrants(
+    client: &Client,
+    base_url: &str,
+    locale: Locale,
+    competition_id: CompetitionId,
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+    cancellation_token: Option<&CancellationToken>,
+) -> Result<Vec<UdaRegistrant>> {
+    let url = build_url(
+        base_url,
+        &format!(
+            "{}/competitions/{competition_id}/registrants.xls",
+            locale.code()
+        ),
+    )?;
+    fetch_registrants_export(
+        client,
+        &url,
+        retry_policy,
+        rate_limiter,
+        basic_auth,
+        cancellation_token,
+    )
+    .await
+}
+
+/// Retrieve the registrants of a single event of a competition, e.g. to schedule its heats.
+/// Fails with [`FeatureNotAvailable`] on instances that don't export registrants per event.
+pub async fn retrieve_registrants_for_event(
+    client: &Client,
+    base_url: &str,
+    competition_id: CompetitionId,
+    event_id: EventId,
+) -> Result<Vec<UdaRegistrant>> {
+    fetch_registrants_for_event(
+        client,
+        base_url,
+        Locale::default(),
+        competition_id,
+        event_id,
+        &RetryPolicy::default(),
+        None,
+        None,
+    )
+    .await
+}
+
+/// Retrieve the registrants of a single event, waiting for the rate limiter, if any, before each attempt.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn fetch_registrants_for_event(
+    client: &Client,
+    base_url: &str,
+    locale: Locale,
+    competition_id: CompetitionId,
+    event_id: EventId,
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+) -> Result<Vec<UdaRegistrant>> {
+    let url = build_url(
+        base_url,
+        &format!(
+            "{}/competitions/{competition_id}/events/{event_id}/registrants.xls",
+            locale.code()
+        ),
+    )?;
+    fetch_registrants_export(client, &url, retry_policy, rate_limiter, basic_auth, None)
+        .await
+        .map_err(|error| match error {
+            UnexpectedStatus(StatusCode::NOT_FOUND) => {
+                error!("Can't find event registrants export. Unsupported by this UDA version? [competition: {competition_id}, event: {event_id}]");
+                FeatureNotAvailable("event_registrants".to_owned())
+            }
+            error => error,
+        })
+}
+
+async fn fetch_registrants_export(
+    client: &Client,
+    url: &str,
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+    cancellation_token: Option<&CancellationToken>,
+) -> Result<Vec<UdaRegistrant>> {
+    let response =
+        send_cancellable_with_retry(retry_policy, rate_limiter, cancellation_token, || {
+            authorize(client.get(url), url, basic_auth)
+        })
+        .await?
+        .map_err(log_message_and_return(
+            "Can't reach registrants export",
+            UdaError::connection,
+        ))?;
+
+    let status = response.status();
+    if status.is_success() {
+        let body = response.bytes().await.map_err(log_message_and_return(
+            "Can't read registrants export",
+            UdaError::connection,
+        ))?;
+        if is_sign_in_page(&body) {
+            error!("Redirected to sign in page instead of export. Session expired?");
+            return Err(SessionExpired);
+        }
+
+        deserialize_first_sheet(Cursor::new(body)).map(|registrants| registrants.rows)
+    } else if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+        error!("Can't access registrants export. Lack of permissions? [status: {status}]");
+        Err(LackOfPermissions)
+    } else {
+        error!("Can't reach registrants export: {status:?}");
+        Err(UnexpectedStatus(status))
+    }
+}
+
+/// Maximum number of pages of registrants read, should UDA keep linking to a next page.
+const MAX_REGISTRANTS_PAGES: u32 = 100;
+
+/// Retrieve the registrants of a competition from its paginated HTML list,
+/// for instances that don't export them.
+/// When there are more than [`MAX_REGISTRANTS_PAGES`] pages, the registrants read so far are returned.
+pub async fn retrieve_registrants_html(
+    client: &Client,
+    base_url: &str,
+    competition_id: CompetitionId,
+) -> Result<Vec<UdaRegistrant>> {
+    get_registrants_pages(
+        client,
+        base_url,
+        Locale::default(),
+        competition_id,
+        &RetryPolicy::default(),
+        None,
+        None,
+        None,
+    )
+    .await
+}
+
+/// Retrieve the registrants of a competition from its paginated HTML list,
+/// like [`retrieve_registrants_html`], stopping once the token is cancelled, e.g. on shutdown.
+/// The token is checked before each page is requested, failing with [`UdaError::Cancelled`].
+pub async fn retrieve_registrants_html_with_cancellation(
+    client: &Client,
+    base_url: &str,
+    competition_id: CompetitionId,
+    cancellation_token: &CancellationToken,
+) -> Result<Vec<UdaRegistrant>> {
+    get_registrants_pages(
+        client,
+        base_url,
+        Locale::default(),
+        competition_id,
+        &RetryPolicy::default(),
+        None,
+        None,
+        Some(cancellation_token),
+    )
+    .await
+}
+
+/// Retrieve the registrants of a competition from its paginated HTML list,
+/// waiting for the rate limiter, if any, before each request.
+/// The token, if any, is checked once the rate limiter lets each request through.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn get_registrants_pages(
+    client: &Client,
+    base_url: &str,
+    locale: Locale,
+    competition_id: CompetitionId,
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+    cancellation_token: Option<&CancellationToken>,
+) -> Result<Vec<UdaRegistrant>> {
+    let mut registrants = vec![];
+    for page in 1..=MAX_REGISTRANTS_PAGES {
+        let body = get_registrants_page(
+            client,
+            base_url,
+            locale,
+            competition_id,
+            page,
+            retry_policy,
+            rate_limiter,
+            basic_auth,
+            cancellation_token,
+        )
+        .await?;
+        let (page_registrants, has_next_page) = get_registrants_from_html(&body)?;
+        if page_registrants.is_empty() {
+            return Ok(registrants);
+        }
+        registrants.extend(page_registrants);
+        if !has_next_page {
+            return Ok(registrants);
+        }
+    }
+
+    warn!(
+        "Too many pages of registrants. Ignoring the next ones. [competition: {competition_id}, pages: {MAX_REGISTRANTS_PAGES}]"
+    );
+    Ok(registrants)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn get_registrants_page(
+    client: &Client,
+    base_url: &str,
+    locale: Locale,
+    competition_id: CompetitionId,
+    page: u32,
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    basic_auth: Option<&BasicAuth>,
+    cancellation_token: Option<&CancellationToken>,
+) -> Result<String> {
+    let url = build_url(
+        base_url,
+        &format!(
+            "{}/competitions/{competition_id}/registrants?page={page}",
+            locale.code()
+        ),
+    )?;
+    let response =
+        send_cancellable_with_retry(retry_policy, rate_limiter, cancellation_token, || {
+            authorize(client.get(&url), &url, basic_auth)
+        })
+        .await?
+        .map_err(log_message_and_return(
+            "Can't reach registrants page",
+            UdaError::connection,
+        ))?;
+
+    let status = response.status();
+    if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+        error!("Can't access registrants page. Lack of permissions? [status: {status}]");
+        return Err(LackOfPermissions);
+    } else if !status.is_success() {
+        error!("Can't reach registrants page: {status:?} [page: {page}]");
+        return Err(UnexpectedStatus(status));
+    }
+
+    let body = response.text().await.map_err(log_message_and_return(
+        "Can't read registrants page",
+        UdaError::connection,
+    ))?;
+    if is_sign_in_page(body.as_bytes()) {
+        error!("Redirected to sign in page instead of registrants. Session expired?");
+        return Err(SessionExpired);
+    }
+    Ok(body)
+}
+
+/// Registrants listed on the page, and whether the page links to a next one.
+fn get_registrants_from_html(body: &str) -> Result<(Vec<UdaRegistrant>, bool)> {
+    let row_selector = Selector::parse("tbody tr").map_err(UdaError::from)?;
+    let next_selector = Selector::parse(r#"a[rel="next"]"#).map_err(UdaError::from)?;
+    let document = Html::parse_document(body);
+
+    let registrants = document
+        .select(&row_selector)
+        .flat_map(|row| get_registrant_from_row(&row))
+        .collect();
+    let has_next_page = document.select(&next_selector).next().is_some();
+
+    Ok((registrants, has_next_page))
+}
+
+fn get_registrant_from_row(row: &ElementRef) -> Option<UdaRegistrant> {
+    let selector = Selector::parse("td").ok()?;
+    let cells = row
+        .select(&selector)
+        .map(|cell| cell.text().collect::<String>().trim().to_owned())
+        .collect::<Vec<_>>();
+
+    let [id, first_name, last_name, age, gender, events] = &cells[..] else {
+        warn!("Ignoring registrant because wrongly formatted [row: {row:?}]");
+        return None;
+    };
+    let (Ok(id), Ok(age)) = (id.parse(), age.parse()) else {
+        warn!("Ignoring registrant because of invalid id or age [id: {id}, age: {age}]");
+        return None;
+    };
+
+    Some(UdaRegistrant::new(
+        id,
+        first_name.to_owned(),
+        last_name.to_owned(),
+        age,
+        Gender::from(gender.as_str()),
+        split_events(events),
+    ))
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod retrieve_registrants {
+        use crate::error::UdaError;
+        use crate::gender::Gender;
+        use crate::ids::{CompetitionId, MemberId};
+        use crate::locale::Locale;
+        use crate::retrieve_registrants::{fetch_registrants, retrieve_registrants};
+        use crate::retry::RetryPolicy;
+        use crate::tools::build_client;
+        use crate::uda_registrant::UdaRegistrant;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        pub fn get_expected_registrants() -> Vec<UdaRegistrant> {
+            vec![
+                UdaRegistrant::new(
+                    MemberId(1),
+                    "Jon".to_owned(),
+                    "Doe".to_owned(),
+                    42,
+                    Gender::Male,
+                    vec!["100m".to_owned(), "Freestyle".to_owned()],
+                ),
+                UdaRegistrant::new(
+                    MemberId(2),
+                    "Jonette".to_owned(),
+                    "Snow".to_owned(),
+                    34,
+                    Gender::Female,
+                    vec!["Marathon".to_owned()],
+                ),
+                UdaRegistrant::new(
+                    MemberId(3),
+                    "Kris".to_owned(),
+                    "Holm".to_owned(),
+                    49,
+                    Gender::Male,
+                    vec![],
+                ),
+            ]
+        }
+
+        #[tokio::test]
+        async fn success() {
+            let mock_server = MockServer::start().await;
+            let content = std::fs::read("test/resources/uda_registrants.xlsx").unwrap();
+            Mock::given(method("GET"))
+                .and(path("/en/competitions/12/registrants.xls"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(content))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let registrants = retrieve_registrants(&client, &mock_server.uri(), CompetitionId(12))
+                .await
+                .unwrap();
+
+            assert_eq!(get_expected_registrants(), registrants);
+        }
+
+        #[tokio::test]
+        async fn success_in_locale() {
+            let mock_server = MockServer::start().await;
+            let content = std::fs::read("test/resources/uda_registrants.xlsx").unwrap();
+            Mock::given(method("GET"))
+                .and(path("/fr/competitions/12/registrants.xls"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(content))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let registrants = fetch_registrants(
+                &client,
+                &mock_server.uri(),
+                Locale::Fr,
+                CompetitionId(12),
+                &RetryPolicy::default(),
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(get_expected_registrants(), registrants);
+        }
+
+        #[tokio::test]
+        async fn fail_when_lack_of_permissions() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/en/competitions/12/registrants.xls"))
+                .respond_with(ResponseTemplate::new(403))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let error = retrieve_registrants(&client, &mock_server.uri(), CompetitionId(12))
+                .await
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::LackOfPermissions));
+        }
+    }
+
+    mod retrieve_registrants_for_event {
+        use crate::error::UdaError;
+        use crate::ids::{CompetitionId, EventId};
+        use crate::retrieve_registrants::retrieve_registrants_for_event;
+        use crate::retrieve_registrants::tests::retrieve_registrants::get_expected_registrants;
+        use crate::tools::build_client;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        async fn setup_event_export(mock_server: &MockServer, file: &str) {
+            let content = std::fs::read(file).unwrap();
+            Mock::given(method("GET"))
+                .and(path("/en/competitions/12/events/4/registrants.xls"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(content))
+                .mount(mock_server)
+                .await;
+        }
+
+        #[tokio::test]
+        async fn success() {
+            let mock_server = MockServer::start().await;
+            setup_event_export(&mock_server, "test/resources/uda_registrants.xlsx").await;
+
+            let client = build_client().unwrap();
+            let registrants = retrieve_registrants_for_event(
+                &client,
+                &mock_server.uri(),
+                CompetitionId(12),
+                EventId(4),
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(get_expected_registrants(), registrants);
+        }
+
+        #[tokio::test]
+        async fn success_when_no_entrant() {
+            let mock_server = MockServer::start().await;
+            setup_event_export(&mock_server, "test/resources/uda_registrants_empty.xlsx").await;
+
+            let client = build_client().unwrap();
+            let registrants = retrieve_registrants_for_event(
+                &client,
+                &mock_server.uri(),
+                CompetitionId(12),
+                EventId(4),
+            )
+            .await
+            .unwrap();
+
+            assert!(registrants.is_empty());
+        }
+
+        #[tokio::test]
+        async fn fail_when_not_available() {
+            let mock_server = MockServer::start().await;
+
+            let client = build_client().unwrap();
+            let error = retrieve_registrants_for_event(
+                &client,
+                &mock_server.uri(),
+                CompetitionId(12),
+                EventId(4),
+            )
+            .await
+            .unwrap_err();
+
+            assert!(matches!(error, UdaError::FeatureNotAvailable(_)));
+        }
+    }
+
+    mod retrieve_registrants_html {
+        use crate::error::UdaError;
+        use crate::gender::Gender;
+        use crate::ids::{CompetitionId, MemberId};
+        use crate::retrieve_registrants::{
+            retrieve_registrants_html, retrieve_registrants_html_with_cancellation,
+        };
+        use crate::tools::build_client;
+        use crate::tools::tests::CancelAfterRequests;
+        use crate::uda_registrant::UdaRegistrant;
+        use tokio_util::sync::CancellationToken;
+        use wiremock::matchers::{method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        const FIRST_PAGE: &str = r##"<html><body><table><thead><tr><th>ID</th><th>First Name</th><th>Last Name</th><th>Age</th><th>Gender</th><th>Events</th></tr></thead><tbody><tr><td>1</td><td>Jon</td><td>Doe</td><td>42</td><td>Male</td><td>100m, Freestyle</td></tr><tr><td>2</td><td>Jonette</td><td>Snow</td><td>34</td><td>Female</td><td>Marathon</td></tr></tbody></table><nav class="pagination"><a rel="next" href="/en/competitions/12/registrants?page=2">Next</a></nav></body></html>"##;
+        const SECOND_PAGE: &str = r##"<html><body><table><thead><tr><th>ID</th><th>First Name</th><th>Last Name</th><th>Age</th><th>Gender</th><th>Events</th></tr></thead><tbody><tr><td>3</td><td>Kris</td><td>Holm</td><td>49</td><td>Male</td><td></td></tr></tbody></table><nav class="pagination"><a rel="next" href="/en/competitions/12/registrants?page=3">Next</a></nav></body></html>"##;
+        const LAST_PAGE: &str = r##"<html><body><table><thead><tr><th>ID</th><th>First Name</th><th>Last Name</th><th>Age</th><th>Gender</th><th>Events</th></tr></thead><tbody><tr><td>3</td><td>Kris</td><td>Holm</td><td>49</td><td>Male</td><td></td></tr></tbody></table></body></html>"##;
+        const EMPTY_PAGE: &str = r##"<html><body><table><thead><tr><th>ID</th><th>First Name</th><th>Last Name</th><th>Age</th><th>Gender</th><th>Events</th></tr></thead><tbody></tbody></table></body></html>"##;
+
+        async fn mount_page(mock_server: &MockServer, page: &str, body: &str) {
+            Mock::given(method("GET"))
+                .and(path("/en/competitions/12/registrants"))
+                .and(query_param("page", page))
+                .respond_with(ResponseTemplate::new(200).set_body_string(body))
+                .mount(mock_server)
+                .await;
+        }
+
+        #[tokio::test]
+        async fn success_across_pages() {
+            let mock_server = MockServer::start().await;
+            mount_page(&mock_server, "1", FIRST_PAGE).await;
+            mount_page(&mock_server, "2", SECOND_PAGE).await;
+            mount_page(&mock_server, "3", EMPTY_PAGE).await;
+
+            let client = build_client().unwrap();
+            let registrants =
+                retrieve_registrants_html(&client, &mock_server.uri(), CompetitionId(12))
+                    .await
+                    .unwrap();
+
+            assert_eq!(
+                vec![
+                    UdaRegistrant::new(
+                        MemberId(1),
+                        "Jon".to_owned(),
+                        "Doe".to_owned(),
+                        42,
+                        Gender::Male,
+                        vec!["100m".to_owned(), "Freestyle".to_owned()],
+                    ),
+                    UdaRegistrant::new(
+                        MemberId(2),
+                        "Jonette".to_owned(),
+                        "Snow".to_owned(),
+                        34,
+                        Gender::Female,
+                        vec!["Marathon".to_owned()],
+                    ),
+                    UdaRegistrant::new(
+                        MemberId(3),
+                        "Kris".to_owned(),
+                        "Holm".to_owned(),
+                        49,
+                        Gender::Male,
+                        vec![],
+                    ),
+                ],
+                registrants
+            );
+            assert_eq!(3, mock_server.received_requests().await.unwrap().len());
+        }
+
+        #[tokio::test]
+        async fn stop_when_no_next_page() {
+            let mock_server = MockServer::start().await;
+            mount_page(&mock_server, "1", LAST_PAGE).await;
+
+            let client = build_client().unwrap();
+            let registrants =
+                retrieve_registrants_html(&client, &mock_server.uri(), CompetitionId(12))
+                    .await
+                    .unwrap();
+
+            assert_eq!(1, registrants.len());
+            assert_eq!(1, mock_server.received_requests().await.unwrap().len());
+        }
+
+        #[tokio::test]
+        async fn stop_at_max_pages() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/en/competitions/12/registrants"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(FIRST_PAGE))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let registrants =
+                retrieve_registrants_html(&client, &mock_server.uri(), CompetitionId(12))
+                    .await
+                    .unwrap();
+
+            assert_eq!(200, registrants.len());
+        }
+
+        #[tokio::test]
+        async fn stop_when_cancelled() {
+            let mock_server = MockServer::start().await;
+            let cancellation_token = CancellationToken::new();
+            // Every page links to a next one, so the retrieval only stops when cancelled, here on the 3rd page
+            Mock::given(method("GET"))
+                .and(path("/en/competitions/12/registrants"))
+                .respond_with(CancelAfterRequests::new(
+                    ResponseTemplate::new(200).set_body_string(FIRST_PAGE),
+                    3,
+                    cancellation_token.clone(),
+                ))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let error = retrieve_registrants_html_with_cancellation(
+                &client,
+                &mock_server.uri(),
+                CompetitionId(12),
+                &cancellation_token,
+            )
+            .await
+            .unwrap_err();
+
+            assert!(matches!(error, UdaError::Cancelled));
+            assert_eq!(3, mock_server.received_requests().await.unwrap().len());
+        }
+    }
+}
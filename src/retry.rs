@@ -0,0 +1,313 @@
+use crate::rate_limit::{throttle, RateLimiter};
+use crate::tools::check_cancellation;
+use crate::Result;
+use chrono::{DateTime, Utc};
+use derive_getters::Getters;
+use log::warn;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{RequestBuilder, Response};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
+
+/// How to retry requests to UDA that failed because of a transient error,
+/// i.e. a network error or a 5xx status.
+/// Delays grow exponentially from `base_delay` up to `max_delay`.
+#[derive(Debug, Clone, PartialEq, Getters)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32, base_delay: Duration, max_delay: Duration, jitter: bool) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            base_delay,
+            max_delay,
+            jitter,
+        }
+    }
+
+    /// Policy trying each request only once.
+    pub fn no_retry() -> Self {
+        Self::new(1, Duration::ZERO, Duration::ZERO, false)
+    }
+
+    /// Delay to wait before the next attempt, knowing `attempt` attempts have already failed.
+    fn delay_before_retry(&self, attempt: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self
+            .base_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+
+        if self.jitter {
+            // Anywhere between half and the whole delay, so that concurrent clients don't retry in sync.
+            let random = RandomState::new().build_hasher().finish();
+            let ratio = 0.5 + (random % 1000) as f64 / 2000.0;
+            delay.mul_f64(ratio)
+        } else {
+            delay
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::no_retry()
+    }
+}
+
+/// Send the request built by `build_request`, sending it again while it fails transiently,
+/// as long as the policy allows it, waiting for the rate limiter, if any, before each attempt.
+pub(crate) async fn send_throttled_with_retry<F>(
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    build_request: F,
+) -> reqwest::Result<Response>
+where
+    F: Fn() -> RequestBuilder,
+{
+    send_cancellable_with_retry(retry_policy, rate_limiter, None, build_request)
+        .await
+        .unwrap_or_else(|_| unreachable!("Requests can't be cancelled without a token"))
+}
+
+/// Like [`send_throttled_with_retry`], checking the token, if any, once the rate limiter lets each attempt through,
+/// so that no request is sent once it has been cancelled.
+pub(crate) async fn send_cancellable_with_retry<F>(
+    retry_policy: &RetryPolicy,
+    rate_limiter: Option<&RateLimiter>,
+    cancellation_token: Option<&CancellationToken>,
+    build_request: F,
+) -> Result<reqwest::Result<Response>>
+where
+    F: Fn() -> RequestBuilder,
+{
+    let mut attempt = 1;
+    loop {
+        throttle(rate_limiter).await;
+        check_cancellation(cancellation_token)?;
+        let result = build_request().send().await;
+        let is_transient = match &result {
+            Ok(response) => response.status().is_server_error(),
+            Err(_) => true,
+        };
+        if !is_transient || attempt >= retry_policy.max_attempts {
+            return Ok(result);
+        }
+
+        let delay = retry_policy.delay_before_retry(attempt);
+        warn!("Transient failure when requesting UDA. Retrying. [attempt: {attempt}, delay: {delay:?}]");
+        sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// How long to wait before requesting again, according to the `Retry-After` header.
+/// The header holds either a number of seconds or an HTTP date.
+pub(crate) fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = date.with_timezone(&Utc) - Utc::now();
+    Some(delay.to_std().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod delay_before_retry {
+        use crate::retry::RetryPolicy;
+        use std::time::Duration;
+
+        #[test]
+        fn grow_exponentially() {
+            let policy = RetryPolicy::new(
+                5,
+                Duration::from_millis(100),
+                Duration::from_secs(10),
+                false,
+            );
+
+            assert_eq!(Duration::from_millis(100), policy.delay_before_retry(1));
+            assert_eq!(Duration::from_millis(200), policy.delay_before_retry(2));
+            assert_eq!(Duration::from_millis(400), policy.delay_before_retry(3));
+        }
+
+        #[test]
+        fn cap_to_max_delay() {
+            let policy = RetryPolicy::new(
+                50,
+                Duration::from_millis(100),
+                Duration::from_secs(1),
+                false,
+            );
+
+            assert_eq!(Duration::from_secs(1), policy.delay_before_retry(5));
+            assert_eq!(Duration::from_secs(1), policy.delay_before_retry(40));
+        }
+
+        #[test]
+        fn stay_within_bounds_with_jitter() {
+            let policy =
+                RetryPolicy::new(5, Duration::from_millis(100), Duration::from_secs(10), true);
+
+            let delay = policy.delay_before_retry(2);
+            assert!(delay >= Duration::from_millis(100));
+            assert!(delay <= Duration::from_millis(200));
+        }
+    }
+
+    mod parse_retry_after {
+        use crate::retry::parse_retry_after;
+        use chrono::{TimeDelta, Utc};
+        use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+        use std::time::Duration;
+
+        fn headers(retry_after: &str) -> HeaderMap {
+            let mut headers = HeaderMap::new();
+            headers.insert(RETRY_AFTER, HeaderValue::from_str(retry_after).unwrap());
+            headers
+        }
+
+        #[test]
+        fn parse_seconds() {
+            assert_eq!(
+                Some(Duration::from_secs(120)),
+                parse_retry_after(&headers("120"))
+            );
+        }
+
+        #[test]
+        fn parse_http_date() {
+            let date = Utc::now() + TimeDelta::seconds(300);
+            let retry_after = date.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+
+            let delay = parse_retry_after(&headers(&retry_after)).unwrap();
+            assert!(delay > Duration::from_secs(290));
+            assert!(delay <= Duration::from_secs(300));
+        }
+
+        #[test]
+        fn zero_when_http_date_is_past() {
+            assert_eq!(
+                Some(Duration::ZERO),
+                parse_retry_after(&headers("Wed, 21 Oct 2015 07:28:00 GMT"))
+            );
+        }
+
+        #[test]
+        fn none_when_missing_or_malformed() {
+            assert_eq!(None, parse_retry_after(&HeaderMap::new()));
+            assert_eq!(None, parse_retry_after(&headers("soon")));
+        }
+    }
+
+    mod send_throttled_with_retry {
+        use crate::retry::{send_throttled_with_retry, RetryPolicy};
+        use crate::tools::build_client;
+        use std::time::Duration;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        fn fast_policy() -> RetryPolicy {
+            RetryPolicy::new(
+                3,
+                Duration::from_millis(10),
+                Duration::from_millis(50),
+                false,
+            )
+        }
+
+        #[tokio::test]
+        async fn retry_until_success() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/resource"))
+                .respond_with(ResponseTemplate::new(503))
+                .up_to_n_times(2)
+                .with_priority(1)
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/resource"))
+                .respond_with(ResponseTemplate::new(200))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let url = format!("{}/resource", mock_server.uri());
+            let response = send_throttled_with_retry(&fast_policy(), None, || client.get(&url))
+                .await
+                .unwrap();
+
+            assert!(response.status().is_success());
+            assert_eq!(3, mock_server.received_requests().await.unwrap().len());
+        }
+
+        #[tokio::test]
+        async fn not_retry_when_client_error() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/resource"))
+                .respond_with(ResponseTemplate::new(401))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let url = format!("{}/resource", mock_server.uri());
+            let response = send_throttled_with_retry(&fast_policy(), None, || client.get(&url))
+                .await
+                .unwrap();
+
+            assert_eq!(401, response.status().as_u16());
+            assert_eq!(1, mock_server.received_requests().await.unwrap().len());
+        }
+
+        #[tokio::test]
+        async fn give_up_after_max_attempts() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/resource"))
+                .respond_with(ResponseTemplate::new(502))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let url = format!("{}/resource", mock_server.uri());
+            let response = send_throttled_with_retry(&fast_policy(), None, || client.get(&url))
+                .await
+                .unwrap();
+
+            assert_eq!(502, response.status().as_u16());
+            assert_eq!(3, mock_server.received_requests().await.unwrap().len());
+        }
+
+        #[tokio::test]
+        async fn not_retry_by_default() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/resource"))
+                .respond_with(ResponseTemplate::new(503))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let url = format!("{}/resource", mock_server.uri());
+            send_throttled_with_retry(&RetryPolicy::default(), None, || client.get(&url))
+                .await
+                .unwrap();
+
+            assert_eq!(1, mock_server.received_requests().await.unwrap().len());
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use rust_xlsxwriter::{Workbook, XlsxError};
+use uda_dto::uda_member::UdaMember;
+
+/// Headers of the members export, in the order UDA exports them.
+const HEADERS: [&str; 15] = [
+    "Id",
+    "Manual Organization Membership#",
+    "System Organization Membership#",
+    "First Name",
+    "Last Name",
+    "Birthday",
+    "Address Line1",
+    "City",
+    "State",
+    "Zip",
+    "Country",
+    "Phone",
+    "Email",
+    "Club",
+    "Confirmed already a member",
+];
+
+/// `UdaMember` being defined in `uda-dto`, `UdaMember::sample()` is provided by this trait,
+/// for downstream crates to test their own logic with.
+pub trait SampleMember {
+    /// The first of the [`sample_members`].
+    fn sample() -> Self;
+}
+
+impl SampleMember for UdaMember {
+    fn sample() -> Self {
+        sample_member(1)
+    }
+}
+
+/// Deterministic members, with ids from 1 to `count`.
+/// Every other member is confirmed and every third one has no club.
+/// Keep `count` under the max competitor id for all of them to be read back from an export.
+pub fn sample_members(count: u16) -> Vec<UdaMember> {
+    (1..=count).map(sample_member).collect()
+}
+
+fn sample_member(id: u16) -> UdaMember {
+    UdaMember::new(
+        id,
+        Some(format!("{id:06}")),
+        format!("First{id}"),
+        format!("Last{id}"),
+        format!("member{id}@example.com"),
+        (!id.is_multiple_of(3)).then(|| format!("Club {}", id % 3)),
+        id.is_multiple_of(2),
+    )
+}
+
+/// Members export listing the [`sample_members`], as UDA serves it.
+pub fn sample_members_xls_bytes(count: u16) -> Vec<u8> {
+    members_xls_bytes(&sample_members(count))
+}
+
+/// Members export listing the members, as UDA serves it.
+/// The fields UDA exports but `UdaMember` doesn't hold, e.g. the address, are filled with
+/// placeholders.
+pub fn members_xls_bytes(members: &[UdaMember]) -> Vec<u8> {
+    write_members_export(members).expect("Can't write members export")
+}
+
+fn write_members_export(members: &[UdaMember]) -> Result<Vec<u8>, XlsxError> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    for (column, header) in (0..).zip(HEADERS) {
+        worksheet.write_string(0, column, header)?;
+    }
+    for (row, member) in (1..).zip(members) {
+        worksheet.write_number(row, 0, *member.id())?;
+        if let Some(membership_number) = member.membership_number() {
+            worksheet.write_string(row, 1, membership_number)?;
+        }
+        worksheet.write_string(row, 3, member.first_name())?;
+        worksheet.write_string(row, 4, member.last_name())?;
+        worksheet.write_string(row, 5, "01.01.2000")?;
+        worksheet.write_string(row, 6, "1 Sample Street")?;
+        worksheet.write_string(row, 7, "Sample City")?;
+        worksheet.write_string(row, 9, "00000")?;
+        worksheet.write_string(row, 10, "France")?;
+        worksheet.write_string(row, 12, member.email())?;
+        if let Some(club) = member.club() {
+            worksheet.write_string(row, 13, club)?;
+        }
+        worksheet.write_boolean(row, 14, *member.confirmed())?;
+    }
+    workbook.save_to_buffer()
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod sample_members_xls_bytes {
+        use crate::retrieve_members::parse_members_export;
+        use crate::sample_data::{sample_members, sample_members_xls_bytes, SampleMember};
+        use uda_dto::uda_member::UdaMember;
+
+        #[test]
+        fn parse_back_into_sample_members() {
+            let export = sample_members_xls_bytes(50);
+
+            let members = parse_members_export(&export).unwrap();
+
+            assert_eq!(sample_members(50), members);
+        }
+
+        #[test]
+        fn parse_back_into_sample_member() {
+            let export = sample_members_xls_bytes(1);
+
+            let members = parse_members_export(&export).unwrap();
+
+            assert_eq!(vec![UdaMember::sample()], members);
+        }
+    }
+}
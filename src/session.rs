@@ -0,0 +1,1535 @@
+use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
+use crate::confirm_member::confirm_member_throttled;
+use crate::credentials::UdaCredentials;
+use crate::error::log_message_and_return;
+use crate::error::UdaError::{CookiePersistenceFailed, SessionExpired};
+use crate::ids::{CompetitionId, EventId, MemberId};
+use crate::locale::Locale;
+use crate::login::{
+    authenticate_into_uda_throttled, is_signed_in, sign_out_throttled, LoginConfig,
+};
+use crate::rate_limit::RateLimiter;
+use crate::retrieve_members::{
+    fetch_member_by_id, fetch_members, fetch_members_with_report, MemberFilter, MembersReport,
+};
+use crate::retrieve_registrants::{
+    fetch_registrants, fetch_registrants_for_event, get_registrants_pages,
+};
+use crate::retry::RetryPolicy;
+use crate::tools::{build_client_with_cookie_store, normalize_base_url, BasicAuth, ClientConfig};
+use crate::uda_registrant::UdaRegistrant;
+use crate::Result;
+use derive_getters::Getters;
+use futures_util::{stream, StreamExt};
+use log::{debug, error, warn};
+use reqwest::Client;
+use reqwest_cookie_store::{CookieStore, CookieStoreMutex};
+use std::fs::File;
+use std::future::Future;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use uda_dto::uda_member::UdaMember;
+
+/// Requests a session sends at once when retrieving several resources, unless told otherwise.
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 4;
+
+/// An authenticated connection to a UDA instance.
+/// Owning the client ensures protected endpoints are only called once logged in.
+///
+/// Keep the session around for repeated calls rather than logging in each time:
+/// its client pools connections, sparing a TCP and TLS handshake per request.
+/// Cloning it is cheap, clones sharing the same client, hence the same connections and cookies.
+#[derive(Debug, Clone, Getters)]
+pub struct UdaSession {
+    client: Client,
+    base_url: String,
+    login: String,
+    locale: Locale,
+    retry_policy: RetryPolicy,
+    member_filter: MemberFilter,
+    rate_limiter: Option<RateLimiter>,
+    circuit_breaker: Option<CircuitBreaker>,
+    /// Requests sent at once when retrieving several resources, e.g. the registrants of several competitions.
+    max_concurrent_requests: usize,
+    /// HTTP Basic Auth sent along the requests to the instance, if the deployment requires it.
+    #[getter(skip)]
+    basic_auth: Option<BasicAuth>,
+    /// Store of the cookies held by the client, kept to be able to save them.
+    #[getter(skip)]
+    cookie_store: Arc<CookieStoreMutex>,
+    /// Credentials to log into UDA again with when the session expires, if enabled.
+    #[getter(skip)]
+    reauth_credentials: Option<UdaCredentials>,
+}
+
+impl UdaSession {
+    /// Log into UDA with a new client and return the resulting session.
+    pub async fn authenticate(base_url: &str, login: &str, password: &str) -> Result<Self> {
+        Self::authenticate_with_locale(base_url, login, password, Locale::default()).await
+    }
+
+    /// Log into UDA with a new client, browsing pages in the given locale.
+    pub async fn authenticate_with_locale(
+        base_url: &str,
+        login: &str,
+        password: &str,
+        locale: Locale,
+    ) -> Result<Self> {
+        Self::authenticate_with_client_config(
+            base_url,
+            login,
+            password,
+            locale,
+            &ClientConfig::default(),
+            None,
+        )
+        .await
+    }
+
+    /// Log into UDA with a new client, using the HTTP Basic Auth of the credentials, if any,
+    /// on every request.
+    pub async fn authenticate_with_credentials(credentials: &UdaCredentials) -> Result<Self> {
+        Self::authenticate_with_client_config(
+            credentials.uda_url(),
+            credentials.login(),
+            credentials.password(),
+            Locale::default(),
+            &client_config(credentials),
+            None,
+        )
+        .await
+    }
+
+    /// Log into UDA like [`Self::authenticate_with_credentials`], the login counting against
+    /// the given rate limiter, which the session then keeps.
+    /// Meant for opening sessions on several instances hosted on the same server.
+    pub async fn authenticate_with_rate_limiter(
+        credentials: &UdaCredentials,
+        rate_limiter: RateLimiter,
+    ) -> Result<Self> {
+        Self::authenticate_with_client_config(
+            credentials.uda_url(),
+            credentials.login(),
+            credentials.password(),
+            Locale::default(),
+            &client_config(credentials),
+            Some(rate_limiter),
+        )
+        .await
+    }
+
+    /// Log into UDA with a new client built from the given settings, e.g. its timeout and HTTP Basic Auth,
+    /// the login counting against the rate limiter, if any, which the session then keeps.
+    async fn authenticate_with_client_config(
+        base_url: &str,
+        login: &str,
+        password: &str,
+        locale: Locale,
+        config: &ClientConfig,
+        rate_limiter: Option<RateLimiter>,
+    ) -> Result<Self> {
+        let base_url = normalize_base_url(base_url)?;
+        let cookie_store = Arc::new(CookieStoreMutex::default());
+        let client = build_client_with_cookie_store(cookie_store.clone(), config)?;
+        let basic_auth = config.basic_auth_for(&base_url)?;
+        authenticate_into_uda_throttled(
+            &client,
+            &base_url,
+            login,
+            password,
+            locale,
+            rate_limiter.as_ref(),
+            basic_auth.as_ref(),
+        )
+        .await?;
+
+        Ok(Self {
+            rate_limiter,
+            basic_auth,
+            ..Self::new(client, base_url, login, locale, cookie_store)
+        })
+    }
+
+    /// Resume the session whose cookies were saved with [`UdaSession::save_cookies`],
+    /// so that UDA isn't logged into on each run.
+    /// When the cookies can't be read or are rejected by UDA, logs into UDA again instead.
+    pub async fn from_saved_cookies(
+        base_url: &str,
+        login: &str,
+        password: &str,
+        path: impl AsRef<Path>,
+    ) -> Result<Self> {
+        let credentials = UdaCredentials::try_new(base_url, login.to_owned(), password.to_owned())?;
+        Self::from_saved_cookies_with_credentials(&credentials, Locale::default(), None, path).await
+    }
+
+    /// Resume the session whose cookies were saved, like [`Self::from_saved_cookies`],
+    /// browsing pages in the given locale and using the HTTP Basic Auth of the credentials, if any.
+    /// Checking the cookies, or logging in again, counts against the given rate limiter, if any,
+    /// which the session then keeps.
+    pub async fn from_saved_cookies_with_credentials(
+        credentials: &UdaCredentials,
+        locale: Locale,
+        rate_limiter: Option<RateLimiter>,
+        path: impl AsRef<Path>,
+    ) -> Result<Self> {
+        let base_url = normalize_base_url(credentials.uda_url())?;
+        let login = credentials.login();
+        let config = client_config(credentials);
+        if let Some(cookie_store) = load_cookie_store(path.as_ref()) {
+            let cookie_store = Arc::new(CookieStoreMutex::new(cookie_store));
+            let client = build_client_with_cookie_store(cookie_store.clone(), &config)?;
+            let basic_auth = config.basic_auth_for(&base_url)?;
+            if is_signed_in(
+                &client,
+                &base_url,
+                locale,
+                rate_limiter.as_ref(),
+                basic_auth.as_ref(),
+            )
+            .await?
+            {
+                debug!("Resumed UDA session from saved cookies [user: {login}]");
+                return Ok(Self {
+                    rate_limiter,
+                    basic_auth,
+                    ..Self::new(client, base_url, login, locale, cookie_store)
+                });
+            }
+            debug!("Saved cookies have been rejected by UDA. Logging in again. [user: {login}]");
+        }
+
+        Self::authenticate_with_client_config(
+            &base_url,
+            login,
+            credentials.password(),
+            locale,
+            &config,
+            rate_limiter,
+        )
+        .await
+    }
+
+    fn new(
+        client: Client,
+        base_url: String,
+        login: &str,
+        locale: Locale,
+        cookie_store: Arc<CookieStoreMutex>,
+    ) -> Self {
+        Self {
+            client,
+            base_url,
+            login: login.to_owned(),
+            locale,
+            retry_policy: RetryPolicy::default(),
+            member_filter: MemberFilter::default(),
+            rate_limiter: None,
+            circuit_breaker: None,
+            max_concurrent_requests: DEFAULT_MAX_CONCURRENT_REQUESTS,
+            basic_auth: None,
+            cookie_store,
+            reauth_credentials: None,
+        }
+    }
+
+    /// Save the cookies of the session to the file, so that it can be resumed
+    /// with [`UdaSession::from_saved_cookies`]. As they grant access to UDA, keep the file private.
+    pub fn save_cookies(&self, path: impl AsRef<Path>) -> Result<()> {
+        let file = File::create(path).map_err(log_message_and_return(
+            "Can't create cookies file",
+            |error| CookiePersistenceFailed(Arc::new(error)),
+        ))?;
+        let cookie_store = self
+            .cookie_store
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        // The session cookie of UDA expires with the browser session, it has to be saved anyway
+        cookie_store::serde::json::save_incl_expired_and_nonpersistent(
+            &cookie_store,
+            &mut BufWriter::new(file),
+        )
+        .map_err(log_message_and_return("Can't save cookies", |error| {
+            CookiePersistenceFailed(Arc::from(error))
+        }))
+    }
+
+    /// Retry requests of this session that fail transiently according to the given policy.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Keep only members accepted by the given filter when retrieving them.
+    pub fn with_member_filter(mut self, member_filter: MemberFilter) -> Self {
+        self.member_filter = member_filter;
+        self
+    }
+
+    /// Send no more than `requests_per_second` requests each second to UDA.
+    /// The limit is shared with the clones of this session.
+    pub fn with_rate_limit(self, requests_per_second: u32) -> Self {
+        self.with_rate_limiter(RateLimiter::new(requests_per_second))
+    }
+
+    /// Share the given rate limiter, e.g. with sessions on other instances hosted on the same server.
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Send at most `max_concurrent_requests` requests at once when retrieving several resources,
+    /// e.g. with [`Self::retrieve_registrants_bulk`]. At least one request is sent at a time.
+    pub fn with_max_concurrent_requests(mut self, max_concurrent_requests: usize) -> Self {
+        self.max_concurrent_requests = max_concurrent_requests.max(1);
+        self
+    }
+
+    /// Stop calling UDA for a while once it keeps failing to answer, failing with
+    /// [`crate::error::UdaError::CircuitOpen`] instead. The circuit is shared with the clones of this session.
+    pub fn with_circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker = Some(CircuitBreaker::new(config));
+        self
+    }
+
+    /// Log into UDA again with the given credentials when the session turns out to have expired,
+    /// then send the failed request once more.
+    pub fn with_auto_reauth(mut self, credentials: UdaCredentials) -> Self {
+        self.reauth_credentials = Some(credentials);
+        self
+    }
+
+    /// Retrieve members from UDA's organisation membership page.
+    pub async fn retrieve_members(&self) -> Result<Vec<UdaMember>> {
+        self.guard(self.reauth_when_expired(|| {
+            fetch_members(
+                &self.client,
+                &self.base_url,
+                self.locale,
+                &self.retry_policy,
+                self.rate_limiter.as_ref(),
+                self.basic_auth.as_ref(),
+                &self.member_filter,
+            )
+        }))
+        .await
+    }
+
+    /// Retrieve members from UDA's organisation membership page, along with the rows that couldn't be read.
+    pub async fn retrieve_members_with_report(&self) -> Result<MembersReport> {
+        self.guard(self.reauth_when_expired(|| {
+            fetch_members_with_report(
+                &self.client,
+                &self.base_url,
+                self.locale,
+                &self.retry_policy,
+                self.rate_limiter.as_ref(),
+                self.basic_auth.as_ref(),
+                &self.member_filter,
+                None,
+            )
+        }))
+        .await
+    }
+
+    /// Run the call through the circuit breaker, if enabled.
+    async fn guard<T>(&self, call: impl Future<Output = Result<T>>) -> Result<T> {
+        match &self.circuit_breaker {
+            Some(circuit_breaker) => circuit_breaker.call(call).await,
+            None => call.await,
+        }
+    }
+
+    /// Run the request, running it a second time after logging in again if the session has expired
+    /// and auto re-authentication is enabled. Fails with the original error when logging in fails.
+    async fn reauth_when_expired<T, F, Fut>(&self, request: F) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let result = request().await;
+        let credentials = match (&result, &self.reauth_credentials) {
+            (Err(SessionExpired), Some(credentials)) => credentials,
+            _ => return result,
+        };
+
+        warn!(
+            "UDA session has expired. Logging in again. [user: {}]",
+            self.login
+        );
+        if let Err(error) = authenticate_into_uda_throttled(
+            &self.client,
+            &self.base_url,
+            credentials.login(),
+            credentials.password(),
+            self.locale,
+            self.rate_limiter.as_ref(),
+            self.basic_auth.as_ref(),
+        )
+        .await
+        {
+            error!(
+                "Can't log into UDA again [user: {}, error: {error}]",
+                self.login
+            );
+            return result;
+        }
+
+        request().await
+    }
+
+    /// Retrieve the registrants of several competitions concurrently, e.g. for a series of events,
+    /// sending no more requests at once than allowed by [`Self::with_max_concurrent_requests`].
+    /// Each competition gets its own result, in the order of the ids, so that one failure doesn't abort the batch.
+    pub async fn retrieve_registrants_bulk(
+        &self,
+        competition_ids: &[CompetitionId],
+    ) -> Vec<(CompetitionId, Result<Vec<UdaRegistrant>>)> {
+        self.retrieve_registrants_of_competitions(competition_ids, None)
+            .await
+    }
+
+    /// Retrieve the registrants of several competitions concurrently, like [`Self::retrieve_registrants_bulk`],
+    /// until the token is cancelled, e.g. on shutdown.
+    /// The token is checked once the rate limiter lets each request through:
+    /// the competitions whose request hasn't been sent yet get a [`UdaError::Cancelled`] result.
+    ///
+    /// [`UdaError::Cancelled`]: crate::error::UdaError::Cancelled
+    pub async fn retrieve_registrants_bulk_with_cancellation(
+        &self,
+        competition_ids: &[CompetitionId],
+        cancellation_token: &CancellationToken,
+    ) -> Vec<(CompetitionId, Result<Vec<UdaRegistrant>>)> {
+        self.retrieve_registrants_of_competitions(competition_ids, Some(cancellation_token))
+            .await
+    }
+
+    async fn retrieve_registrants_of_competitions(
+        &self,
+        competition_ids: &[CompetitionId],
+        cancellation_token: Option<&CancellationToken>,
+    ) -> Vec<(CompetitionId, Result<Vec<UdaRegistrant>>)> {
+        let mut results: Vec<_> = stream::iter(competition_ids.iter().copied().enumerate())
+            .map(|(index, competition_id)| async move {
+                let registrants = self
+                    .guard(self.reauth_when_expired(|| {
+                        fetch_registrants(
+                            &self.client,
+                            &self.base_url,
+                            self.locale,
+                            competition_id,
+                            &self.retry_policy,
+                            self.rate_limiter.as_ref(),
+                            self.basic_auth.as_ref(),
+                            cancellation_token,
+                        )
+                    }))
+                    .await;
+                (index, competition_id, registrants)
+            })
+            .buffer_unordered(self.max_concurrent_requests)
+            .collect()
+            .await;
+        results.sort_by_key(|(index, _, _)| *index);
+        results
+            .into_iter()
+            .map(|(_, competition_id, registrants)| (competition_id, registrants))
+            .collect()
+    }
+
+    /// Retrieve the registrants of a competition from its paginated HTML list,
+    /// for instances that don't export them.
+    pub async fn retrieve_registrants_html(
+        &self,
+        competition_id: CompetitionId,
+    ) -> Result<Vec<UdaRegistrant>> {
+        self.guard(self.reauth_when_expired(|| {
+            get_registrants_pages(
+                &self.client,
+                &self.base_url,
+                self.locale,
+                competition_id,
+                &self.retry_policy,
+                self.rate_limiter.as_ref(),
+                self.basic_auth.as_ref(),
+                None,
+            )
+        }))
+        .await
+    }
+
+    /// Retrieve the registrants of a single event of a competition, e.g. to schedule its heats.
+    pub async fn retrieve_registrants_for_event(
+        &self,
+        competition_id: CompetitionId,
+        event_id: EventId,
+    ) -> Result<Vec<UdaRegistrant>> {
+        self.guard(self.reauth_when_expired(|| {
+            fetch_registrants_for_event(
+                &self.client,
+                &self.base_url,
+                self.locale,
+                competition_id,
+                event_id,
+                &self.retry_policy,
+                self.rate_limiter.as_ref(),
+                self.basic_auth.as_ref(),
+            )
+        }))
+        .await
+    }
+
+    /// Try and mark member as confirmed on UDA.
+    pub async fn confirm_member(&self, id: MemberId) -> Result<()> {
+        self.guard(self.reauth_when_expired(|| {
+            confirm_member_throttled(
+                &self.client,
+                &self.base_url,
+                self.locale,
+                id,
+                &self.retry_policy,
+                self.rate_limiter.as_ref(),
+                self.basic_auth.as_ref(),
+            )
+        }))
+        .await
+    }
+
+    /// Retrieve a single member, e.g. to check an update after a full import.
+    /// Returns `None` when UDA doesn't know the member.
+    /// See [`crate::retrieve_members::retrieve_member_by_id`].
+    pub async fn retrieve_member_by_id(&self, id: MemberId) -> Result<Option<UdaMember>> {
+        self.guard(self.reauth_when_expired(|| {
+            fetch_member_by_id(
+                &self.client,
+                &self.base_url,
+                self.locale,
+                id,
+                &self.retry_policy,
+                self.rate_limiter.as_ref(),
+                self.basic_auth.as_ref(),
+            )
+        }))
+        .await
+    }
+
+    /// Log out of UDA, ending the session.
+    pub async fn sign_out(self) -> Result<()> {
+        sign_out_throttled(
+            &self.client,
+            &self.base_url,
+            &LoginConfig::for_locale(self.locale),
+            self.rate_limiter.as_ref(),
+            self.basic_auth.as_ref(),
+        )
+        .await
+    }
+}
+
+/// Settings of the client of a session logging in with the credentials.
+fn client_config(credentials: &UdaCredentials) -> ClientConfig {
+    match credentials.basic_auth() {
+        Some((username, password)) => ClientConfig::default().with_basic_auth(username, password),
+        None => ClientConfig::default(),
+    }
+}
+
+fn load_cookie_store(path: &Path) -> Option<CookieStore> {
+    let file = File::open(path)
+        .inspect_err(|error| debug!("No saved cookies [path: {path:?}, error: {error}]"))
+        .ok()?;
+    cookie_store::serde::json::load(BufReader::new(file))
+        .inspect_err(|error| warn!("Can't load saved cookies [path: {path:?}, error: {error}]"))
+        .ok()
+}
+
+/// Log into UDA with a new client and retrieve members right away.
+/// Meant for one-off retrievals: keep a [`UdaSession`] to retrieve members repeatedly.
+pub async fn connect_and_retrieve_members(credentials: &UdaCredentials) -> Result<Vec<UdaMember>> {
+    let session = UdaSession::authenticate_with_credentials(credentials).await?;
+    session.retrieve_members().await
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod authenticate {
+        use crate::error::UdaError;
+        use crate::login::{setup_authentication, setup_authenticity_token};
+        use crate::session::UdaSession;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn success() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+
+            let session = UdaSession::authenticate(
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(credentials.uda_url(), session.base_url());
+            assert_eq!(credentials.login(), session.login());
+        }
+
+        #[tokio::test]
+        async fn fail_when_wrong_credentials() {
+            let mock_server = MockServer::start().await;
+            setup_authenticity_token(&mock_server).await;
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(
+                    "<html><body>Invalid User Account Email or password</body></html>",
+                ))
+                .mount(&mock_server)
+                .await;
+
+            let error = UdaSession::authenticate(&mock_server.uri(), "login", "wrong")
+                .await
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::WrongCredentials));
+        }
+    }
+
+    mod authenticate_with_credentials {
+        use crate::login::setup_authentication;
+        use crate::retrieve_members::setup_member_retrieval;
+        use crate::session::UdaSession;
+        use wiremock::http::Method;
+        use wiremock::MockServer;
+
+        #[tokio::test]
+        async fn send_basic_auth_header() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server)
+                .await
+                .with_basic_auth("user".to_owned(), "secret".to_owned());
+
+            UdaSession::authenticate_with_credentials(&credentials)
+                .await
+                .unwrap();
+
+            let requests = mock_server.received_requests().await.unwrap();
+            let sign_in_page_request = requests
+                .iter()
+                .find(|request| request.method == Method::GET)
+                .unwrap();
+            assert_eq!(
+                "Basic dXNlcjpzZWNyZXQ=",
+                sign_in_page_request.headers.get("Authorization").unwrap()
+            );
+        }
+
+        #[tokio::test]
+        async fn send_basic_auth_header_along_every_request() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server)
+                .await
+                .with_basic_auth("user".to_owned(), "secret".to_owned());
+            setup_member_retrieval(&mock_server).await;
+
+            let session = UdaSession::authenticate_with_credentials(&credentials)
+                .await
+                .unwrap();
+            session.retrieve_members().await.unwrap();
+
+            let requests = mock_server.received_requests().await.unwrap();
+            assert_eq!(3, requests.len());
+            for request in requests {
+                assert_eq!(
+                    "Basic dXNlcjpzZWNyZXQ=",
+                    request.headers.get("Authorization").unwrap()
+                );
+            }
+        }
+    }
+
+    mod retrieve_members {
+        use crate::login::setup_authentication;
+        use crate::retrieve_members::setup_member_retrieval;
+        use crate::session::UdaSession;
+        use wiremock::MockServer;
+
+        #[tokio::test]
+        async fn success() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            let expected_members = setup_member_retrieval(&mock_server).await;
+
+            let session = UdaSession::authenticate(
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap();
+            let members = session.retrieve_members().await.unwrap();
+
+            assert_eq!(expected_members, members);
+        }
+    }
+
+    mod reuse_connections {
+        use crate::login::setup_authentication;
+        use crate::retrieve_members::setup_member_retrieval;
+        use crate::session::UdaSession;
+        use std::net::SocketAddr;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use tokio::io::copy_bidirectional;
+        use tokio::net::{TcpListener, TcpStream};
+        use wiremock::MockServer;
+
+        /// Forward connections to the target, counting them, as the mock server can't tell them apart.
+        async fn start_counting_proxy(target: SocketAddr) -> (String, Arc<AtomicUsize>) {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+            let connections = Arc::new(AtomicUsize::new(0));
+
+            let counter = connections.clone();
+            tokio::spawn(async move {
+                while let Ok((mut inbound, _)) = listener.accept().await {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    tokio::spawn(async move {
+                        let mut outbound = TcpStream::connect(target).await.unwrap();
+                        let _ = copy_bidirectional(&mut inbound, &mut outbound).await;
+                    });
+                }
+            });
+
+            (format!("http://{address}"), connections)
+        }
+
+        async fn authenticate() -> (UdaSession, Arc<AtomicUsize>, MockServer) {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            setup_member_retrieval(&mock_server).await;
+            let (proxy_url, connections) = start_counting_proxy(*mock_server.address()).await;
+
+            let session =
+                UdaSession::authenticate(&proxy_url, credentials.login(), credentials.password())
+                    .await
+                    .unwrap();
+            (session, connections, mock_server)
+        }
+
+        #[tokio::test]
+        async fn keep_connection_alive_between_calls() {
+            let (session, connections, _mock_server) = authenticate().await;
+
+            session.retrieve_members().await.unwrap();
+            session.retrieve_members().await.unwrap();
+
+            assert_eq!(1, connections.load(Ordering::SeqCst));
+        }
+
+        #[tokio::test]
+        async fn share_connections_between_clones() {
+            let (session, connections, mock_server) = authenticate().await;
+
+            for _ in 0..100 {
+                session.clone().retrieve_members().await.unwrap();
+            }
+
+            assert_eq!(1, connections.load(Ordering::SeqCst));
+            // Both sign-in requests, then the exports
+            assert_eq!(102, mock_server.received_requests().await.unwrap().len());
+        }
+    }
+
+    mod connect_and_retrieve_members {
+        use crate::error::UdaError;
+        use crate::fake_uda::FakeUda;
+        use crate::session::connect_and_retrieve_members;
+
+        #[tokio::test]
+        async fn success() {
+            let fake_uda = FakeUda::start().await;
+
+            let members = connect_and_retrieve_members(fake_uda.credentials())
+                .await
+                .unwrap();
+
+            assert_eq!(fake_uda.expected_members(), members);
+        }
+
+        #[tokio::test]
+        async fn fail_when_wrong_credentials() {
+            let fake_uda = FakeUda::start().await.with_wrong_credentials().await;
+
+            let error = connect_and_retrieve_members(fake_uda.credentials())
+                .await
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::WrongCredentials));
+        }
+
+        #[tokio::test]
+        async fn fail_when_lack_of_permissions() {
+            let fake_uda = FakeUda::start().await.with_export_failure(401).await;
+
+            let error = connect_and_retrieve_members(fake_uda.credentials())
+                .await
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::LackOfPermissions));
+        }
+    }
+
+    mod with_auto_reauth {
+        use crate::credentials::UdaCredentials;
+        use crate::error::UdaError;
+        use crate::login::setup_authentication;
+        use crate::retrieve_members::{get_expected_member, get_test_file_content};
+        use crate::session::UdaSession;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        const SIGN_IN_FORM: &str = r#"<html><body><form action="/en/users/sign_in" method="post"><input type="password" name="user[password]"></form></body></html>"#;
+
+        async fn setup_expired_export(mock_server: &MockServer, times: u64) {
+            Mock::given(method("GET"))
+                .and(path("/en/organization_memberships/export.xls"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(SIGN_IN_FORM))
+                .up_to_n_times(times)
+                .with_priority(1)
+                .mount(mock_server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/en/organization_memberships/export.xls"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(get_test_file_content()))
+                .mount(mock_server)
+                .await;
+        }
+
+        async fn authenticate(credentials: &UdaCredentials) -> UdaSession {
+            UdaSession::authenticate(
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap()
+        }
+
+        #[tokio::test]
+        async fn success_after_logging_in_again() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            setup_expired_export(&mock_server, 1).await;
+
+            let session = authenticate(&credentials)
+                .await
+                .with_auto_reauth(credentials.clone());
+            let members = session.retrieve_members().await.unwrap();
+
+            assert_eq!(get_expected_member(), members);
+        }
+
+        #[tokio::test]
+        async fn fail_when_disabled() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            setup_expired_export(&mock_server, 1).await;
+
+            let session = authenticate(&credentials).await;
+            let error = session.retrieve_members().await.unwrap_err();
+
+            assert!(matches!(error, UdaError::SessionExpired));
+        }
+
+        #[tokio::test]
+        async fn retry_only_once() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            setup_expired_export(&mock_server, 2).await;
+
+            let session = authenticate(&credentials)
+                .await
+                .with_auto_reauth(credentials.clone());
+            let error = session.retrieve_members().await.unwrap_err();
+
+            assert!(matches!(error, UdaError::SessionExpired));
+            let export_requests = mock_server
+                .received_requests()
+                .await
+                .unwrap()
+                .into_iter()
+                .filter(|request| request.url.path() == "/en/organization_memberships/export.xls")
+                .count();
+            assert_eq!(2, export_requests);
+        }
+
+        #[tokio::test]
+        async fn fail_with_original_error_when_logging_in_again_fails() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            setup_expired_export(&mock_server, 1).await;
+            let wrong_credentials = UdaCredentials::new(
+                credentials.uda_url().to_owned(),
+                credentials.login().to_owned(),
+                "wrong".to_owned(),
+            );
+
+            let session = authenticate(&credentials)
+                .await
+                .with_auto_reauth(wrong_credentials);
+            let error = session.retrieve_members().await.unwrap_err();
+
+            assert!(matches!(error, UdaError::SessionExpired));
+        }
+    }
+
+    mod from_saved_cookies {
+        use crate::credentials::UdaCredentials;
+        use crate::locale::Locale;
+        use crate::rate_limit::RateLimiter;
+        use crate::retrieve_members::setup_member_retrieval;
+        use crate::session::UdaSession;
+        use std::path::PathBuf;
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        const SIGN_IN_FORM: &str = r#"<html><body><form action="/en/users/sign_in" method="post"><input name="authenticity_token" value="token"><input type="email" name="user[email]"><input type="password" name="user[password]"></form></body></html>"#;
+
+        /// UDA accepting only the session cookie `_uda_session=valid`, while setting `session_cookie` on login.
+        async fn setup_uda(mock_server: &MockServer, session_cookie: &str) {
+            Mock::given(method("GET"))
+                .and(path("/en/users/sign_in"))
+                .and(header("Cookie", "_uda_session=valid"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_string("<html><body>You are already signed in.</body></html>"),
+                )
+                .with_priority(1)
+                .mount(mock_server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(SIGN_IN_FORM))
+                .mount(mock_server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .append_header(
+                            "Set-Cookie",
+                            format!("_uda_session={session_cookie}; path=/"),
+                        )
+                        .set_body_string("Signed in successfully"),
+                )
+                .mount(mock_server)
+                .await;
+        }
+
+        fn cookies_path(name: &str) -> PathBuf {
+            std::env::temp_dir().join(format!("uda_connector_{name}_{}.json", std::process::id()))
+        }
+
+        async fn count_logins(mock_server: &MockServer) -> usize {
+            mock_server
+                .received_requests()
+                .await
+                .unwrap()
+                .iter()
+                .filter(|request| request.method.as_str() == "POST")
+                .count()
+        }
+
+        #[tokio::test]
+        async fn resume_session_from_saved_cookies() {
+            let mock_server = MockServer::start().await;
+            setup_uda(&mock_server, "valid").await;
+            let expected_members = setup_member_retrieval(&mock_server).await;
+            let path = cookies_path("resume");
+
+            let session = UdaSession::authenticate(&mock_server.uri(), "login", "password")
+                .await
+                .unwrap();
+            session.save_cookies(&path).unwrap();
+            let resumed_session =
+                UdaSession::from_saved_cookies(&mock_server.uri(), "login", "password", &path)
+                    .await
+                    .unwrap();
+            let members = resumed_session.retrieve_members().await.unwrap();
+            std::fs::remove_file(&path).unwrap();
+
+            assert_eq!(expected_members, members);
+            assert_eq!(1, count_logins(&mock_server).await);
+        }
+
+        #[tokio::test]
+        async fn keep_settings_of_credentials_when_resuming() {
+            let mock_server = MockServer::start().await;
+            setup_uda(&mock_server, "valid").await;
+            setup_member_retrieval(&mock_server).await;
+            let path = cookies_path("settings");
+            let credentials =
+                UdaCredentials::new(mock_server.uri(), "login".to_owned(), "password".to_owned())
+                    .with_basic_auth("user".to_owned(), "secret".to_owned());
+
+            let session = UdaSession::authenticate_with_credentials(&credentials)
+                .await
+                .unwrap();
+            session.save_cookies(&path).unwrap();
+            let resumed_session = UdaSession::from_saved_cookies_with_credentials(
+                &credentials,
+                Locale::En,
+                Some(RateLimiter::new(10)),
+                &path,
+            )
+            .await
+            .unwrap();
+            resumed_session.retrieve_members().await.unwrap();
+            std::fs::remove_file(&path).unwrap();
+
+            assert_eq!(1, count_logins(&mock_server).await);
+            assert!(resumed_session.rate_limiter().is_some());
+            assert_eq!(Locale::En, *resumed_session.locale());
+            let requests = mock_server.received_requests().await.unwrap();
+            for request in requests {
+                assert_eq!(
+                    "Basic dXNlcjpzZWNyZXQ=",
+                    request.headers.get("Authorization").unwrap()
+                );
+            }
+        }
+
+        #[tokio::test]
+        async fn log_in_when_cookies_are_rejected() {
+            let mock_server = MockServer::start().await;
+            setup_uda(&mock_server, "expired").await;
+            let path = cookies_path("rejected");
+
+            let session = UdaSession::authenticate(&mock_server.uri(), "login", "password")
+                .await
+                .unwrap();
+            session.save_cookies(&path).unwrap();
+            UdaSession::from_saved_cookies(&mock_server.uri(), "login", "password", &path)
+                .await
+                .unwrap();
+            std::fs::remove_file(&path).unwrap();
+
+            assert_eq!(2, count_logins(&mock_server).await);
+        }
+
+        #[tokio::test]
+        async fn log_in_when_no_saved_cookies() {
+            let mock_server = MockServer::start().await;
+            setup_uda(&mock_server, "valid").await;
+
+            UdaSession::from_saved_cookies(
+                &mock_server.uri(),
+                "login",
+                "password",
+                cookies_path("missing"),
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(1, count_logins(&mock_server).await);
+        }
+    }
+
+    mod with_rate_limit {
+        use crate::login::setup_authentication;
+        use crate::rate_limit::RateLimiter;
+        use crate::retrieve_members::setup_member_retrieval;
+        use crate::session::UdaSession;
+        use std::time::{Duration, Instant};
+        use wiremock::MockServer;
+
+        #[tokio::test]
+        async fn space_out_requests() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            setup_member_retrieval(&mock_server).await;
+
+            let session = UdaSession::authenticate(
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap()
+            .with_rate_limit(4);
+            let cloned_session = session.clone();
+
+            let start = Instant::now();
+            for _ in 0..3 {
+                session.retrieve_members().await.unwrap();
+                cloned_session.retrieve_members().await.unwrap();
+            }
+
+            // The first 4 requests are sent right away, the next ones every 250ms
+            assert!(start.elapsed() >= Duration::from_millis(500));
+        }
+
+        #[tokio::test]
+        async fn count_login_against_limit() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            let rate_limiter = RateLimiter::new(4);
+            let start = Instant::now();
+            for _ in 0..4 {
+                rate_limiter.acquire().await;
+            }
+
+            let session = UdaSession::authenticate_with_rate_limiter(&credentials, rate_limiter)
+                .await
+                .unwrap();
+
+            // Getting the sign-in form then sending it waits for a token each
+            assert!(start.elapsed() >= Duration::from_millis(500));
+            assert!(session.rate_limiter().is_some());
+        }
+    }
+
+    mod with_circuit_breaker {
+        use crate::circuit_breaker::CircuitBreakerConfig;
+        use crate::error::UdaError;
+        use crate::login::setup_authentication;
+        use crate::retrieve_members::setup_member_retrieval;
+        use crate::retry::RetryPolicy;
+        use crate::session::UdaSession;
+        use std::net::SocketAddr;
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+        use std::sync::{Arc, Mutex};
+        use std::time::Duration;
+        use tokio::io::copy_bidirectional;
+        use tokio::net::{TcpListener, TcpStream};
+        use tokio::task::AbortHandle;
+        use wiremock::MockServer;
+
+        /// Proxy to the target that can be broken, closing every connection as UDA going down would.
+        struct BreakableProxy {
+            url: String,
+            broken: Arc<AtomicBool>,
+            connections: Arc<AtomicUsize>,
+            forwarders: Arc<Mutex<Vec<AbortHandle>>>,
+        }
+
+        impl BreakableProxy {
+            async fn start(target: SocketAddr) -> Self {
+                let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+                let url = format!("http://{}", listener.local_addr().unwrap());
+                let broken = Arc::new(AtomicBool::new(false));
+                let connections = Arc::new(AtomicUsize::new(0));
+                let forwarders = Arc::new(Mutex::new(vec![]));
+
+                let (is_broken, counter, handles) =
+                    (broken.clone(), connections.clone(), forwarders.clone());
+                tokio::spawn(async move {
+                    while let Ok((mut inbound, _)) = listener.accept().await {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        if is_broken.load(Ordering::SeqCst) {
+                            continue;
+                        }
+                        let forwarder = tokio::spawn(async move {
+                            let mut outbound = TcpStream::connect(target).await.unwrap();
+                            let _ = copy_bidirectional(&mut inbound, &mut outbound).await;
+                        });
+                        handles.lock().unwrap().push(forwarder.abort_handle());
+                    }
+                });
+
+                Self {
+                    url,
+                    broken,
+                    connections,
+                    forwarders,
+                }
+            }
+
+            fn break_down(&self) {
+                self.broken.store(true, Ordering::SeqCst);
+                for forwarder in self.forwarders.lock().unwrap().drain(..) {
+                    forwarder.abort();
+                }
+            }
+        }
+
+        #[tokio::test]
+        async fn short_circuit_calls_once_uda_keeps_failing() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            setup_member_retrieval(&mock_server).await;
+            let proxy = BreakableProxy::start(*mock_server.address()).await;
+
+            let session =
+                UdaSession::authenticate(&proxy.url, credentials.login(), credentials.password())
+                    .await
+                    .unwrap()
+                    .with_retry_policy(RetryPolicy::no_retry())
+                    .with_circuit_breaker(CircuitBreakerConfig::new(
+                        2,
+                        Duration::from_secs(60),
+                        Duration::from_secs(60),
+                    ));
+            session.retrieve_members().await.unwrap();
+
+            proxy.break_down();
+            for _ in 0..2 {
+                let error = session.retrieve_members().await.unwrap_err();
+                assert!(matches!(
+                    error,
+                    UdaError::OrganizationMembershipsAccessFailed(_)
+                ));
+            }
+            let connections = proxy.connections.load(Ordering::SeqCst);
+
+            let error = session.clone().retrieve_members().await.unwrap_err();
+
+            assert!(matches!(error, UdaError::CircuitOpen { .. }));
+            // UDA isn't even called
+            assert_eq!(connections, proxy.connections.load(Ordering::SeqCst));
+        }
+    }
+
+    mod retrieve_registrants_bulk {
+        use crate::error::UdaError;
+        use crate::ids::CompetitionId;
+        use crate::login::setup_authentication;
+        use crate::retry::RetryPolicy;
+        use crate::session::UdaSession;
+        use crate::tools::tests::CancelAfterRequests;
+        use std::time::{Duration, Instant};
+        use tokio_util::sync::CancellationToken;
+        use wiremock::matchers::{method, path, path_regex};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn keep_results_of_each_competition() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            let content = std::fs::read("test/resources/uda_registrants.xlsx").unwrap();
+            Mock::given(method("GET"))
+                .and(path("/en/competitions/12/registrants.xls"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(content))
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/en/competitions/15/registrants.xls"))
+                .respond_with(ResponseTemplate::new(403))
+                .mount(&mock_server)
+                .await;
+
+            let session = UdaSession::authenticate(
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap()
+            .with_rate_limit(50);
+            let results = session
+                .retrieve_registrants_bulk(&[CompetitionId(12), CompetitionId(15)])
+                .await;
+
+            assert_eq!(2, results.len());
+            let (competition_id, registrants) = &results[0];
+            assert_eq!(CompetitionId(12), *competition_id);
+            assert_eq!(3, registrants.as_ref().unwrap().len());
+            let (competition_id, error) = &results[1];
+            assert_eq!(CompetitionId(15), *competition_id);
+            assert!(matches!(error, Err(UdaError::LackOfPermissions)));
+        }
+
+        #[tokio::test]
+        async fn send_no_more_requests_at_once_than_allowed() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            Mock::given(method("GET"))
+                .and(path_regex(r"^/en/competitions/\d+/registrants.xls$"))
+                .respond_with(ResponseTemplate::new(403).set_delay(Duration::from_millis(200)))
+                .expect(3)
+                .mount(&mock_server)
+                .await;
+
+            let session = UdaSession::authenticate(
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap()
+            .with_max_concurrent_requests(1);
+            let start = Instant::now();
+            let results = session
+                .retrieve_registrants_bulk(&[
+                    CompetitionId(12),
+                    CompetitionId(15),
+                    CompetitionId(18),
+                ])
+                .await;
+
+            // Each request waits for the previous one to be answered
+            assert!(start.elapsed() >= Duration::from_millis(600));
+            let competition_ids: Vec<_> = results.iter().map(|(id, _)| *id).collect();
+            assert_eq!(
+                vec![CompetitionId(12), CompetitionId(15), CompetitionId(18)],
+                competition_ids
+            );
+        }
+
+        #[tokio::test]
+        async fn retry_transient_failures() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            let content = std::fs::read("test/resources/uda_registrants.xlsx").unwrap();
+            Mock::given(method("GET"))
+                .and(path("/en/competitions/12/registrants.xls"))
+                .respond_with(ResponseTemplate::new(503))
+                .up_to_n_times(1)
+                .with_priority(1)
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/en/competitions/12/registrants.xls"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(content))
+                .mount(&mock_server)
+                .await;
+
+            let session = UdaSession::authenticate(
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap()
+            .with_retry_policy(RetryPolicy::new(
+                2,
+                Duration::from_millis(10),
+                Duration::from_millis(10),
+                false,
+            ));
+            let results = session
+                .retrieve_registrants_bulk(&[CompetitionId(12)])
+                .await;
+
+            assert_eq!(3, results[0].1.as_ref().unwrap().len());
+        }
+
+        #[tokio::test]
+        async fn log_in_again_when_session_expired() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            let content = std::fs::read("test/resources/uda_registrants.xlsx").unwrap();
+            Mock::given(method("GET"))
+                .and(path("/en/competitions/12/registrants.xls"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_string(r#"<form><input name="user[password]"></form>"#),
+                )
+                .up_to_n_times(1)
+                .with_priority(1)
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/en/competitions/12/registrants.xls"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(content))
+                .mount(&mock_server)
+                .await;
+
+            let session = UdaSession::authenticate(
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap()
+            .with_auto_reauth(credentials.clone());
+            let results = session
+                .retrieve_registrants_bulk(&[CompetitionId(12)])
+                .await;
+
+            assert_eq!(3, results[0].1.as_ref().unwrap().len());
+            let logins = mock_server
+                .received_requests()
+                .await
+                .unwrap()
+                .into_iter()
+                .filter(|request| request.method.as_str() == "POST")
+                .count();
+            assert_eq!(2, logins);
+        }
+
+        #[tokio::test]
+        async fn stop_when_cancelled() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            let content = std::fs::read("test/resources/uda_registrants.xlsx").unwrap();
+            let cancellation_token = CancellationToken::new();
+            // Cancelled when UDA receives the 5th export request, which is still answered
+            Mock::given(method("GET"))
+                .and(path_regex("^/en/competitions/[0-9]+/registrants.xls$"))
+                .respond_with(CancelAfterRequests::new(
+                    ResponseTemplate::new(200).set_body_bytes(content),
+                    5,
+                    cancellation_token.clone(),
+                ))
+                .mount(&mock_server)
+                .await;
+
+            // One request at a time, so that none is on its way when cancelling
+            let session = UdaSession::authenticate(
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap()
+            .with_max_concurrent_requests(1);
+            let results = session
+                .retrieve_registrants_bulk_with_cancellation(
+                    &(12..27).map(CompetitionId).collect::<Vec<_>>(),
+                    &cancellation_token,
+                )
+                .await;
+
+            assert!(results[..5].iter().all(|(_, result)| result.is_ok()));
+            assert!(results[5..]
+                .iter()
+                .all(|(_, result)| matches!(result, Err(UdaError::Cancelled))));
+            let export_requests = mock_server
+                .received_requests()
+                .await
+                .unwrap()
+                .into_iter()
+                .filter(|request| request.url.path().ends_with("registrants.xls"))
+                .count();
+            assert_eq!(5, export_requests);
+        }
+    }
+
+    mod confirm_member {
+        use crate::confirm_member::{setup_confirm_member, setup_csrf_token};
+        use crate::ids::MemberId;
+        use crate::login::setup_authentication;
+        use crate::retry::RetryPolicy;
+        use crate::session::UdaSession;
+        use std::time::Duration;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn success() {
+            let id = MemberId(10);
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            let csrf_token = setup_csrf_token(&mock_server).await;
+            setup_confirm_member(&mock_server, &csrf_token, id.0).await;
+
+            let session = UdaSession::authenticate(
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap();
+
+            session.confirm_member(id).await.unwrap();
+        }
+
+        #[tokio::test]
+        async fn retry_transient_failures() {
+            let id = MemberId(10);
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            Mock::given(method("GET"))
+                .and(path("/en/organization_memberships"))
+                .respond_with(ResponseTemplate::new(503))
+                .up_to_n_times(1)
+                .with_priority(1)
+                .mount(&mock_server)
+                .await;
+            let csrf_token = setup_csrf_token(&mock_server).await;
+            setup_confirm_member(&mock_server, &csrf_token, id.0).await;
+
+            let session = UdaSession::authenticate(
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap()
+            .with_retry_policy(RetryPolicy::new(
+                2,
+                Duration::from_millis(10),
+                Duration::from_millis(10),
+                false,
+            ));
+
+            session.confirm_member(id).await.unwrap();
+        }
+
+        #[tokio::test]
+        async fn log_in_again_when_session_expired() {
+            let id = MemberId(10);
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            Mock::given(method("GET"))
+                .and(path("/en/organization_memberships"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_string(r#"<form><input name="user[password]"></form>"#),
+                )
+                .up_to_n_times(1)
+                .with_priority(1)
+                .mount(&mock_server)
+                .await;
+            let csrf_token = setup_csrf_token(&mock_server).await;
+            setup_confirm_member(&mock_server, &csrf_token, id.0).await;
+
+            let session = UdaSession::authenticate(
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap()
+            .with_auto_reauth(credentials.clone());
+
+            session.confirm_member(id).await.unwrap();
+            let logins = mock_server
+                .received_requests()
+                .await
+                .unwrap()
+                .into_iter()
+                .filter(|request| request.method.as_str() == "POST")
+                .count();
+            assert_eq!(2, logins);
+        }
+    }
+
+    mod sign_out {
+        use crate::login::{setup_authentication, setup_sign_out};
+        use crate::session::UdaSession;
+        use wiremock::MockServer;
+
+        #[tokio::test]
+        async fn success() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            setup_sign_out(&mock_server).await;
+
+            let session = UdaSession::authenticate(
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap();
+
+            session.sign_out().await.unwrap();
+        }
+    }
+}
@@ -0,0 +1,595 @@
+use crate::error::UdaError::{
+    MalformedCsvFile, MalformedXlsFile, ProtectedWorkbook, WorksheetNotFound,
+};
+use crate::error::{log_error_and_return, log_message_and_return, UdaError};
+use crate::Result;
+use calamine::{
+    open_workbook_from_rs, Data, DeError, Range, RangeDeserializer, RangeDeserializerBuilder,
+    Reader, Sheets, Xlsx, XlsxError,
+};
+use derive_getters::Getters;
+use encoding_rs::WINDOWS_1252;
+use log::{debug, error, warn};
+use serde::de::DeserializeOwned;
+use std::io::Cursor;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const BOM: char = '\u{feff}';
+
+/// A row of a spreadsheet that couldn't be read.
+#[derive(Debug, Clone, PartialEq, Getters)]
+pub struct SkippedRow {
+    /// Index of the row in the worksheet, the header row being 0.
+    row_index: usize,
+    error: String,
+}
+
+impl SkippedRow {
+    pub fn new(row_index: usize, error: String) -> Self {
+        Self { row_index, error }
+    }
+}
+
+/// What an export contains, e.g. to diagnose why its rows can't be read.
+#[derive(Debug, Clone, PartialEq, Getters)]
+pub struct ExportInfo {
+    sheet_names: Vec<String>,
+    /// First row of the worksheet members are read from, i.e. the first non-empty one,
+    /// or `None` when every worksheet is empty.
+    header_row: Option<Vec<String>>,
+}
+
+/// List the worksheets of an export, along with the headers of the one members are read from,
+/// e.g. to spot a column whose name doesn't match the expected one.
+pub fn inspect_export(export: &[u8]) -> Result<ExportInfo> {
+    let mut workbook = open_workbook(Cursor::new(export))?;
+    let sheet_names = workbook.sheet_names();
+    let range = first_non_empty_range(&mut workbook)?;
+    let header_row = range
+        .rows()
+        .next()
+        .map(|row| row.iter().map(ToString::to_string).collect());
+
+    Ok(ExportInfo {
+        sheet_names,
+        header_row,
+    })
+}
+
+/// Rows whose columns may be exported under different headers depending on the UDA version.
+pub(crate) trait HeaderAliases {
+    /// Header the column is deserialized from, e.g. `Birthday` for `Date of birth`,
+    /// or `None` when the header is unknown and kept as is.
+    fn canonical_header(_header: &str) -> Option<&'static str> {
+        None
+    }
+}
+
+/// Rows read from a spreadsheet, along with the rows that couldn't be read.
+pub(crate) struct DeserializedRows<T> {
+    pub(crate) rows: Vec<T>,
+    pub(crate) skipped: Vec<SkippedRow>,
+}
+
+/// Read each row of the first non-empty worksheet as a `T`, using the first row as headers.
+/// Rows that can't be read are reported as skipped rather than failing the whole file.
+pub(crate) fn deserialize_first_sheet<T: DeserializeOwned + HeaderAliases, R: AsRef<[u8]>>(
+    cursor: Cursor<R>,
+) -> Result<DeserializedRows<T>> {
+    deserialize_sheet(cursor, None)
+}
+
+/// Read each row of the worksheet named `sheet_name` as a `T`, using the first row as headers.
+/// When no name is given, the first non-empty worksheet is read.
+pub(crate) fn deserialize_sheet<T: DeserializeOwned + HeaderAliases, R: AsRef<[u8]>>(
+    cursor: Cursor<R>,
+    sheet_name: Option<&str>,
+) -> Result<DeserializedRows<T>> {
+    let mut workbook = open_workbook(cursor)?;
+    let mut range = worksheet_range(&mut workbook, sheet_name)?;
+    map_headers::<T>(&mut range);
+    if range.is_empty() {
+        // A valid export without any member yet
+        debug!("Worksheet is empty, no row to read");
+        return Ok(DeserializedRows {
+            rows: vec![],
+            skipped: vec![],
+        });
+    }
+    let deserializer: RangeDeserializer<'_, Data, T> = RangeDeserializerBuilder::new()
+        .has_headers(true)
+        .from_range(&range)
+        .map_err(log_message_and_return(
+            "Can't read spreadsheet content",
+            |error: DeError| MalformedXlsFile(Some(Arc::new(error.into()))),
+        ))?;
+
+    let mut rows = vec![];
+    let mut skipped = vec![];
+    for (index, result) in deserializer.enumerate() {
+        match result {
+            Ok(row) => rows.push(row),
+            Err(error) => {
+                let row_index = index + 1; // The header row isn't deserialized
+                warn!("Can't deserialize row. Ignoring. [row: {row_index}] {error:?}");
+                skipped.push(SkippedRow::new(row_index, error.to_string()));
+            }
+        }
+    }
+
+    Ok(DeserializedRows { rows, skipped })
+}
+
+/// Read each record of a CSV file as a `T`, using the first record as headers.
+/// Like for spreadsheets, records that can't be read are reported as skipped.
+pub(crate) fn deserialize_csv<T: DeserializeOwned + HeaderAliases>(
+    content: &[u8],
+) -> Result<DeserializedRows<T>> {
+    // Some exports start with a byte order mark, which would end up in the first header
+    let content = content.strip_prefix(UTF8_BOM).unwrap_or(content);
+    let mut reader = csv::Reader::from_reader(content);
+    let headers = reader
+        .headers()
+        .map_err(log_message_and_return("Can't read CSV headers", |error| {
+            MalformedCsvFile(Arc::new(error))
+        }))?
+        .iter()
+        .map(canonical_header::<T>)
+        .collect();
+    reader.set_headers(headers);
+
+    let mut rows = vec![];
+    let mut skipped = vec![];
+    for (index, result) in reader.deserialize().enumerate() {
+        match result {
+            Ok(row) => rows.push(row),
+            Err(error) => {
+                let row_index = index + 1; // The header record isn't deserialized
+                warn!("Can't deserialize record. Ignoring. [row: {row_index}] {error:?}");
+                skipped.push(SkippedRow::new(row_index, error.to_string()));
+            }
+        }
+    }
+
+    Ok(DeserializedRows { rows, skipped })
+}
+
+/// Rows of a worksheet deserialized only when iterated over.
+/// Rows that can't be read are skipped.
+pub(crate) struct LazyRows<T> {
+    range: Range<Data>,
+    next_row_index: usize,
+    row_type: PhantomData<T>,
+}
+
+impl<T: DeserializeOwned> Iterator for LazyRows<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        while self.next_row_index < self.range.height() {
+            let row_index = self.next_row_index;
+            self.next_row_index += 1;
+            match self.deserialize_row(row_index) {
+                Ok(row) => return Some(row),
+                Err(error) => {
+                    warn!("Can't deserialize row. Ignoring. [row: {row_index}] {error:?}")
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl<T: DeserializeOwned> LazyRows<T> {
+    /// Deserialize the row alone, along with the header row mapping its cells to fields.
+    fn deserialize_row(&self, row_index: usize) -> std::result::Result<T, DeError> {
+        let width = self.range.width();
+        let mut row = Range::new((0, 0), (1, width as u32 - 1));
+        for column in 0..width {
+            for (index, source_index) in [(0, 0), (1, row_index)] {
+                if let Some(value) = self.range.get((source_index, column)) {
+                    row.set_value((index, column as u32), value.clone());
+                }
+            }
+        }
+
+        RangeDeserializerBuilder::new()
+            .has_headers(true)
+            .from_range::<_, T>(&row)?
+            .next()
+            .unwrap_or_else(|| Err(DeError::Custom("Row is missing".to_owned())))
+    }
+}
+
+/// Like [`deserialize_sheet`], but rows are deserialized one at a time while iterating.
+/// The worksheet is still decoded all at once, calamine being unable to read it partially.
+pub(crate) fn deserialize_sheet_lazily<T: DeserializeOwned + HeaderAliases, R: AsRef<[u8]>>(
+    cursor: Cursor<R>,
+    sheet_name: Option<&str>,
+) -> Result<LazyRows<T>> {
+    let mut workbook = open_workbook(cursor)?;
+    let mut range = worksheet_range(&mut workbook, sheet_name)?;
+    map_headers::<T>(&mut range);
+    if !range.is_empty() {
+        // Fail early when headers are missing, rather than on each row
+        RangeDeserializerBuilder::new()
+            .has_headers(true)
+            .from_range::<_, T>(&range)
+            .map_err(log_message_and_return(
+                "Can't read spreadsheet content",
+                |error: DeError| MalformedXlsFile(Some(Arc::new(error.into()))),
+            ))?;
+    }
+
+    Ok(LazyRows {
+        range,
+        next_row_index: 1, // The header row isn't deserialized
+        row_type: PhantomData,
+    })
+}
+
+/// Worksheet named `sheet_name`, or the first non-empty one, with its mis-decoded texts repaired
+/// and its trailing empty rows removed.
+fn worksheet_range<T: AsRef<[u8]>>(
+    workbook: &mut Sheets<Cursor<T>>,
+    sheet_name: Option<&str>,
+) -> Result<Range<Data>> {
+    let range = match sheet_name {
+        Some(sheet_name) => named_range(workbook, sheet_name)?,
+        None => first_non_empty_range(workbook)?,
+    };
+    let mut range = trim_trailing_empty_rows(range);
+    repair_encoding(&mut range);
+    Ok(range)
+}
+
+/// Some spreadsheet tools save blank rows after the last one, which would otherwise be reported as
+/// rows that can't be read.
+fn trim_trailing_empty_rows(range: Range<Data>) -> Range<Data> {
+    let (Some(start), Some(end)) = (range.start(), range.end()) else {
+        return range;
+    };
+    let Some(last_row) = range.rows().rposition(|row| !row.iter().all(is_blank)) else {
+        return range;
+    };
+    let last_row = start.0 + last_row as u32;
+    if last_row == end.0 {
+        return range;
+    }
+
+    debug!("Ignoring trailing empty rows [count: {}]", end.0 - last_row);
+    range.range(start, (last_row, end.1))
+}
+
+fn is_blank(cell: &Data) -> bool {
+    match cell {
+        Data::Empty => true,
+        Data::String(text) => text.trim().is_empty(),
+        _ => false,
+    }
+}
+
+/// Rename the headers of the first row to the ones `T` is deserialized from,
+/// so that columns renamed by some UDA versions are still read.
+fn map_headers<T: HeaderAliases>(range: &mut Range<Data>) {
+    let Some((start_row, start_column)) = range.start() else {
+        return;
+    };
+    let headers = range
+        .rows()
+        .next()
+        .map(|row| row.iter().map(ToString::to_string).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    for (column, header) in headers.iter().enumerate() {
+        let canonical_header = canonical_header::<T>(header);
+        if canonical_header != *header {
+            let position = (start_row, start_column + column as u32);
+            range.set_value(position, Data::String(canonical_header));
+        }
+    }
+}
+
+/// Header the column is to be deserialized from, the given one when it is unknown.
+/// A byte order mark some tools prefix the first header with is removed.
+fn canonical_header<T: HeaderAliases>(header: &str) -> String {
+    let header = header.strip_prefix(BOM).unwrap_or(header);
+    match T::canonical_header(header) {
+        Some(canonical_header) => {
+            debug!("Resolved field from column [field: {canonical_header}, header: {header}]");
+            canonical_header.to_owned()
+        }
+        None => header.to_owned(),
+    }
+}
+
+/// Some exports have their UTF-8 texts decoded as Windows-1252, e.g. `SÃ©tif` instead of `Sétif`:
+/// such texts are transcoded back.
+fn repair_encoding(range: &mut Range<Data>) {
+    let Some((start_row, start_column)) = range.start() else {
+        return;
+    };
+    let repaired_cells = range
+        .used_cells()
+        .filter_map(|(row, column, cell)| match cell {
+            Data::String(text) => repair_text(text).map(|repaired_text| {
+                let position = (start_row + row as u32, start_column + column as u32);
+                (position, Data::String(repaired_text))
+            }),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    for (position, cell) in repaired_cells {
+        range.set_value(position, cell);
+    }
+}
+
+/// The text transcoded back to UTF-8 if it has been decoded as Windows-1252, `None` if it is fine.
+fn repair_text(text: &str) -> Option<String> {
+    if text.is_ascii() {
+        return None;
+    }
+    if text.contains(char::REPLACEMENT_CHARACTER) {
+        warn!("Text has been decoded with the wrong encoding and can't be repaired [text: {text}]");
+        return None;
+    }
+
+    let (bytes, _, has_unmappable_characters) = WINDOWS_1252.encode(text);
+    if has_unmappable_characters {
+        return None;
+    }
+    // Genuine accented texts are almost never valid UTF-8 once encoded as Windows-1252
+    let repaired_text = String::from_utf8(bytes.into_owned()).ok()?;
+    debug!(
+        "Repaired text decoded with the wrong encoding [text: {text}, repaired: {repaired_text}]"
+    );
+    Some(repaired_text)
+}
+
+fn named_range<T: AsRef<[u8]>>(
+    workbook: &mut Sheets<Cursor<T>>,
+    sheet_name: &str,
+) -> Result<Range<Data>> {
+    if !workbook.sheet_names().iter().any(|name| name == sheet_name) {
+        error!("Worksheet not found [name: {sheet_name}]");
+        return Err(WorksheetNotFound(sheet_name.to_owned()));
+    }
+
+    workbook
+        .worksheet_range(sheet_name)
+        .map_err(log_message_and_return(
+            "Can't read spreadsheet content",
+            UdaError::from,
+        ))
+}
+
+/// Exports may start with blank worksheets, which are skipped.
+/// When every worksheet is blank, an empty range is returned.
+fn first_non_empty_range<T: AsRef<[u8]>>(workbook: &mut Sheets<Cursor<T>>) -> Result<Range<Data>> {
+    for worksheet_name in workbook.sheet_names() {
+        let range = workbook
+            .worksheet_range(&worksheet_name)
+            .map_err(log_message_and_return(
+                "Can't read spreadsheet content",
+                UdaError::from,
+            ))?;
+        if !range.is_empty() {
+            return Ok(range);
+        }
+        debug!("Ignoring empty worksheet [name: {worksheet_name}]");
+    }
+
+    debug!("No worksheet with content found");
+    Ok(Range::empty())
+}
+
+/// Spreadsheet formats UDA may export data as.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SpreadsheetFormat {
+    /// Legacy binary format (OLE2).
+    Xls,
+    /// OOXML format, i.e. a zip archive.
+    Xlsx,
+}
+
+const XLS_SIGNATURE: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+const XLSX_SIGNATURE: &[u8] = b"PK\x03\x04";
+
+/// Detect the format from the first bytes of the file, as UDA doesn't reliably set the content type.
+/// Unknown content is considered as XLS, the historical format.
+fn detect_spreadsheet_format(content: &[u8]) -> SpreadsheetFormat {
+    if content.starts_with(XLSX_SIGNATURE) {
+        SpreadsheetFormat::Xlsx
+    } else {
+        if !content.starts_with(XLS_SIGNATURE) {
+            warn!("Unknown spreadsheet signature. Trying to read it as XLS.");
+        }
+        SpreadsheetFormat::Xls
+    }
+}
+
+fn open_workbook<T: AsRef<[u8]>>(cursor: Cursor<T>) -> Result<Sheets<Cursor<T>>> {
+    let format = detect_spreadsheet_format(cursor.get_ref().as_ref());
+    if format == SpreadsheetFormat::Xls && is_encrypted_xlsx(cursor.get_ref().as_ref()) {
+        error!("Can't read spreadsheet content: the workbook is password protected");
+        return Err(ProtectedWorkbook);
+    }
+
+    let workbook = match format {
+        SpreadsheetFormat::Xls => open_workbook_from_rs(cursor)
+            .map(Sheets::Xls)
+            .map_err(calamine::Error::from),
+        SpreadsheetFormat::Xlsx => open_workbook_from_rs(cursor)
+            .map(Sheets::Xlsx)
+            .map_err(calamine::Error::from),
+    };
+    workbook.map_err(log_error_and_return(UdaError::from))
+}
+
+/// Encrypted XLSX workbooks are wrapped into an OLE2 container, hence detected as XLS ones.
+fn is_encrypted_xlsx(content: &[u8]) -> bool {
+    matches!(Xlsx::new(Cursor::new(content)), Err(XlsxError::Password))
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod detect_spreadsheet_format {
+        use crate::spreadsheet::{detect_spreadsheet_format, SpreadsheetFormat};
+
+        #[test]
+        fn detect_xls() {
+            let content = std::fs::read("test/resources/uda_members.xls").unwrap();
+            assert_eq!(SpreadsheetFormat::Xls, detect_spreadsheet_format(&content));
+        }
+
+        #[test]
+        fn detect_xlsx() {
+            let content = std::fs::read("test/resources/uda_members.xlsx").unwrap();
+            assert_eq!(SpreadsheetFormat::Xlsx, detect_spreadsheet_format(&content));
+        }
+
+        #[test]
+        fn fall_back_to_xls_when_unknown() {
+            assert_eq!(
+                SpreadsheetFormat::Xls,
+                detect_spreadsheet_format(b"unknown")
+            );
+        }
+    }
+
+    mod deserialize_first_sheet {
+        use crate::error::UdaError;
+        use crate::imported_uda_member::ImportedUdaMember;
+        use crate::spreadsheet::{deserialize_first_sheet, DeserializedRows, SkippedRow};
+        use std::io::Cursor;
+
+        fn retrieve_ids(path: &str) -> Vec<u16> {
+            let content = std::fs::read(path).unwrap();
+            let members: DeserializedRows<ImportedUdaMember> =
+                deserialize_first_sheet(Cursor::new(content)).unwrap();
+            members.rows.iter().map(|member| *member.id()).collect()
+        }
+
+        #[test]
+        fn success() {
+            assert_eq!(
+                vec![1, 2, 1999, 2000],
+                retrieve_ids("test/resources/uda_members.xls")
+            );
+        }
+
+        #[test]
+        fn skip_empty_worksheets() {
+            assert_eq!(
+                vec![1, 2, 1999, 2000],
+                retrieve_ids("test/resources/uda_members_with_empty_first_sheet.xlsx")
+            );
+        }
+
+        #[test]
+        fn ignore_bom_and_trailing_empty_rows() {
+            let content =
+                std::fs::read("test/resources/uda_members_with_trailing_empty_rows.xlsx").unwrap();
+            let members: DeserializedRows<ImportedUdaMember> =
+                deserialize_first_sheet(Cursor::new(content)).unwrap();
+
+            let ids: Vec<u16> = members.rows.iter().map(|member| *member.id()).collect();
+            assert_eq!(vec![1, 2, 1999, 2000], ids);
+            assert_eq!(Vec::<SkippedRow>::new(), members.skipped);
+        }
+
+        #[test]
+        fn repair_texts_decoded_with_wrong_encoding() {
+            let content = std::fs::read("test/resources/uda_members_mojibake.xlsx").unwrap();
+            let members: DeserializedRows<ImportedUdaMember> =
+                deserialize_first_sheet(Cursor::new(content)).unwrap();
+
+            let member = members
+                .rows
+                .iter()
+                .find(|member| *member.id() == 2)
+                .unwrap();
+            assert_eq!("Jérémie", member.first_name());
+            assert_eq!(Some("Sétif"), member.state().as_deref());
+            assert_eq!("1337, Là-bas", member.address_line());
+        }
+
+        #[test]
+        fn fail_when_malformed() {
+            let error = deserialize_first_sheet::<ImportedUdaMember, _>(Cursor::new(""))
+                .err()
+                .unwrap();
+            assert!(matches!(error, UdaError::MalformedXlsFile(_)));
+        }
+
+        #[test]
+        fn fail_when_password_protected() {
+            let content = std::fs::read("test/resources/uda_members_protected.xlsx").unwrap();
+            let error = deserialize_first_sheet::<ImportedUdaMember, _>(Cursor::new(content))
+                .err()
+                .unwrap();
+            assert!(matches!(error, UdaError::ProtectedWorkbook));
+        }
+    }
+
+    mod inspect_export {
+        use crate::error::UdaError;
+        use crate::spreadsheet::inspect_export;
+
+        #[test]
+        fn success() {
+            let content = std::fs::read("test/resources/uda_members.xls").unwrap();
+            let info = inspect_export(&content).unwrap();
+
+            assert_eq!(&vec!["Worksheet1".to_owned()], info.sheet_names());
+            let header_row = info.header_row().as_ref().unwrap();
+            assert_eq!(15, header_row.len());
+            assert_eq!("Id", header_row[0]);
+            assert_eq!("Birthday", header_row[5]);
+            assert_eq!("Confirmed already a member", header_row[14]);
+        }
+
+        #[test]
+        fn list_every_worksheet() {
+            let content =
+                std::fs::read("test/resources/uda_members_with_empty_first_sheet.xlsx").unwrap();
+            let info = inspect_export(&content).unwrap();
+
+            assert_eq!(2, info.sheet_names().len());
+            assert_eq!("Id", info.header_row().as_ref().unwrap()[0]);
+        }
+
+        #[test]
+        fn fail_when_malformed() {
+            let error = inspect_export(b"not a spreadsheet").unwrap_err();
+            assert!(matches!(error, UdaError::MalformedXlsFile(_)));
+        }
+    }
+
+    mod repair_text {
+        use crate::spreadsheet::repair_text;
+
+        #[test]
+        fn repair_windows_1252_mojibake() {
+            assert_eq!(Some("Jérémie".to_owned()), repair_text("JÃ©rÃ©mie"));
+            assert_eq!(Some("Zoë Åström".to_owned()), repair_text("ZoÃ« Ã…strÃ¶m"));
+        }
+
+        #[test]
+        fn keep_valid_texts() {
+            assert_eq!(None, repair_text("Jon Doe"));
+            assert_eq!(None, repair_text("Sétif"));
+            assert_eq!(None, repair_text("Là-bas"));
+            assert_eq!(None, repair_text("Łódź"));
+        }
+
+        #[test]
+        fn keep_unrecoverable_texts() {
+            assert_eq!(None, repair_text("J\u{FFFD}r\u{FFFD}mie"));
+        }
+    }
+}
@@ -1,13 +1,952 @@
+use crate::error::UdaError::{Cancelled, ConnectionFailed, InvalidUrl};
+use crate::error::{log_message_and_return, UdaError};
+use crate::Result;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use derive_getters::Getters;
+use log::{debug, error};
+use reqwest::cookie::CookieStore;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use reqwest::redirect::Policy;
+use reqwest::{Client, ClientBuilder, RequestBuilder};
+use reqwest_cookie_store::CookieStoreMutex;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use url::{Origin, Url};
+
+pub use reqwest::tls::Version as TlsVersion;
+
+/// Timeout applied by [`build_client`] to each request.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// User-Agent sent by the clients built here, unless told otherwise, e.g. `uda-connector/0.1.6`.
+pub const DEFAULT_USER_AGENT: &str = concat!("uda-connector/", env!("CARGO_PKG_VERSION"));
+
+/// Redirections followed by the client, as UDA redirects after signing in and out.
+pub(crate) const MAX_REDIRECTIONS: usize = 5;
+
+/// Settings of the clients built here, so that they can be combined,
+/// e.g. a shorter timeout along with the HTTP Basic Auth of a deployment.
+/// Defaults to the settings of [`build_client`].
+#[derive(Clone, PartialEq, Getters)]
+pub struct ClientConfig {
+    /// Both the connection timeout and the timeout of each request.
+    timeout: Duration,
+    user_agent: String,
+    /// Oldest TLS version negotiated, if enforced.
+    min_tls_version: Option<TlsVersion>,
+    danger_accept_invalid_certs: bool,
+    /// Username and password of the HTTP Basic Auth sent along the requests to the instance, if any.
+    #[getter(skip)]
+    basic_auth: Option<(String, String)>,
+}
+
+impl ClientConfig {
+    /// Give up on requests that last longer than `timeout`, so that a hung instance can't block forever.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Identify with the given User-Agent, e.g. to let UDA admins recognize an integration.
+    pub fn with_user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = user_agent.to_owned();
+        self
+    }
+
+    /// Refuse to negotiate a TLS version older than `min_version`, e.g. to enforce TLS 1.2+ in production.
+    /// With `danger_accept_invalid_certs`, any certificate is trusted, e.g. the self-signed one of a staging instance:
+    /// anyone able to intercept the connection could then steal the session, so never enable it in production.
+    pub fn with_tls(mut self, min_version: TlsVersion, danger_accept_invalid_certs: bool) -> Self {
+        self.min_tls_version = Some(min_version);
+        self.danger_accept_invalid_certs = danger_accept_invalid_certs;
+        self
+    }
+
+    /// Authenticate the requests a [`crate::session::UdaSession`] sends to its instance with HTTP Basic Auth,
+    /// as required by some deployments in front of UDA. It's never sent to another host, e.g. serving photos.
+    pub fn with_basic_auth(mut self, username: &str, password: &str) -> Self {
+        self.basic_auth = Some((username.to_owned(), password.to_owned()));
+        self
+    }
+
+    /// HTTP Basic Auth to send along the requests to the instance at `base_url`, if any.
+    pub(crate) fn basic_auth_for(&self, base_url: &str) -> Result<Option<BasicAuth>> {
+        self.basic_auth
+            .as_ref()
+            .map(|(username, password)| BasicAuth::new(base_url, username, password))
+            .transpose()
+    }
+
+    /// Apply the settings to the builder, on top of those it already has.
+    fn apply(&self, builder: ClientBuilder) -> Result<ClientBuilder> {
+        let mut builder = builder
+            .connect_timeout(self.timeout)
+            .timeout(self.timeout)
+            .user_agent(self.user_agent.as_str())
+            .danger_accept_invalid_certs(self.danger_accept_invalid_certs);
+        if let Some(min_tls_version) = self.min_tls_version {
+            builder = builder.min_tls_version(min_tls_version);
+        }
+        Ok(builder)
+    }
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_TIMEOUT,
+            user_agent: DEFAULT_USER_AGENT.to_owned(),
+            min_tls_version: None,
+            danger_accept_invalid_certs: false,
+            basic_auth: None,
+        }
+    }
+}
+
+/// The Basic Auth password is redacted, so that it can't leak into logs.
+impl Debug for ClientConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("ClientConfig");
+        debug
+            .field("timeout", &self.timeout)
+            .field("user_agent", &self.user_agent)
+            .field("min_tls_version", &self.min_tls_version)
+            .field(
+                "danger_accept_invalid_certs",
+                &self.danger_accept_invalid_certs,
+            );
+        if let Some((username, _)) = &self.basic_auth {
+            debug.field("basic_auth", &format!("{username}:***"));
+        }
+        debug.finish()
+    }
+}
+
+/// Build a client able to keep the session cookies UDA relies on.
+/// UDA identifies a logged-in user with the cookie set when signing in,
+/// so protected pages can only be reached by the client that authenticated, and only if it stores cookies:
+/// a bare `Client::new()` would be redirected to the sign-in page.
+/// Compressed responses are decompressed transparently, as some instances gzip their exports.
+/// Build it once and reuse it: each client has its own connection pool.
+pub fn build_client() -> Result<Client> {
+    build_client_with_config(&ClientConfig::default())
+}
+
+/// Build a client with the given settings, on top of the cookie store and redirection policy UDA requires.
+pub fn build_client_with_config(config: &ClientConfig) -> Result<Client> {
+    build_client_from_builder(config.apply(ClientBuilder::new())?)
+}
+
+/// Build a client giving up on requests that last longer than `timeout`, so that a hung instance can't block forever.
+pub fn build_client_with_timeout(timeout: Duration) -> Result<Client> {
+    build_client_with_config(&ClientConfig::default().with_timeout(timeout))
+}
+
+/// Build a client identifying itself with the given User-Agent, e.g. to let UDA admins recognize an integration.
+pub fn build_client_with_user_agent(user_agent: &str) -> Result<Client> {
+    build_client_with_config(&ClientConfig::default().with_user_agent(user_agent))
+}
+
+/// Build a client negotiating TLS as set by [`ClientConfig::with_tls`],
+/// e.g. to reach a test instance behind a self-signed certificate.
+pub fn build_client_with_tls(
+    min_version: TlsVersion,
+    danger_accept_invalid_certs: bool,
+) -> Result<Client> {
+    build_client_with_config(
+        &ClientConfig::default().with_tls(min_version, danger_accept_invalid_certs),
+    )
+}
+
+/// Build a client from the given builder, e.g. to set a proxy or a root certificate.
+/// The settings UDA requires are applied on top of it: the default cookie store is enabled,
+/// as being logged in relies on the session cookie, and redirections are followed within the instance.
+/// A cookie provider set on the builder would be replaced by the default store:
+/// give it to [`build_client_from_builder_with_cookie_provider`] instead.
+pub fn build_client_from_builder(builder: ClientBuilder) -> Result<Client> {
+    finish_client(builder.cookie_store(true))
+}
+
+/// Build a client from the given builder like [`build_client_from_builder`],
+/// keeping the session cookies into the given provider rather than the default store,
+/// e.g. to share them with another client.
+pub fn build_client_from_builder_with_cookie_provider<C: CookieStore + 'static>(
+    builder: ClientBuilder,
+    cookie_provider: Arc<C>,
+) -> Result<Client> {
+    finish_client(builder.cookie_provider(cookie_provider))
+}
+
+/// Build a client authenticating every request with HTTP Basic Auth, as required by some deployments in front of UDA.
+/// The credentials are sent to any host the client contacts:
+/// prefer a [`crate::session::UdaSession`], which only sends them to its instance.
+#[deprecated(
+    note = "Sends the Basic Auth to every host, use UdaSession::authenticate_with_credentials"
+)]
+pub fn build_client_with_basic_auth(username: &str, password: &str) -> Result<Client> {
+    build_client_from_builder(
+        ClientConfig::default()
+            .apply(ClientBuilder::new())?
+            .default_headers(basic_auth_headers(username, password)?),
+    )
+}
+
+/// Build the client, following redirections within the instance.
+fn finish_client(builder: ClientBuilder) -> Result<Client> {
+    builder
+        .redirect(redirect_policy(MAX_REDIRECTIONS))
+        .build()
+        .map_err(log_message_and_return(
+            "Can't build HTTP client",
+            UdaError::connection,
+        ))
+}
+
+/// Follow at most `max_redirections` redirections, and only within the host of the original request:
+/// being redirected elsewhere, e.g. to an SSO intercepting the sign-in, could leak credentials or loop.
+pub fn redirect_policy(max_redirections: usize) -> Policy {
+    Policy::custom(move |attempt| {
+        let redirections = attempt.previous().len();
+        let is_same_host = attempt
+            .previous()
+            .first()
+            .is_none_or(|original_url| original_url.host_str() == attempt.url().host_str());
+
+        if redirections > max_redirections {
+            attempt.error(format!("Too many redirections [max: {max_redirections}]"))
+        } else if !is_same_host {
+            let url = attempt.url().to_string();
+            attempt.error(format!("Redirection to another host rejected [url: {url}]"))
+        } else {
+            attempt.follow()
+        }
+    })
+}
+
+fn basic_auth_headers(username: &str, password: &str) -> Result<HeaderMap> {
+    let credentials = STANDARD.encode(format!("{username}:{password}"));
+    let mut value = HeaderValue::from_str(&format!("Basic {credentials}")).map_err(|_| {
+        error!("Can't build Basic Auth header [username: {username}]");
+        ConnectionFailed(None)
+    })?;
+    value.set_sensitive(true);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(AUTHORIZATION, value);
+    Ok(headers)
+}
+
+/// HTTP Basic Auth of a deployment in front of a UDA instance.
+/// It's only sent along the requests to the instance, so that it can't leak to another host.
+#[derive(Clone, PartialEq)]
+pub(crate) struct BasicAuth {
+    /// Scheme, host and port of the instance.
+    origin: Origin,
+    username: String,
+    password: String,
+}
+
+impl BasicAuth {
+    pub(crate) fn new(base_url: &str, username: &str, password: &str) -> Result<Self> {
+        let url = Url::parse(base_url.trim())
+            .map_err(log_message_and_return("Can't parse UDA URL", |_| {
+                InvalidUrl(base_url.to_owned())
+            }))?;
+        Ok(Self {
+            origin: url.origin(),
+            username: username.to_owned(),
+            password: password.to_owned(),
+        })
+    }
+
+    fn is_sent_to(&self, url: &str) -> bool {
+        Url::parse(url).is_ok_and(|url| url.origin() == self.origin)
+    }
+}
+
+/// The password is redacted, so that it can't leak into logs.
+impl Debug for BasicAuth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:***", self.username)
+    }
+}
+
+/// Authenticate the request to `url` with the HTTP Basic Auth, if any, provided it goes to the instance.
+pub(crate) fn authorize(
+    request: RequestBuilder,
+    url: &str,
+    basic_auth: Option<&BasicAuth>,
+) -> RequestBuilder {
+    match basic_auth {
+        Some(basic_auth) if basic_auth.is_sent_to(url) => {
+            request.basic_auth(&basic_auth.username, Some(&basic_auth.password))
+        }
+        _ => request,
+    }
+}
+
+/// Build a client storing its cookies into the given store, so that they can be saved and reloaded.
+pub(crate) fn build_client_with_cookie_store(
+    cookie_store: Arc<CookieStoreMutex>,
+    config: &ClientConfig,
+) -> Result<Client> {
+    build_client_from_builder_with_cookie_provider(
+        config.apply(ClientBuilder::new())?,
+        cookie_store,
+    )
+}
+
+/// Check the URL of a UDA instance is an HTTP(S) URL and remove its trailing slashes,
+/// so that paths can be appended to it.
+/// Internationalized domain names are punycode-encoded, e.g. `monocycle-été.example` becomes
+/// `xn--monocycle-t-kbbb.example`, as that's the host DNS knows.
+pub fn normalize_base_url(base_url: &str) -> Result<String> {
+    let url = Url::parse(base_url.trim())
+        .map_err(log_message_and_return("Can't parse UDA URL", |_| {
+            InvalidUrl(base_url.to_owned())
+        }))?;
+    if !matches!(url.scheme(), "http" | "https") || !url.has_host() {
+        error!("UDA URL should be an HTTP(S) URL [url: {base_url}]");
+        return Err(InvalidUrl(base_url.to_owned()));
+    }
+
+    Ok(url.as_str().trim_end_matches('/').to_owned())
+}
+
+/// URL of the page at `path` on the UDA instance, e.g. `en/competitions?page=2`.
+/// The instance may be served under a sub-path, e.g. behind a reverse proxy, which is kept
+/// whether or not the base URL ends with a slash.
+pub(crate) fn build_url(base_url: &str, path: &str) -> Result<String> {
+    let mut url = Url::parse(base_url.trim())
+        .map_err(log_message_and_return("Can't parse UDA URL", |_| {
+            InvalidUrl(base_url.to_owned())
+        }))?;
+    let base_path = format!("{}/", url.path().trim_end_matches('/'));
+    url.set_path(&base_path);
+    url.set_query(None);
+
+    let url = url
+        .join(path.trim_start_matches('/'))
+        .map_err(log_message_and_return("Can't build UDA URL", |_| {
+            InvalidUrl(format!("{base_url}/{path}"))
+        }))?;
+    Ok(url.into())
+}
+
+/// Fail with [`Cancelled`] once the token, if any, has been cancelled,
+/// e.g. between two requests of a batch.
+pub(crate) fn check_cancellation(cancellation_token: Option<&CancellationToken>) -> Result<()> {
+    match cancellation_token {
+        Some(cancellation_token) if cancellation_token.is_cancelled() => {
+            debug!("Operation cancelled, no further request is sent");
+            Err(Cancelled)
+        }
+        _ => Ok(()),
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
-    use crate::error::UdaError;
-    use crate::error::UdaError::ConnectionFailed;
-    use reqwest::Client;
-
-    pub fn build_client() -> Result<Client, UdaError> {
-        reqwest::ClientBuilder::new()
-            .cookie_store(true)
-            .build()
-            .map_err(|_| ConnectionFailed)
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio_util::sync::CancellationToken;
+    use wiremock::{Request, Respond, ResponseTemplate};
+
+    /// Answer with the template, cancelling the token when receiving the given number of requests,
+    /// so that tests cancel at a known point of a batch rather than after some time.
+    pub(crate) struct CancelAfterRequests {
+        template: ResponseTemplate,
+        requests: usize,
+        received: AtomicUsize,
+        cancellation_token: CancellationToken,
+    }
+
+    impl CancelAfterRequests {
+        pub(crate) fn new(
+            template: ResponseTemplate,
+            requests: usize,
+            cancellation_token: CancellationToken,
+        ) -> Self {
+            Self {
+                template,
+                requests,
+                received: AtomicUsize::new(0),
+                cancellation_token,
+            }
+        }
+    }
+
+    impl Respond for CancelAfterRequests {
+        fn respond(&self, _request: &Request) -> ResponseTemplate {
+            if self.received.fetch_add(1, Ordering::SeqCst) + 1 == self.requests {
+                self.cancellation_token.cancel();
+            }
+            self.template.clone()
+        }
+    }
+
+    mod build_client {
+        use crate::login::{authenticate_into_uda, setup_authenticity_token};
+        use crate::retrieve_members::{get_test_file_content, retrieve_members};
+        use crate::tools::build_client;
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn keep_session_cookie_between_requests() {
+            let mock_server = MockServer::start().await;
+            setup_authenticity_token(&mock_server).await;
+            Mock::given(method("POST"))
+                .and(path("/en/users/sign_in"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .insert_header("Set-Cookie", "_uda_session=session; path=/")
+                        .set_body_string("Signed in successfully"),
+                )
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/en/organization_memberships/export.xls"))
+                .and(header("Cookie", "_uda_session=session"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(get_test_file_content()))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            authenticate_into_uda(&client, &mock_server.uri(), "login", "password")
+                .await
+                .unwrap();
+            let members = retrieve_members(&client, &mock_server.uri()).await.unwrap();
+
+            assert_eq!(3, members.len());
+        }
+    }
+
+    mod redirect_policy {
+        use crate::tools::build_client;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        async fn setup_redirection(mock_server: &MockServer, from: &str, to: &str) {
+            Mock::given(method("GET"))
+                .and(path(from))
+                .respond_with(ResponseTemplate::new(302).insert_header("Location", to))
+                .mount(mock_server)
+                .await;
+        }
+
+        #[tokio::test]
+        async fn follow_redirections_within_host() {
+            let mock_server = MockServer::start().await;
+            setup_redirection(&mock_server, "/start", "/landing").await;
+            Mock::given(method("GET"))
+                .and(path("/landing"))
+                .respond_with(ResponseTemplate::new(200))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client().unwrap();
+            let response = client
+                .get(format!("{}/start", mock_server.uri()))
+                .send()
+                .await
+                .unwrap();
+
+            assert!(response.status().is_success());
+            assert_eq!(
+                format!("{}/landing", mock_server.uri()),
+                response.url().as_str()
+            );
+        }
+
+        #[tokio::test]
+        async fn reject_redirection_to_another_host() {
+            let mock_server = MockServer::start().await;
+            setup_redirection(&mock_server, "/start", "https://sso.example.com/login").await;
+
+            let client = build_client().unwrap();
+            let error = client
+                .get(format!("{}/start", mock_server.uri()))
+                .send()
+                .await
+                .unwrap_err();
+
+            assert!(error.is_redirect());
+        }
+
+        #[tokio::test]
+        async fn reject_redirection_loop() {
+            let mock_server = MockServer::start().await;
+            setup_redirection(&mock_server, "/loop", "/loop").await;
+
+            let client = build_client().unwrap();
+            let error = client
+                .get(format!("{}/loop", mock_server.uri()))
+                .send()
+                .await
+                .unwrap_err();
+
+            assert!(error.is_redirect());
+            assert_eq!(6, mock_server.received_requests().await.unwrap().len());
+        }
+    }
+
+    mod build_client_with_timeout {
+        use crate::error::UdaError;
+        use crate::instances::retrieve_uda_instances;
+        use crate::tools::build_client_with_timeout;
+        use std::error::Error;
+        use std::time::Duration;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn fail_when_server_hangs() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("tenants"))
+                .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(10)))
+                .mount(&mock_server)
+                .await;
+
+            let client = build_client_with_timeout(Duration::from_millis(100)).unwrap();
+            let instances_list_url = format!("{}/tenants?locale=en", mock_server.uri());
+            let error = retrieve_uda_instances(&client, &instances_list_url)
+                .await
+                .unwrap_err();
+
+            assert!(
+                matches!(error, UdaError::ConnectionFailed(Some(ref source)) if source.is_timeout())
+            );
+            assert!(error.source().is_some());
+        }
+    }
+
+    mod build_client_with_tls {
+        use crate::tools::{build_client_with_tls, TlsVersion};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpListener;
+        use tokio_native_tls::native_tls::{Identity, TlsAcceptor};
+
+        /// Start an HTTPS server answering every request with an empty page,
+        /// using a self-signed certificate for `localhost`.
+        async fn start_self_signed_server() -> String {
+            let certificate = std::fs::read("test/resources/self_signed.crt").unwrap();
+            let key = std::fs::read("test/resources/self_signed.key").unwrap();
+            let identity = Identity::from_pkcs8(&certificate, &key).unwrap();
+            let acceptor = tokio_native_tls::TlsAcceptor::from(TlsAcceptor::new(identity).unwrap());
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+
+            tokio::spawn(async move {
+                loop {
+                    let (stream, _) = listener.accept().await.unwrap();
+                    let acceptor = acceptor.clone();
+                    tokio::spawn(async move {
+                        // The handshake fails when the client rejects the certificate
+                        let Ok(mut stream) = acceptor.accept(stream).await else {
+                            return;
+                        };
+                        let mut request = vec![0; 4096];
+                        let _ = stream.read(&mut request).await;
+                        let _ = stream
+                            .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                            .await;
+                        let _ = stream.shutdown().await;
+                    });
+                }
+            });
+
+            format!("https://localhost:{port}")
+        }
+
+        #[tokio::test]
+        async fn accept_self_signed_certificate_when_told_to() {
+            let url = start_self_signed_server().await;
+
+            let client = build_client_with_tls(TlsVersion::TLS_1_2, true).unwrap();
+            let response = client.get(url).send().await.unwrap();
+
+            assert!(response.status().is_success());
+        }
+
+        #[tokio::test]
+        async fn reject_self_signed_certificate_by_default() {
+            let url = start_self_signed_server().await;
+
+            let client = build_client_with_tls(TlsVersion::TLS_1_2, false).unwrap();
+            let error = client.get(url).send().await.unwrap_err();
+
+            assert!(error.is_connect());
+        }
+    }
+
+    mod normalize_base_url {
+        use crate::error::UdaError;
+        use crate::login::{authenticate_into_uda, setup_authentication};
+        use crate::tools::{build_client_from_builder, normalize_base_url};
+        use reqwest::ClientBuilder;
+        use wiremock::MockServer;
+
+        #[test]
+        fn keep_valid_url() {
+            assert_eq!(
+                "https://cfm2025.reg.unicycling-software.com",
+                normalize_base_url("https://cfm2025.reg.unicycling-software.com").unwrap()
+            );
+        }
+
+        #[test]
+        fn remove_trailing_slash() {
+            assert_eq!(
+                "https://cfm2025.reg.unicycling-software.com",
+                normalize_base_url("https://cfm2025.reg.unicycling-software.com/").unwrap()
+            );
+        }
+
+        #[test]
+        fn encode_internationalized_domain_name() {
+            assert_eq!(
+                "https://xn--monocycle-t-kbbb.example/uda",
+                normalize_base_url("https://monocycle-été.example/uda/").unwrap()
+            );
+        }
+
+        #[tokio::test]
+        async fn request_punycode_host() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            let port = mock_server.address().port();
+            // Only the punycode host resolves, to the mock server
+            let client = build_client_from_builder(
+                ClientBuilder::new()
+                    .no_proxy()
+                    .resolve("xn--monocycle-t-kbbb.example", *mock_server.address()),
+            )
+            .unwrap();
+
+            let base_url =
+                normalize_base_url(&format!("http://monocycle-été.example:{port}")).unwrap();
+            authenticate_into_uda(
+                &client,
+                &base_url,
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap();
+
+            let requests = mock_server.received_requests().await.unwrap();
+            assert!(!requests.is_empty());
+            for request in requests {
+                assert_eq!(
+                    format!("xn--monocycle-t-kbbb.example:{port}"),
+                    request.headers.get("host").unwrap().to_str().unwrap()
+                );
+            }
+        }
+
+        #[test]
+        fn fail_when_missing_scheme() {
+            let error = normalize_base_url("cfm2025.reg.unicycling-software.com").unwrap_err();
+            assert!(matches!(error, UdaError::InvalidUrl(_)));
+        }
+
+        #[test]
+        fn fail_when_not_http() {
+            let error =
+                normalize_base_url("ftp://cfm2025.reg.unicycling-software.com").unwrap_err();
+            assert!(matches!(error, UdaError::InvalidUrl(_)));
+        }
+    }
+
+    mod build_url {
+        use crate::error::UdaError;
+        use crate::tools::build_url;
+
+        #[test]
+        fn join_with_or_without_trailing_slash() {
+            for base_url in [
+                "https://cfm2025.reg.unicycling-software.com",
+                "https://cfm2025.reg.unicycling-software.com/",
+            ] {
+                assert_eq!(
+                    "https://cfm2025.reg.unicycling-software.com/en/dashboard",
+                    build_url(base_url, "en/dashboard").unwrap(),
+                    "{base_url}"
+                );
+            }
+        }
+
+        #[test]
+        fn keep_sub_path() {
+            for base_url in ["https://example.com/uda", "https://example.com/uda//"] {
+                assert_eq!(
+                    "https://example.com/uda/en/dashboard",
+                    build_url(base_url, "/en/dashboard").unwrap(),
+                    "{base_url}"
+                );
+            }
+        }
+
+        #[test]
+        fn keep_query_parameters() {
+            assert_eq!(
+                "https://example.com/uda/en/competitions/3/registrants?page=2",
+                build_url(
+                    "https://example.com/uda/",
+                    "en/competitions/3/registrants?page=2"
+                )
+                .unwrap()
+            );
+        }
+
+        #[test]
+        fn ignore_base_url_query() {
+            assert_eq!(
+                "https://example.com/en/dashboard",
+                build_url("https://example.com/?locale=fr", "en/dashboard").unwrap()
+            );
+        }
+
+        #[test]
+        fn fail_when_invalid_base_url() {
+            let error = build_url("not a url", "en/dashboard").unwrap_err();
+            assert!(matches!(error, UdaError::InvalidUrl(_)));
+        }
+    }
+
+    mod build_client_from_builder {
+        use crate::login::{authenticate_into_uda, setup_authentication};
+        use crate::tools::{
+            build_client_from_builder, build_client_from_builder_with_cookie_provider,
+        };
+        use reqwest::cookie::{CookieStore, Jar};
+        use reqwest::{ClientBuilder, Url};
+        use std::sync::Arc;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn success_to_authenticate() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+
+            let client = build_client_from_builder(ClientBuilder::new().no_proxy()).unwrap();
+            authenticate_into_uda(
+                &client,
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap();
+        }
+
+        #[tokio::test]
+        async fn keep_cookies_into_given_provider() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/en/dashboard"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .insert_header("Set-Cookie", "_uda_session=session; path=/"),
+                )
+                .mount(&mock_server)
+                .await;
+            let jar = Arc::new(Jar::default());
+
+            let client =
+                build_client_from_builder_with_cookie_provider(ClientBuilder::new(), jar.clone())
+                    .unwrap();
+            client
+                .get(format!("{}/en/dashboard", mock_server.uri()))
+                .send()
+                .await
+                .unwrap();
+
+            let url = Url::parse(&mock_server.uri()).unwrap();
+            let cookies = jar.cookies(&url).unwrap();
+            assert_eq!("_uda_session=session", cookies.to_str().unwrap());
+        }
+    }
+
+    mod build_client_with_user_agent {
+        use crate::login::{authenticate_into_uda, setup_authentication};
+        use crate::tools::{build_client, build_client_with_user_agent, DEFAULT_USER_AGENT};
+        use reqwest::Client;
+        use wiremock::MockServer;
+
+        /// User-Agent of the request for the sign-in page, sent when authenticating with the client.
+        async fn get_sign_in_user_agent(client: &Client) -> String {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+
+            authenticate_into_uda(
+                client,
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap();
+
+            let requests = mock_server.received_requests().await.unwrap();
+            let sign_in_page_request = requests
+                .iter()
+                .find(|request| request.method.as_str() == "GET")
+                .unwrap();
+            sign_in_page_request.headers["User-Agent"]
+                .to_str()
+                .unwrap()
+                .to_owned()
+        }
+
+        #[tokio::test]
+        async fn send_custom_user_agent() {
+            let client = build_client_with_user_agent("my-integration/1.0").unwrap();
+
+            assert_eq!("my-integration/1.0", get_sign_in_user_agent(&client).await);
+        }
+
+        #[tokio::test]
+        async fn send_default_user_agent() {
+            let client = build_client().unwrap();
+
+            let user_agent = get_sign_in_user_agent(&client).await;
+            assert_eq!(DEFAULT_USER_AGENT, user_agent);
+            assert!(user_agent.starts_with("uda-connector/"));
+        }
+    }
+
+    #[allow(deprecated)]
+    mod build_client_with_basic_auth {
+        use crate::login::{authenticate_into_uda, setup_authentication};
+        use crate::tools::build_client_with_basic_auth;
+        use wiremock::MockServer;
+
+        #[tokio::test]
+        async fn send_basic_auth_header() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+
+            let client = build_client_with_basic_auth("user", "secret").unwrap();
+            authenticate_into_uda(
+                &client,
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap();
+
+            let requests = mock_server.received_requests().await.unwrap();
+            assert_eq!(2, requests.len());
+            for request in requests {
+                assert_eq!(
+                    "Basic dXNlcjpzZWNyZXQ=",
+                    request.headers.get("Authorization").unwrap()
+                );
+            }
+        }
+    }
+
+    mod build_client_with_config {
+        use crate::login::{authenticate_into_uda, setup_authentication};
+        use crate::tools::{build_client_with_config, ClientConfig};
+        use std::time::Duration;
+        use wiremock::MockServer;
+
+        #[tokio::test]
+        async fn combine_settings() {
+            let mock_server = MockServer::start().await;
+            let credentials = setup_authentication(&mock_server).await;
+            let config = ClientConfig::default()
+                .with_timeout(Duration::from_secs(5))
+                .with_user_agent("my-integration/1.0")
+                .with_basic_auth("user", "secret");
+
+            let client = build_client_with_config(&config).unwrap();
+            authenticate_into_uda(
+                &client,
+                credentials.uda_url(),
+                credentials.login(),
+                credentials.password(),
+            )
+            .await
+            .unwrap();
+
+            let requests = mock_server.received_requests().await.unwrap();
+            assert_eq!(2, requests.len());
+            for request in requests {
+                assert_eq!("my-integration/1.0", request.headers["User-Agent"]);
+                // Only a session sends the Basic Auth, and to its instance only
+                assert!(!request.headers.contains_key("Authorization"));
+            }
+        }
+
+        #[test]
+        fn redact_basic_auth_password() {
+            let config = ClientConfig::default().with_basic_auth("user", "secret");
+
+            let debug = format!("{config:?}");
+
+            assert!(debug.contains("user:***"));
+            assert!(!debug.contains("secret"));
+        }
+    }
+
+    mod authorize {
+        use crate::tools::{authorize, BasicAuth};
+        use reqwest::Client;
+
+        /// Authorization header of the request to `url`, if any.
+        fn get_authorization(url: &str, basic_auth: Option<&BasicAuth>) -> Option<String> {
+            let request = authorize(Client::new().get(url), url, basic_auth)
+                .build()
+                .unwrap();
+            request
+                .headers()
+                .get("Authorization")
+                .map(|value| value.to_str().unwrap().to_owned())
+        }
+
+        #[test]
+        fn send_basic_auth_to_instance() {
+            let basic_auth = BasicAuth::new("https://uda.test", "user", "secret").unwrap();
+
+            let authorization =
+                get_authorization("https://uda.test/en/users/sign_in", Some(&basic_auth));
+
+            assert_eq!(Some("Basic dXNlcjpzZWNyZXQ=".to_owned()), authorization);
+        }
+
+        #[test]
+        fn not_send_basic_auth_to_another_host() {
+            let basic_auth = BasicAuth::new("https://uda.test", "user", "secret").unwrap();
+
+            assert_eq!(
+                None,
+                get_authorization("https://photos.test/member.jpg", Some(&basic_auth))
+            );
+            assert_eq!(
+                None,
+                get_authorization("http://uda.test/en/users/sign_in", Some(&basic_auth))
+            );
+            assert_eq!(
+                None,
+                get_authorization("https://uda.test:8443/en", Some(&basic_auth))
+            );
+        }
+
+        #[test]
+        fn not_send_anything_without_basic_auth() {
+            assert_eq!(
+                None,
+                get_authorization("https://uda.test/en/users/sign_in", None)
+            );
+        }
     }
 }
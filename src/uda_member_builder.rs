@@ -0,0 +1,141 @@
+use crate::error::UdaError::MissingMemberField;
+use crate::Result;
+use log::error;
+use uda_dto::uda_member::UdaMember;
+
+/// Build a [`UdaMember`] field by field, rather than through positional arguments.
+/// Optional fields default to `None` and the member to not confirmed.
+#[derive(Debug, Clone, Default)]
+pub struct UdaMemberBuilder {
+    id: Option<u16>,
+    membership_number: Option<String>,
+    first_name: Option<String>,
+    last_name: Option<String>,
+    email: Option<String>,
+    club: Option<String>,
+    confirmed: bool,
+}
+
+impl UdaMemberBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn id(mut self, id: u16) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    pub fn membership_number(mut self, membership_number: impl Into<String>) -> Self {
+        self.membership_number = Some(membership_number.into());
+        self
+    }
+
+    pub fn first_name(mut self, first_name: impl Into<String>) -> Self {
+        self.first_name = Some(first_name.into());
+        self
+    }
+
+    pub fn last_name(mut self, last_name: impl Into<String>) -> Self {
+        self.last_name = Some(last_name.into());
+        self
+    }
+
+    pub fn email(mut self, email: impl Into<String>) -> Self {
+        self.email = Some(email.into());
+        self
+    }
+
+    pub fn club(mut self, club: impl Into<String>) -> Self {
+        self.club = Some(club.into());
+        self
+    }
+
+    pub fn confirmed(mut self, confirmed: bool) -> Self {
+        self.confirmed = confirmed;
+        self
+    }
+
+    /// Build the member, failing when the id, the name or the email is missing.
+    pub fn build(self) -> Result<UdaMember> {
+        Ok(UdaMember::new(
+            required_field(self.id, "id")?,
+            self.membership_number,
+            required_field(self.first_name, "first_name")?,
+            required_field(self.last_name, "last_name")?,
+            required_field(self.email, "email")?,
+            self.club,
+            self.confirmed,
+        ))
+    }
+}
+
+pub(crate) fn required_field<T>(value: Option<T>, field: &str) -> Result<T> {
+    value.ok_or_else(|| {
+        error!("Can't build member without {field}");
+        MissingMemberField(field.to_owned())
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    mod build {
+        use crate::error::UdaError;
+        use crate::uda_member_builder::UdaMemberBuilder;
+        use uda_dto::uda_member::UdaMember;
+
+        #[test]
+        fn success() {
+            let member = UdaMemberBuilder::new()
+                .id(1)
+                .membership_number("123456")
+                .first_name("Jon")
+                .last_name("Doe")
+                .email("jon.doe@email.com")
+                .club("Le club de test")
+                .confirmed(true)
+                .build()
+                .unwrap();
+
+            assert_eq!(
+                UdaMember::new(
+                    1,
+                    Some("123456".to_owned()),
+                    "Jon".to_owned(),
+                    "Doe".to_owned(),
+                    "jon.doe@email.com".to_owned(),
+                    Some("Le club de test".to_owned()),
+                    true,
+                ),
+                member
+            );
+        }
+
+        #[test]
+        fn default_optional_fields() {
+            let member = UdaMemberBuilder::new()
+                .id(2)
+                .first_name("Jonette")
+                .last_name("Snow")
+                .email("jonette.snow@email.com")
+                .build()
+                .unwrap();
+
+            assert_eq!(&None, member.membership_number());
+            assert_eq!(&None, member.club());
+            assert!(!member.confirmed());
+        }
+
+        #[test]
+        fn fail_when_missing_required_field() {
+            let error = UdaMemberBuilder::new()
+                .id(1)
+                .first_name("Jon")
+                .email("jon.doe@email.com")
+                .build()
+                .unwrap_err();
+
+            assert!(matches!(error, UdaError::MissingMemberField(field) if field == "last_name"));
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::gender::Gender;
+use crate::ids::MemberId;
+use crate::imported_uda_member::parse_birthdate;
+use crate::spreadsheet::HeaderAliases;
+use chrono::NaiveDate;
+use derive_getters::Getters;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// A person registered to a competition, as exported by UDA.
+#[derive(Debug, Getters, Serialize, Deserialize, PartialEq)]
+pub struct UdaRegistrant {
+    #[serde(rename = "ID")]
+    id: MemberId,
+    #[serde(rename = "First Name")]
+    first_name: String,
+    #[serde(rename = "Last Name")]
+    last_name: String,
+    #[serde(rename = "Age")]
+    age: u8,
+    /// [`Gender::Unknown`] when the cell is blank or unrecognized.
+    #[serde(rename = "Gender", default)]
+    gender: Gender,
+    /// Names of the events the registrant entered, empty when the cell is.
+    #[serde(rename = "Events", default, deserialize_with = "deserialize_events")]
+    events: Vec<String>,
+    /// Birthday as exported by UDA, e.g. `01.02.1983`, when the export has the column.
+    #[serde(rename = "Birthday", default)]
+    birthday: Option<String>,
+}
+
+impl UdaRegistrant {
+    pub fn new(
+        id: MemberId,
+        first_name: String,
+        last_name: String,
+        age: u8,
+        gender: Gender,
+        events: Vec<String>,
+    ) -> Self {
+        Self {
+            id,
+            first_name,
+            last_name,
+            age,
+            gender,
+            events,
+            birthday: None,
+        }
+    }
+
+    pub fn with_birthday(mut self, birthday: String) -> Self {
+        self.birthday = Some(birthday);
+        self
+    }
+
+    /// Birthday parsed as a date, or `None` when it is missing or malformed.
+    pub fn birthdate(&self) -> Option<NaiveDate> {
+        self.birthday.as_deref().and_then(parse_birthdate)
+    }
+}
+
+impl HeaderAliases for UdaRegistrant {}
+
+/// UDA lists the events entered in a single cell, separated by commas.
+fn deserialize_events<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let events = Option::<String>::deserialize(deserializer)?;
+    Ok(events.as_deref().map(split_events).unwrap_or_default())
+}
+
+pub(crate) fn split_events(events: &str) -> Vec<String> {
+    events
+        .split(',')
+        .map(str::trim)
+        .filter(|event| !event.is_empty())
+        .map(str::to_owned)
+        .collect()
+}